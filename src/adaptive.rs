@@ -0,0 +1,249 @@
+use serde::Serialize;
+use std::{sync::Mutex, time::Duration};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::debug;
+
+/// Sends allowed in flight when an adaptive run starts
+pub const ADAPTIVE_START: usize = 4;
+
+/// Ceiling of an adaptive run when no `max_concurrency` is configured
+pub const ADAPTIVE_MAX: usize = 1024;
+
+/// Share of failed sends in a window above which the limit backs off
+const MAX_ERROR_RATE: f64 = 0.05;
+
+/// How far a window's median latency may rise above the best one seen before the limit backs off
+const LATENCY_TOLERANCE: f64 = 1.5;
+
+/// Rises smaller than this are jitter, not a spike, however large relative to a fast endpoint
+const MIN_LATENCY_SPIKE: Duration = Duration::from_millis(5);
+
+/// Fewest sends a window is judged on, so a low limit still gets a stable median
+const MIN_WINDOW: usize = 16;
+
+/// What the adaptive limit settled on
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ConcurrencyReport {
+    /// Limit when the run ended
+    pub converged: usize,
+    /// Highest limit reached
+    pub peak: usize,
+    /// Times the limit was halved because latency spiked or sends failed
+    pub backoffs: usize,
+}
+
+/// Concurrency limit tuned AIMD-style from the sends it lets through. The limit doubles while
+/// latency stays flat and errors are rare, then grows by one per window after the first back-off.
+/// A window that fails too often or whose median latency spikes halves it.
+#[derive(Debug)]
+pub struct AdaptiveLimiter {
+    semaphore: Semaphore,
+    max: usize,
+    state: Mutex<LimitState>,
+}
+
+#[derive(Debug)]
+struct LimitState {
+    limit: usize,
+    /// Permits to retire as they are released, after the limit shrank below the sends in flight
+    debt: usize,
+    /// Outcome of each send completed at the current limit, as (latency, accepted)
+    window: Vec<(Duration, bool)>,
+    /// Lowest window median seen, the latency of an unloaded endpoint
+    baseline: Option<Duration>,
+    /// Doubling until the first back-off
+    slow_start: bool,
+    peak: usize,
+    backoffs: usize,
+}
+
+/// A slot in the limit, released when dropped
+pub struct AdaptivePermit<'a> {
+    limiter: &'a AdaptiveLimiter,
+    permit: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for AdaptivePermit<'_> {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().expect("adaptive state lock poisoned");
+        if let Some(permit) = self.permit.take() {
+            if state.debt > 0 {
+                state.debt -= 1;
+                permit.forget();
+            }
+        }
+    }
+}
+
+impl AdaptiveLimiter {
+    /// Starts at `start` sends in flight and never goes above `max`
+    pub fn new(start: usize, max: usize) -> Self {
+        let max = max.max(1);
+        let start = start.clamp(1, max);
+        Self {
+            semaphore: Semaphore::new(start),
+            max,
+            state: Mutex::new(LimitState {
+                limit: start,
+                debt: 0,
+                window: Vec::new(),
+                baseline: None,
+                slow_start: true,
+                peak: start,
+                backoffs: 0,
+            }),
+        }
+    }
+
+    /// Current limit
+    pub fn limit(&self) -> usize {
+        self.state.lock().expect("adaptive state lock poisoned").limit
+    }
+
+    /// Waits for a slot under the current limit
+    pub async fn acquire(&self) -> AdaptivePermit<'_> {
+        let permit = self.semaphore.acquire().await.expect("semaphore is never closed");
+        AdaptivePermit { limiter: self, permit: Some(permit) }
+    }
+
+    /// Feeds back how a send went. Once a window as large as the limit (and at least `MIN_WINDOW`)
+    /// has completed, the limit is adjusted.
+    pub fn record(&self, latency: Duration, accepted: bool) {
+        let mut state = self.state.lock().expect("adaptive state lock poisoned");
+        state.window.push((latency, accepted));
+        if state.window.len() < state.limit.max(MIN_WINDOW) {
+            return;
+        }
+
+        let window = std::mem::take(&mut state.window);
+        let failed = window.iter().filter(|(_, accepted)| !accepted).count();
+        let error_rate = failed as f64 / window.len() as f64;
+        let mut latencies: Vec<Duration> =
+            window.iter().filter(|(_, accepted)| *accepted).map(|(latency, _)| *latency).collect();
+        latencies.sort();
+        let median = latencies.get(latencies.len() / 2).copied();
+
+        let spiked = match (median, state.baseline) {
+            (Some(median), Some(baseline)) => {
+                median.as_secs_f64() > baseline.as_secs_f64() * LATENCY_TOLERANCE
+                    && median.saturating_sub(baseline) >= MIN_LATENCY_SPIKE
+            }
+            _ => false,
+        };
+        if let Some(median) = median {
+            state.baseline = Some(state.baseline.map_or(median, |baseline| baseline.min(median)));
+        }
+
+        let old = state.limit;
+        let new = if error_rate > MAX_ERROR_RATE || spiked {
+            state.slow_start = false;
+            state.backoffs += 1;
+            (old / 2).max(1)
+        } else if state.slow_start {
+            (old * 2).min(self.max)
+        } else {
+            (old + 1).min(self.max)
+        };
+        if new == old {
+            return;
+        }
+        debug!(old, new, error_rate, ?median, baseline = ?state.baseline, "adaptive concurrency adjusted");
+
+        state.limit = new;
+        state.peak = state.peak.max(new);
+        if new > old {
+            // Growing first pays off permits still owed from an earlier back-off
+            let grow = new - old;
+            let repaid = grow.min(state.debt);
+            state.debt -= repaid;
+            self.semaphore.add_permits(grow - repaid);
+        } else {
+            // Free permits go right away, the rest as the sends holding them finish
+            let shrink = old - new;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            state.debt += shrink - forgotten;
+        }
+    }
+
+    /// Where the limit ended up
+    pub fn report(&self) -> ConcurrencyReport {
+        let state = self.state.lock().expect("adaptive state lock poisoned");
+        ConcurrencyReport { converged: state.limit, peak: state.peak, backoffs: state.backoffs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `sends` completed sends with the same outcome
+    fn window(limiter: &AdaptiveLimiter, sends: usize, latency_ms: u64, accepted: bool) {
+        for _ in 0..sends {
+            limiter.record(Duration::from_millis(latency_ms), accepted);
+        }
+    }
+
+    #[test]
+    fn doubles_until_a_spike_then_grows_by_one() {
+        let limiter = AdaptiveLimiter::new(4, 64);
+        // Windows are at least MIN_WINDOW sends, however low the limit
+        window(&limiter, 15, 10, true);
+        assert_eq!(limiter.limit(), 4);
+        window(&limiter, 1, 10, true);
+        assert_eq!(limiter.limit(), 8);
+        window(&limiter, 16, 10, true);
+        window(&limiter, 16, 10, true);
+        assert_eq!(limiter.limit(), 32);
+
+        // Twice the baseline is a spike, and so the end of slow start
+        window(&limiter, 32, 20, true);
+        assert_eq!(limiter.limit(), 16);
+        window(&limiter, 16, 10, true);
+        assert_eq!(limiter.limit(), 17);
+        // A rise within the tolerance is not a spike, but a window failing more than MAX_ERROR_RATE is
+        window(&limiter, 17, 14, true);
+        assert_eq!(limiter.limit(), 18);
+        window(&limiter, 17, 10, true);
+        window(&limiter, 1, 10, false);
+        assert_eq!(limiter.limit(), 9);
+
+        let report = limiter.report();
+        assert_eq!((report.converged, report.peak, report.backoffs), (9, 32, 2));
+
+        // Against a fast endpoint, tripling by less than MIN_LATENCY_SPIKE is jitter
+        let fast = AdaptiveLimiter::new(4, 64);
+        window(&fast, 16, 1, true);
+        window(&fast, 16, 3, true);
+        assert_eq!(fast.limit(), 16);
+    }
+
+    #[tokio::test]
+    async fn permits_held_through_a_back_off_are_retired_as_they_are_released() {
+        let limiter = AdaptiveLimiter::new(16, 64);
+        let mut held = Vec::new();
+        for _ in 0..12 {
+            held.push(limiter.acquire().await);
+        }
+
+        // Halving to 8 forgets the 4 free permits right away and owes the other 4
+        window(&limiter, 16, 10, false);
+        assert_eq!(limiter.limit(), 8);
+        assert_eq!(limiter.semaphore.available_permits(), 0);
+        held.truncate(8);
+        assert_eq!(limiter.semaphore.available_permits(), 0);
+        held.truncate(7);
+        assert_eq!(limiter.semaphore.available_permits(), 1);
+
+        // Halving to 4 forgets the free one and owes 3, then growing to 5 repays one of those
+        window(&limiter, 16, 10, false);
+        assert_eq!(limiter.limit(), 4);
+        window(&limiter, 16, 10, true);
+        assert_eq!(limiter.limit(), 5);
+        assert_eq!(limiter.semaphore.available_permits(), 0);
+
+        // Once everything is released, exactly the limit is left
+        held.clear();
+        assert_eq!(limiter.semaphore.available_permits(), limiter.limit());
+        assert_eq!(limiter.report().backoffs, 2);
+    }
+}
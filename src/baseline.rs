@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{fmt, fs, path::Path};
+
+use crate::report::RunReport;
+
+/// Headline numbers of an earlier run, read from its JSON report
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Baseline {
+    pub send_tps: f64,
+    pub p95_ms: f64,
+}
+
+/// The parts of a JSON report a baseline needs; everything else is ignored
+#[derive(Deserialize)]
+struct BaselineJson {
+    /// Null when the run sent nothing
+    send_tps: Option<f64>,
+    send_latency: BaselineLatency,
+}
+
+#[derive(Deserialize)]
+struct BaselineLatency {
+    p95_ms: f64,
+}
+
+impl Baseline {
+    /// Headline numbers of `report`
+    pub fn from_report(report: &RunReport) -> Self {
+        Self {
+            send_tps: report.send_tps(),
+            p95_ms: report.send_latency.p95.as_secs_f64() * 1000.0,
+        }
+    }
+
+    /// Reads the JSON report saved at `path`; `None` when there is no file yet
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("failed to read baseline file {}", path.display())),
+        };
+        let json: BaselineJson = serde_json::from_str(&contents)
+            .with_context(|| format!("baseline file {} is not a JSON run report", path.display()))?;
+        Ok(Some(Self { send_tps: json.send_tps.unwrap_or(0.0), p95_ms: json.send_latency.p95_ms }))
+    }
+
+    /// Saves `report` as the baseline for the next run, in the same JSON shape as `--output json`
+    pub fn save(report: &RunReport, path: &Path) -> Result<()> {
+        fs::write(path, report.to_json()? + "\n")
+            .with_context(|| format!("failed to write baseline file {}", path.display()))
+    }
+}
+
+/// How a run compares with the baseline
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Regression {
+    pub baseline: Baseline,
+    pub current: Baseline,
+}
+
+impl Regression {
+    pub fn new(baseline: Baseline, report: &RunReport) -> Self {
+        Self { baseline, current: Baseline::from_report(report) }
+    }
+
+    /// Percent change in send TPS; positive is faster
+    pub fn tps_change_pct(&self) -> f64 {
+        percent_change(self.baseline.send_tps, self.current.send_tps)
+    }
+
+    /// Percent change in p95 send latency; positive is slower
+    pub fn p95_change_pct(&self) -> f64 {
+        percent_change(self.baseline.p95_ms, self.current.p95_ms)
+    }
+
+    /// Whether TPS dropped by more than `threshold_pct` percent
+    pub fn regressed(&self, threshold_pct: f64) -> bool {
+        -self.tps_change_pct() > threshold_pct
+    }
+}
+
+impl fmt::Display for Regression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Baseline: send TPS {:.2} -> {:.2} ({:+.1}%), p95 latency {:.1} ms -> {:.1} ms ({:+.1}%)",
+            self.baseline.send_tps,
+            self.current.send_tps,
+            self.tps_change_pct(),
+            self.baseline.p95_ms,
+            self.current.p95_ms,
+            self.p95_change_pct()
+        )
+    }
+}
+
+/// Change from `before` to `after` in percent of `before`; zero when there is nothing to compare against
+fn percent_change(before: f64, after: f64) -> f64 {
+    if before == 0.0 {
+        0.0
+    } else {
+        (after - before) / before * 100.0
+    }
+}
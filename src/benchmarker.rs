@@ -0,0 +1,2528 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::{
+            eip1559::Eip1559TransactionRequest, eip2718::TypedTransaction, eip2930::AccessList,
+            eip2930::Eip2930TransactionRequest,
+        },
+        Address, BlockNumber, Bytes, TransactionRequest, H256, U256,
+    },
+    utils::keccak256,
+};
+use futures::{future::join_all, stream::FuturesUnordered, StreamExt};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{mpsc, oneshot, Mutex, Semaphore},
+    task::{self, JoinSet},
+    time::{sleep, timeout},
+};
+use tracing::{debug, info, info_span, instrument, warn, Instrument};
+
+use crate::{
+    adaptive::{AdaptiveLimiter, ADAPTIVE_MAX, ADAPTIVE_START},
+    bundle::{BundleOutcome, BundleReport},
+    calldata::erc20_transfer,
+    config::{AccessListSource, Config, TxType, WalletSelection, Workload},
+    confirm::{
+        confirm_receipts, sample_mined_nonces, wait_for_depth, wait_for_drain, wait_with_replacement, with_progress,
+        ConfirmProgress, SentTx, Submission,
+    },
+    failures::{FailureHistogram, FailureKind},
+    fees::{ether, gwei, minimum_gas_price, outside_sanity_band, FeeMode, GasStrategy},
+    funding::{fund_wallets, FundingReport},
+    hook::{NoopHook, TxHook},
+    mempool::{txpool_status, MempoolReport},
+    metrics::Metrics,
+    nonce_manager::NonceManager,
+    nonce_state::NonceState,
+    probe::{ProbeProfile, ProbeReport, ProbeWindow},
+    ramp::{RampProfile, RampReport, RampWindow},
+    rate::{BurstPattern, TokenBucket},
+    raw_txs::{NonceRange, RawTxFile},
+    report::{BatchStats, BlockRange, Burst, BurstReport, GasPriceRange, NonceGap, RunReport, TxRecord, WalletBalance},
+    send_error::SendError,
+    stats::{tps_timeseries, LatencyHistogram, LatencyStats},
+    shutdown::Shutdown,
+    transport::FailoverTransport,
+};
+
+/// Gas cost of a plain ETH transfer
+pub const TRANSFER_GAS: u64 = 21000;
+
+/// Intrinsic gas of each address in an access list (EIP-2930)
+const ACCESS_LIST_ADDRESS_GAS: u64 = 2400;
+
+/// Intrinsic gas of each storage key in an access list (EIP-2930)
+const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1900;
+
+/// How long in-flight sends may keep going after a shutdown was requested
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// Senders started by the pipeline when no concurrency limit is set
+const DEFAULT_PIPELINE_CONSUMERS: usize = 64;
+
+/// Signing client every transaction is built and sent through
+pub type Client = SignerMiddleware<Provider<FailoverTransport>, LocalWallet>;
+
+/// Remembers which recipients are contracts and the gas and access list resolved per recipient
+/// and calldata, so a batch of identical calls costs one lookup instead of one per transaction
+#[derive(Debug, Default)]
+struct GasCache {
+    is_contract: HashMap<Address, bool>,
+    estimates: HashMap<(Address, Bytes), u64>,
+    access_lists: HashMap<(Address, Bytes), AccessList>,
+}
+
+/// Connection state and chain context resolved before any transaction is built
+struct RunContext {
+    /// One signing client per configured wallet
+    clients: Vec<Arc<Client>>,
+    /// Next free nonce of each wallet, shared by the generator and "nonce too low" recovery
+    nonces: NonceManager,
+    fee_mode: FeeMode,
+    /// Retries issued for transient send failures
+    retries: AtomicU64,
+    /// Sends given up on because the grace period after a shutdown ran out
+    abandoned: AtomicUsize,
+    chain_id: u64,
+    /// Outcome of the funding phase, when one ran
+    funding: Option<FundingReport>,
+    /// When the workload started, the origin of `completions`
+    started: Instant,
+    /// Offset from `started` at which each accepted send completed
+    completions: std::sync::Mutex<Vec<Duration>>,
+    /// When each accepted send was acknowledged by the node and its gas limit, for confirmation
+    submissions: std::sync::Mutex<HashMap<H256, Submission>>,
+    /// Failed sends by reason
+    failures: std::sync::Mutex<FailureHistogram>,
+    /// Accepted transactions as signed, only kept when replace-by-fee is enabled
+    sent: std::sync::Mutex<Vec<SentTx>>,
+    /// Accepted transactions as broadcast, only kept when reorged-out ones are broadcast again
+    broadcast: std::sync::Mutex<HashMap<H256, Bytes>>,
+    /// Balance of each wallet when the workload started
+    balances: Vec<U256>,
+    /// Upper bound of the fees of accepted sends: gas limit times max per-gas price
+    estimated_gas_cost: std::sync::Mutex<U256>,
+    /// Lowest and highest max per-gas price of accepted sends
+    gas_prices: std::sync::Mutex<Option<GasPriceRange>>,
+    /// Outcome of every send, only kept when per-transaction records are enabled
+    records: std::sync::Mutex<Vec<TxRecord>>,
+    /// Wallet and nonce of each accepted send
+    accepted_nonces: std::sync::Mutex<Vec<(usize, u64)>>,
+    /// Nonces whose transaction failed to build, sign or send
+    failed_nonces: std::sync::Mutex<Vec<FailedNonce>>,
+    /// Self-tuning concurrency limit, used instead of `max_concurrency` in adaptive mode
+    adaptive: Option<AdaptiveLimiter>,
+}
+
+/// A nonce handed out to a transaction that never reached the node
+struct FailedNonce {
+    wallet_idx: usize,
+    /// Position of the transaction in the run, which its content depends on
+    index: u64,
+    nonce: u64,
+    /// The transaction as built, unless building it was what failed
+    tx: Option<TypedTransaction>,
+    /// Why the node refused it, when it was sent and counted in the failure histogram
+    kind: Option<FailureKind>,
+}
+
+/// Failed nonces of one wallet that accepted sends with higher nonces are stuck behind
+struct WalletGaps {
+    wallet_idx: usize,
+    /// In nonce order
+    missing: Vec<FailedNonce>,
+    /// Accepted sends above the lowest missing nonce
+    blocked: usize,
+}
+
+impl RunContext {
+    /// Next unused nonce of every wallet
+    async fn nonce_state(&self) -> NonceState {
+        NonceState::new(self.chain_id, self.nonces.snapshot().await)
+    }
+
+    /// Hands out the next nonce of a wallet
+    async fn next_nonce(&self, wallet_idx: usize) -> u64 {
+        self.nonces.next(self.clients[wallet_idx].address()).await
+    }
+
+    /// Remembers a nonce whose transaction never reached the node
+    fn record_failed_nonce(&self, wallet_idx: usize, index: u64, nonce: u64, tx: Option<TypedTransaction>) {
+        self.failed_nonces
+            .lock()
+            .expect("failed nonces lock poisoned")
+            .push(FailedNonce { wallet_idx, index, nonce, tx, kind: None });
+    }
+
+    /// Takes the failed nonces that sit below an accepted nonce of the same wallet. Failures above
+    /// a wallet's last accepted send leave no gap as long as the next run reuses those nonces, so
+    /// the lowest of them is returned per wallet for the run to start from.
+    fn take_nonce_gaps(&self) -> (Vec<WalletGaps>, BTreeMap<Address, u64>) {
+        let accepted = self.accepted_nonces.lock().expect("accepted nonces lock poisoned");
+        let failed = std::mem::take(&mut *self.failed_nonces.lock().expect("failed nonces lock poisoned"));
+
+        let mut missing_by_wallet: BTreeMap<usize, Vec<FailedNonce>> = BTreeMap::new();
+        for failed in failed {
+            missing_by_wallet.entry(failed.wallet_idx).or_default().push(failed);
+        }
+        let mut gaps = Vec::new();
+        let mut unused = BTreeMap::new();
+        for (wallet_idx, mut missing) in missing_by_wallet {
+            let wallet_accepted: Vec<u64> = accepted
+                .iter()
+                .filter(|(idx, _)| *idx == wallet_idx)
+                .map(|(_, nonce)| *nonce)
+                .collect();
+            let highest_accepted = wallet_accepted.iter().max().copied();
+            let trailing = missing.iter().map(|failed| failed.nonce).filter(|nonce| highest_accepted < Some(*nonce));
+            if let Some(lowest_trailing) = trailing.min() {
+                unused.insert(self.clients[wallet_idx].address(), lowest_trailing);
+            }
+            let Some(highest_accepted) = highest_accepted else {
+                continue;
+            };
+            missing.retain(|failed| failed.nonce < highest_accepted);
+            missing.sort_by_key(|failed| failed.nonce);
+            let Some(lowest) = missing.first().map(|failed| failed.nonce) else {
+                continue;
+            };
+            let blocked = wallet_accepted.iter().filter(|nonce| **nonce > lowest).count();
+            gaps.push(WalletGaps { wallet_idx, missing, blocked });
+        }
+        (gaps, unused)
+    }
+}
+
+/// Transactions built and signed ahead of sending, as (index, wallet, nonce, transaction, raw bytes)
+struct SignedBatch {
+    txs: Vec<(u64, usize, u64, TypedTransaction, Bytes)>,
+    /// Transactions that failed to prepare or sign
+    failed: usize,
+    prep_duration: Duration,
+    sign_duration: Duration,
+}
+
+/// Prepares a batch of transactions and floods them at an RPC endpoint
+#[derive(Debug)]
+pub struct Benchmarker {
+    config: Config,
+    gas_cache: Mutex<GasCache>,
+    shutdown: Arc<Shutdown>,
+    metrics: Arc<Metrics>,
+    /// Seeds every random choice of a run, the value jitter and Poisson arrivals: `config.seed` when
+    /// given, random otherwise, and reported so the run can be repeated
+    seed: u64,
+    /// Applied to every transaction after it is built
+    hook: Arc<dyn TxHook>,
+    /// Sends the node rejected as "already known", counted as accepted
+    already_known: AtomicUsize,
+    /// Next nonce of each wallet when the last run ended, so a repeat run skips nonces that are
+    /// still pending even if the node doesn't count them yet
+    next_nonces: std::sync::Mutex<BTreeMap<Address, u64>>,
+}
+
+impl Benchmarker {
+    /// Create a new benchmarker; nothing touches the network until `run`
+    pub fn new(config: Config) -> Self {
+        Self {
+            seed: config.seed.unwrap_or_else(rand::random),
+            config,
+            gas_cache: Mutex::new(GasCache::default()),
+            shutdown: Arc::new(Shutdown::default()),
+            metrics: Arc::new(Metrics::default()),
+            hook: Arc::new(NoopHook),
+            already_known: AtomicUsize::new(0),
+            next_nonces: std::sync::Mutex::default(),
+        }
+    }
+
+    /// Stops on `shutdown` instead of a handle of its own, so one signal can stop several runs
+    pub fn with_shutdown(mut self, shutdown: Arc<Shutdown>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Runs `hook` on every transaction after it is built and before it is signed
+    pub fn with_hook(mut self, hook: Arc<dyn TxHook>) -> Self {
+        self.hook = hook;
+        self
+    }
+
+    /// The configuration this benchmarker was built with
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Seed behind every random choice, drawn at construction unless the config fixes one
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Handle to stop a run early, e.g. from a Ctrl-C handler; the run still returns a report
+    /// covering whatever completed
+    pub fn shutdown(&self) -> Arc<Shutdown> {
+        self.shutdown.clone()
+    }
+
+    /// Live counters updated as sends complete, e.g. for a metrics endpoint scraped during the run
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Connects to the RPC and runs the configured workload: a fixed batch of `count` transactions
+    /// prepared, signed and then submitted all at once (or streamed through a pipeline), a sustained
+    /// stream for `duration`, or a ramp of paced windows
+    pub async fn run(&self) -> Result<RunReport> {
+        let config = &self.config;
+        let mut ctx = self.connect().await?;
+        // The balances are read before the warm-up, so each wallet's warm-up nonces are counted too
+        let connected_nonces = ctx.nonces.snapshot().await;
+        let warmup_sent = self.warm_up(&ctx).await;
+        let starting_nonces = ctx.nonces.snapshot().await;
+        let mempool_before = match config.inspect_mempool {
+            true => txpool_status(&*ctx.clients[0], "before sending").await,
+            false => None,
+        };
+        // Measure completions from the end of the warm-up
+        ctx.started = Instant::now();
+        let client = &ctx.clients[0];
+
+        // Remember the chain tip so confirmations and chain progress can be measured in blocks
+        let start_block = client.get_block_number().await?.as_u64();
+
+        // Watch wallet nonces from the first send until confirmation ends, for mined TPS
+        let mined_sampler = (config.confirm && !config.dry_run).then(|| {
+            let (stop, stopped) = oneshot::channel();
+            let sampler = tokio::spawn(sample_mined_nonces(ctx.clients.clone(), config.poll_interval, stopped));
+            (stop, sampler)
+        });
+
+        let mut report = match (config.ramp, config.workload) {
+            _ if config.probe.is_some() => self.run_probe(&ctx, config.probe.expect("checked above")).await?,
+            (Some(ramp), _) => self.run_ramp(&ctx, ramp).await?,
+            (None, Workload::Duration(duration)) => {
+                let limiter = config.rate.map(|rate| self.limiter(rate));
+                self.run_sustained(&ctx, duration, limiter.as_ref()).await?
+            }
+            (None, Workload::Count(count)) if config.bundle.is_some() => self.run_bundles(&ctx, count).await?,
+            (None, Workload::Count(count)) if config.pipeline => self.run_pipeline(&ctx, count).await?,
+            (None, Workload::Count(count)) => self.run_batch(&ctx, count).await?,
+        };
+        report.warmup_sent = warmup_sent;
+        report.seed = Some(self.seed);
+        report.total_retries = ctx.retries.load(Ordering::Relaxed);
+        report.already_known = self.already_known.swap(0, Ordering::Relaxed);
+        report.total_abandoned += ctx.abandoned.load(Ordering::Relaxed);
+        report.endpoints = client.provider().as_ref().stats();
+        report.funding = ctx.funding.clone();
+        if let Some(adaptive) = &ctx.adaptive {
+            let concurrency = adaptive.report();
+            info!(
+                "Adaptive concurrency settled at {} in flight (peak {}, {} back-offs)",
+                concurrency.converged, concurrency.peak, concurrency.backoffs
+            );
+            report.concurrency = Some(concurrency);
+        }
+
+        // Every send after a failed nonce stays stuck in the mempool until that nonce is used
+        let (gaps, unused_nonces) = ctx.take_nonce_gaps();
+        for gap in &gaps {
+            let wallet = ctx.clients[gap.wallet_idx].address();
+            let lowest = gap.missing[0].nonce;
+            warn!(
+                "Wallet {:?}: nonce {} failed, {} sent transactions with higher nonces are blocked behind it{}",
+                wallet, lowest, gap.blocked,
+                if config.fill_gaps { "" } else { " (use --fill-gaps to resend the failed ones)" }
+            );
+            report.nonce_gaps.push(NonceGap { wallet, nonce: lowest, missing: gap.missing.len(), blocked: gap.blocked });
+        }
+        if config.fill_gaps && !gaps.is_empty() && !self.shutdown.is_requested() {
+            report.gaps_filled = self.fill_gaps(&ctx, gaps, &mut report.hashes).await;
+            report.total_sent += report.gaps_filled;
+            report.total_failed = report.total_failed.saturating_sub(report.gaps_filled);
+        }
+        report.failures = ctx.failures.lock().expect("failures lock poisoned").clone();
+        // Nothing was broadcast in a dry run, so the next run should start from the same nonces. Bundles
+        // only use their nonces if they land, so after them the next run goes by the chain.
+        if !config.dry_run && config.bundle.is_none() {
+            let mut next_nonces = ctx.nonces.snapshot().await;
+            // Failed sends past a wallet's last accepted one never reached the node, so the next run
+            // has to fill those nonces rather than queue behind them
+            for (address, lowest) in unused_nonces {
+                if let Some(next) = next_nonces.get_mut(&address) {
+                    *next = (*next).min(lowest);
+                }
+            }
+            if let Some(path) = &config.nonce_state_file {
+                NonceState::new(ctx.chain_id, next_nonces.clone()).save(path)?;
+            }
+            *self.next_nonces.lock().expect("next nonces lock poisoned") = next_nonces;
+        }
+        report.timeseries = tps_timeseries(&ctx.completions.lock().expect("completions lock poisoned"));
+        report.records = std::mem::take(&mut *ctx.records.lock().expect("records lock poisoned"));
+        report.interrupted = self.shutdown.is_requested();
+        report.dry_run = config.dry_run;
+        if let Some(before) = mempool_before {
+            let after = txpool_status(&**client, "after sending").await;
+            report.mempool = Some(MempoolReport { before, after });
+        }
+
+        if let Some(cooldown) = config.cooldown.filter(|_| !report.interrupted && !config.dry_run) {
+            let sent = ctx.nonces.snapshot().await;
+            info!("Cooling down for up to {:?} while the mempool drains", cooldown);
+            let drain = wait_for_drain(&ctx.clients, &sent, config.poll_interval, cooldown, &self.shutdown).await;
+            report.interrupted |= drain.interrupted;
+            report.cooldown = Some(drain);
+        }
+
+        // An interrupted run reports what it has right away instead of waiting on receipts,
+        // and a dry run has nothing to wait for
+        if config.confirm && !report.interrupted && !config.dry_run {
+            let submissions = std::mem::take(&mut *ctx.submissions.lock().expect("submissions lock poisoned"));
+            let progress = Arc::new(ConfirmProgress::new(report.hashes.len()));
+            let confirm_started = Instant::now();
+            let confirming = async {
+                match config.rbf {
+                    Some(policy) => {
+                        let sent = std::mem::take(&mut *ctx.sent.lock().expect("sent lock poisoned"));
+                        wait_with_replacement(
+                            &ctx.clients,
+                            sent,
+                            &submissions,
+                            start_block,
+                            policy,
+                            config.eip155,
+                            config.poll_interval,
+                            config.confirm_timeout,
+                            &progress,
+                            &self.shutdown,
+                        )
+                        .await
+                    }
+                    None => {
+                        confirm_receipts(
+                            client.provider(),
+                            &report.hashes,
+                            &submissions,
+                            start_block,
+                            config.poll_interval,
+                            config.confirm_timeout,
+                            &progress,
+                            &self.shutdown,
+                        )
+                        .await
+                    }
+                }
+            };
+            let mut confirmation = if config.progress {
+                with_progress(progress.clone(), confirming).await?
+            } else {
+                confirming.await?
+            };
+            if config.confirmations > 1 {
+                let broadcast = std::mem::take(&mut *ctx.broadcast.lock().expect("broadcast lock poisoned"));
+                let rebroadcast = config.rebroadcast_reorged.then_some(&broadcast);
+                let deadline = confirm_started + config.confirm_timeout;
+                let (depth, poll_interval) = (config.confirmations, config.poll_interval);
+                wait_for_depth(client.provider(), &mut confirmation, depth, rebroadcast, poll_interval, deadline, &self.shutdown)
+                    .await?;
+            }
+            report.interrupted |= confirmation.interrupted;
+            report.confirmation = Some(confirmation);
+        }
+        if let Some((stop, sampler)) = mined_sampler {
+            let _ = stop.send(());
+            report.mined = Some(sampler.await?);
+        }
+        let end_block = client.get_block_number().await?.as_u64();
+        report.blocks = Some(BlockRange { start: start_block, end: end_block });
+
+        // A dry run broadcasts nothing, so it costs nothing
+        if !config.dry_run {
+            report.estimated_gas_cost = *ctx.estimated_gas_cost.lock().expect("gas cost lock poisoned");
+            report.gas_prices = *ctx.gas_prices.lock().expect("gas prices lock poisoned");
+        }
+        let next_nonces = ctx.nonces.snapshot().await;
+        for (wallet_client, before) in ctx.clients.iter().zip(&ctx.balances) {
+            let wallet = wallet_client.address();
+            let after = wallet_client.get_balance(wallet, None).await?;
+            let transactions = next_nonces[&wallet].saturating_sub(starting_nonces[&wallet]);
+            let warmup = starting_nonces[&wallet].saturating_sub(connected_nonces[&wallet]);
+            report.balances.push(WalletBalance { wallet, transactions, warmup, before: *before, after });
+        }
+        self.check_chain_id(&ctx).await;
+
+        Ok(report)
+    }
+
+    /// Connects and builds and signs `count` transactions like a batch run, but sends none of them,
+    /// so they can be submitted later with [`Benchmarker::replay`]
+    #[instrument(name = "generate", skip_all)]
+    pub async fn generate(&self) -> Result<RawTxFile> {
+        let count = self.config.fixed_count().context("generating needs a fixed transaction count")?;
+        let ctx = self.connect().await?;
+        let batch = self.prepare_and_sign(&ctx, count).await?;
+        self.check_chain_id(&ctx).await;
+        if batch.failed > 0 {
+            warn!("{} transactions failed to prepare or sign and are missing from the output", batch.failed);
+        }
+
+        let mut ranges: BTreeMap<usize, (u64, u64)> = BTreeMap::new();
+        for (_, wallet_idx, nonce, _, _) in &batch.txs {
+            let range = ranges.entry(*wallet_idx).or_insert((*nonce, *nonce));
+            range.0 = range.0.min(*nonce);
+            range.1 = range.1.max(*nonce);
+        }
+        let nonces = ranges
+            .into_iter()
+            .map(|(wallet_idx, (first, last))| NonceRange { wallet: ctx.clients[wallet_idx].address(), first, last })
+            .collect();
+        Ok(RawTxFile {
+            chain_id: ctx.chain_id,
+            nonces,
+            txs: batch.txs.into_iter().map(|(_, _, _, _, raw_tx)| raw_tx).collect(),
+        })
+    }
+
+    /// Fire-and-forget: builds and signs `count` transactions like a batch run and hands each send to
+    /// a detached task, honouring the rate and concurrency limits. Nothing is measured and nothing
+    /// is waited for beyond a short grace for the last requests to reach the node; sends still
+    /// unanswered by then are dropped. Returns how many transactions were handed off.
+    #[instrument(name = "submit", skip_all)]
+    pub async fn submit_detached(&self) -> Result<usize> {
+        let config = &self.config;
+        let count = config.fixed_count().context("fire-and-forget submission needs a fixed transaction count")?;
+        let ctx = self.connect().await?;
+        let batch = self.prepare_and_sign(&ctx, count).await?;
+        if batch.failed > 0 {
+            warn!("{} transactions failed to prepare or sign and were not submitted", batch.failed);
+        }
+
+        let semaphore = (config.max_concurrency > 0).then(|| Arc::new(Semaphore::new(config.max_concurrency)));
+        let limiter = config.rate.map(|rate| self.limiter(rate));
+        let mut sends = JoinSet::new();
+        for (i, wallet_idx, nonce, _, raw_tx) in batch.txs {
+            if self.shutdown.is_requested() {
+                break;
+            }
+            let permit = match &semaphore {
+                Some(semaphore) => Some(semaphore.clone().acquire_owned().await.expect("semaphore is never closed")),
+                None => None,
+            };
+            Self::pace(limiter.as_ref()).await;
+            let client = ctx.clients[wallet_idx].clone();
+            sends.spawn(async move {
+                let _permit = permit;
+                if let Err(e) = client.send_raw_transaction(raw_tx).await {
+                    debug!(tx = i + 1, nonce, error = %e, "send failed");
+                }
+            });
+        }
+        let submitted = sends.len();
+
+        // The nonces are handed out whether or not the sends land, so the next run skips past them
+        if let Some(path) = &config.nonce_state_file {
+            ctx.nonce_state().await.save(path)?;
+        }
+        let _ = timeout(SHUTDOWN_GRACE, async { while sends.join_next().await.is_some() {} }).await;
+        Ok(submitted)
+    }
+
+    /// Submits pre-signed raw transactions, e.g. from [`Benchmarker::generate`], honouring the concurrency,
+    /// rate, retry and timeout settings. Nothing is built or signed, so the run measures the network alone.
+    #[instrument(name = "replay", skip_all)]
+    pub async fn replay(&self, raw_txs: Vec<Bytes>) -> Result<RunReport> {
+        let config = &self.config;
+        if config.rpc_urls.is_empty() {
+            bail!("at least one RPC URL is required");
+        }
+        let provider = Provider::new(FailoverTransport::connect(&config.rpc_urls, &config.rpc_headers).await?);
+        let start_block = provider.get_block_number().await?.as_u64();
+        info!("RPC URL: {}", provider.as_ref().current_url());
+        info!("Replaying {} pre-signed transactions...", raw_txs.len());
+        let gas_limits: Vec<Option<U256>> = raw_txs.iter().map(signed_gas_limit).collect();
+
+        let semaphore = (config.max_concurrency > 0).then(|| Semaphore::new(config.max_concurrency));
+        let semaphore = semaphore.as_ref();
+        let limiter = config.rate.map(|rate| self.limiter(rate));
+        let limiter = limiter.as_ref();
+        let retries = AtomicU64::new(0);
+        let provider_ref = &provider;
+        let retries_ref = &retries;
+
+        let started = Instant::now();
+        let mut report = RunReport::default();
+        let results = if config.batch_size > 1 {
+            // Each batch holds one concurrency slot and one token per transaction it carries
+            let batch_latencies = std::sync::Mutex::new(Vec::new());
+            let batch_latencies_ref = &batch_latencies;
+            let batches = raw_txs.chunks(config.batch_size).enumerate().map(|(batch_idx, chunk)| async move {
+                let first = batch_idx * config.batch_size;
+                let _permit = match semaphore {
+                    Some(semaphore) => Some(semaphore.acquire().await.expect("semaphore is never closed")),
+                    None => None,
+                };
+                for _ in chunk {
+                    Self::pace(limiter).await;
+                }
+                if self.shutdown.is_requested() {
+                    return Vec::new();
+                }
+                let batch_start = Instant::now();
+                let results = self.send_batch_with_retry(provider_ref, chunk, retries_ref).await;
+                let latency = batch_start.elapsed();
+                batch_latencies_ref.lock().expect("batch latencies lock poisoned").push(latency);
+                let completed = started.elapsed();
+                match &results {
+                    Ok(results) => {
+                        for result in results {
+                            match result {
+                                Ok(_) => self.metrics.record_sent(latency),
+                                Err(_) => self.metrics.record_failed(),
+                            }
+                        }
+                    }
+                    Err(_) => chunk.iter().for_each(|_| self.metrics.record_failed()),
+                }
+                match results {
+                    Ok(results) => results
+                        .into_iter()
+                        .enumerate()
+                        .map(|(offset, result)| {
+                            let result = result.map(|hash| ((hash, latency), completed)).map_err(anyhow::Error::new);
+                            (first + offset, Some(result))
+                        })
+                        .collect(),
+                    Err(e) => (0..chunk.len())
+                        .map(|offset| (first + offset, Some(Err(anyhow!("batch failed: {:#}", e)))))
+                        .collect(),
+                }
+            });
+            let results: Vec<_> = join_all(batches).instrument(info_span!("send")).await.into_iter().flatten().collect();
+            let batch_latencies = batch_latencies.into_inner().expect("batch latencies lock poisoned");
+            report.rpc_batches = Some(BatchStats {
+                count: batch_latencies.len(),
+                size: config.batch_size,
+                latency: LatencyStats::from_samples(&batch_latencies),
+            });
+            results
+        } else {
+            let futures = raw_txs.iter().cloned().enumerate().map(|(i, raw_tx)| async move {
+                let _permit = match semaphore {
+                    Some(semaphore) => Some(semaphore.acquire().await.expect("semaphore is never closed")),
+                    None => None,
+                };
+                Self::pace(limiter).await;
+                if self.shutdown.is_requested() {
+                    return (i, None);
+                }
+                let result = self.send_with_retry(provider_ref, raw_tx, retries_ref).await;
+                match &result {
+                    Ok((_, latency)) => self.metrics.record_sent(*latency),
+                    Err(_) => self.metrics.record_failed(),
+                }
+                (i, Some(result.map(|sent| (sent, started.elapsed()))))
+            });
+            join_all(futures).instrument(info_span!("send")).await
+        };
+        let send_duration = started.elapsed();
+
+        let mut send_latencies = Vec::new();
+        let mut completions = Vec::new();
+        let mut submissions = HashMap::new();
+        let mut broadcast = HashMap::new();
+        for (i, result) in results {
+            match result {
+                Some(Ok(((hash, latency), completed))) => {
+                    debug!(tx = i + 1, ?hash, "sent");
+                    report.hashes.push(hash);
+                    if config.rebroadcast_reorged {
+                        broadcast.insert(hash, raw_txs[i].clone());
+                    }
+                    submissions.insert(hash, Submission { at: started + completed, gas_limit: gas_limits[i] });
+                    send_latencies.push(latency);
+                    completions.push(completed);
+                }
+                Some(Err(e)) => {
+                    debug!(tx = i + 1, error = %e, "send failed");
+                    report.total_failed += 1;
+                    *report.failures.entry(FailureKind::classify(&e)).or_default() += 1;
+                }
+                None => {}
+            }
+        }
+        info!("All transactions submitted in {:?} ({:.2} tx/s)",
+              send_duration,
+              report.hashes.len() as f64 / send_duration.as_secs_f64());
+
+        report.total_sent = report.hashes.len();
+        report.batch_duration = send_duration;
+        report.send_duration = send_duration;
+        report.send_latency = LatencyStats::from_samples(&send_latencies);
+        report.latency_histogram = LatencyHistogram::from_samples(&send_latencies);
+        report.total_retries = retries.load(Ordering::Relaxed);
+        report.seed = Some(self.seed);
+        report.already_known = self.already_known.swap(0, Ordering::Relaxed);
+        report.endpoints = provider.as_ref().stats();
+        report.timeseries = tps_timeseries(&completions);
+        report.interrupted = self.shutdown.is_requested();
+        report.dry_run = config.dry_run;
+
+        if config.confirm && !report.interrupted && !config.dry_run {
+            let progress = Arc::new(ConfirmProgress::new(report.hashes.len()));
+            let confirm_started = Instant::now();
+            let confirming = confirm_receipts(
+                &provider,
+                &report.hashes,
+                &submissions,
+                start_block,
+                config.poll_interval,
+                config.confirm_timeout,
+                &progress,
+                &self.shutdown,
+            );
+            let mut confirmation = if config.progress {
+                with_progress(progress.clone(), confirming).await?
+            } else {
+                confirming.await?
+            };
+            if config.confirmations > 1 {
+                let deadline = confirm_started + config.confirm_timeout;
+                let rebroadcast = config.rebroadcast_reorged.then_some(&broadcast);
+                let depth = config.confirmations;
+                let poll_interval = config.poll_interval;
+                wait_for_depth(&provider, &mut confirmation, depth, rebroadcast, poll_interval, deadline, &self.shutdown)
+                    .await?;
+            }
+            report.interrupted |= confirmation.interrupted;
+            report.confirmation = Some(confirmation);
+        }
+        let end_block = provider.get_block_number().await?.as_u64();
+        report.blocks = Some(BlockRange { start: start_block, end: end_block });
+
+        Ok(report)
+    }
+
+    /// Builds a client per wallet, reads starting nonces and fees, checks balances and prints the run context
+    #[instrument(name = "setup", skip_all)]
+    async fn connect(&self) -> Result<RunContext> {
+        let config = &self.config;
+        if config.wallets.is_empty() {
+            bail!("at least one wallet is required");
+        }
+
+        // Setup connection, over WebSocket when a URL asks for it and failing over between endpoints
+        if config.rpc_urls.is_empty() {
+            bail!("at least one RPC URL is required");
+        }
+        let provider = Provider::new(FailoverTransport::connect(&config.rpc_urls, &config.rpc_headers).await?);
+
+        // Check the chain answers before doing any work, so a bad endpoint fails with a clear message
+        let chain_id = match config.chain_id {
+            Some(chain_id) => chain_id,
+            None => provider.get_chainid().await.context("health check failed: eth_chainId")?.as_u64(),
+        };
+        let block_number = provider.get_block_number().await.context("health check failed: eth_blockNumber")?;
+        info!("Connected to chain {} at block {}", chain_id, block_number);
+
+        // One signing client per wallet, all sharing the same provider
+        let clients: Vec<Arc<Client>> = config
+            .wallets
+            .iter()
+            .map(|wallet| {
+                let wallet = wallet.clone().with_chain_id(chain_id);
+                Arc::new(SignerMiddleware::new(provider.clone(), wallet))
+            })
+            .collect();
+        let client = clients[0].clone();
+
+        // Top wallets up first so their balances are settled before anything is measured
+        let funding = match &config.funding {
+            Some(funding) => {
+                let addresses: Vec<Address> = clients.iter().map(|c| c.address()).collect();
+                Some(
+                    fund_wallets(&provider, chain_id, funding, &addresses, config.poll_interval, config.confirm_timeout)
+                        .await?,
+                )
+            }
+            None => None,
+        };
+
+        // Balances after funding, for the affordability checks once fees are known
+        match config.at_block {
+            Some(BlockNumber::Number(number)) => info!("Reading starting nonces and balances at block {}", number),
+            Some(block) => info!("Reading starting nonces and balances at the {} block", block),
+            None => {}
+        }
+        let mut balances = Vec::with_capacity(clients.len());
+        for wallet_client in &clients {
+            let balance = wallet_client
+                .get_balance(wallet_client.address(), config.at_block.map(Into::into))
+                .await
+                .with_context(|| format!("health check failed: eth_getBalance of {:?}", wallet_client.address()))?;
+            info!("Wallet {:?} balance: {} ETH", wallet_client.address(), ether(balance));
+            balances.push(balance);
+        }
+
+        // Make necessary RPC calls before the transaction loop; every wallet has its own nonce sequence
+        let addresses: Vec<Address> = clients.iter().map(|c| c.address()).collect();
+        let nonce_block = config.at_block.unwrap_or(config.nonce_source.block_number());
+        let nonces = NonceManager::fetch(&*client, &addresses, nonce_block).await?;
+        // A previous run may have handed out nonces the node doesn't know about yet
+        let nonce_state = config
+            .nonce_state_file
+            .as_deref()
+            .and_then(|path| NonceState::load(path, chain_id));
+        for (address, stored_nonce) in nonce_state.map(|state| state.next_nonces).unwrap_or_default() {
+            nonces.raise(address, stored_nonce).await;
+        }
+        // So can an earlier run of this benchmarker, e.g. the previous round
+        let carried = self.next_nonces.lock().expect("next nonces lock poisoned").clone();
+        for (address, next_nonce) in carried {
+            nonces.raise(address, next_nonce).await;
+        }
+
+        // Resolve the transaction type, probing the latest block for a base fee when asked to
+        let tx_type = config.tx_type.resolve(&*client).await?;
+        if !config.eip155 {
+            if tx_type != TxType::Legacy {
+                bail!("signing without EIP-155 replay protection needs legacy transactions, not {:?}", tx_type);
+            }
+            warn!("Signing without EIP-155 replay protection: the transactions are valid on any chain");
+        }
+        let mut fee_mode = config.gas_strategy.fee_mode(&*client, tx_type == TxType::Eip1559).await?;
+
+        // Clamp runaway fees to the cap, but refuse to run when even the network minimum is above it
+        if let Some(max_gas_price) = config.max_gas_price {
+            if fee_mode.max_fee_per_gas() > max_gas_price {
+                let required = minimum_gas_price(&*client, tx_type == TxType::Eip1559).await?;
+                if required > max_gas_price {
+                    bail!(
+                        "gas price cap of {} gwei is below the current network minimum of {} gwei",
+                        gwei(max_gas_price), gwei(required)
+                    );
+                }
+                let capped = fee_mode.clamp(max_gas_price);
+                info!("Gas price capped at {} gwei (strategy asked for {})", gwei(max_gas_price), fee_mode);
+                fee_mode = capped;
+            }
+        }
+
+        // A fee far from what the node suggests is more likely a multiplier or unit mistake than intent.
+        // A literal gas price is taken as meant, and skipping it keeps `--gas-price` free of a fetch.
+        let sanity_factor = config.gas_sanity_factor.filter(|_| !matches!(config.gas_strategy, GasStrategy::Fixed(_)));
+        if let Some(factor) = sanity_factor {
+            let node_price = client.get_gas_price().await?;
+            // Escalation can take later transactions well past the first one's fee
+            let fee = self.max_fee_per_gas(fee_mode);
+            if outside_sanity_band(fee, node_price, factor) {
+                let problem = format!(
+                    "fee per gas of {} gwei is more than {}x {} the node's gas price of {} gwei",
+                    gwei(fee), factor, if fee > node_price { "above" } else { "below" }, gwei(node_price)
+                );
+                if !config.force {
+                    bail!("{}; pass --force to send anyway or --no-gas-sanity-check to skip the check", problem);
+                }
+                warn!("{}, sending anyway because of --force", problem);
+            }
+        }
+
+        // Make sure every wallet can pay for its share of transfers plus worst-case fees before sending anything.
+        // Sustained, ramp and probe runs have no fixed count, so there is nothing to check up front, and in
+        // ERC-20 mode the value is a token amount rather than ETH.
+        let shares = config.fixed_count().map(|total| self.wallet_shares(total));
+        if let Some(shares) = shares.as_ref().filter(|_| !config.value.is_zero() && config.erc20.is_none()) {
+            let gas_limit = config.gas_limit.unwrap_or(TRANSFER_GAS);
+            for (wallet_idx, wallet_client) in clients.iter().enumerate() {
+                let wallet_count = shares[wallet_idx];
+                let balance = balances[wallet_idx];
+                let count = U256::from(wallet_count);
+                // With jitter every value is assumed to come out at its maximum
+                let value = self.max_transaction_value();
+                let required = value * count + self.max_fee_per_gas(fee_mode) * gas_limit * count;
+                if balance < required {
+                    bail!(
+                        "insufficient balance: wallet {} has {} wei but {} transactions of up to {} wei need up to {} wei including fees",
+                        wallet_client.address(), balance, wallet_count, value, required
+                    );
+                }
+            }
+        }
+
+        // Fees alone can drain a wallet even when no value is attached, but estimated gas may be lower,
+        // so this only warns
+        if let Some(shares) = &shares {
+            for (wallet_idx, wallet_client) in clients.iter().enumerate() {
+                let count = U256::from(shares[wallet_idx]);
+                let fees = count * TRANSFER_GAS * self.max_fee_per_gas(fee_mode);
+                if balances[wallet_idx] < fees {
+                    warn!(
+                        "Wallet {:?} holds {} ETH, less than the {} ETH that {} transfers could cost in fees",
+                        wallet_client.address(), ether(balances[wallet_idx]), ether(fees), count
+                    );
+                }
+            }
+        }
+
+        // Display info
+        info!("RPC URL: {}", provider.as_ref().current_url());
+        if config.rpc_urls.len() > 1 {
+            info!("Failover endpoints: {}", config.rpc_urls.len());
+        }
+        for address in &addresses {
+            info!("Wallet address: {} (starting nonce: {})", address, nonces.peek(*address).await);
+        }
+        match config.recipients.as_slice() {
+            [] => info!("Recipient: self"),
+            [recipient] => info!("Recipient: {}", recipient),
+            many => info!("Recipients: {} addresses (round-robin)", many.len()),
+        }
+        info!("Gas strategy: {}", config.gas_strategy);
+        info!("Using {}", fee_mode);
+        let eth_value = match config.erc20 {
+            Some(token) => {
+                info!("ERC-20 token: {} (transfer amount: {})", token, config.value);
+                U256::zero()
+            }
+            None => config.value,
+        };
+        match config.gas_limit {
+            Some(gas_limit) => info!("Value: {} wei, gas limit: {}", eth_value, gas_limit),
+            None => info!("Value: {} wei, gas limit: auto", eth_value),
+        }
+        info!("Transaction type: {:?}{}", tx_type, if config.tx_type == TxType::Auto { " (auto-detected)" } else { "" });
+
+        Ok(RunContext {
+            clients,
+            nonces,
+            fee_mode,
+            retries: AtomicU64::new(0),
+            abandoned: AtomicUsize::new(0),
+            chain_id,
+            funding,
+            started: Instant::now(),
+            completions: std::sync::Mutex::new(Vec::new()),
+            submissions: std::sync::Mutex::new(HashMap::new()),
+            failures: std::sync::Mutex::new(FailureHistogram::new()),
+            sent: std::sync::Mutex::new(Vec::new()),
+            broadcast: std::sync::Mutex::new(HashMap::new()),
+            balances,
+            estimated_gas_cost: std::sync::Mutex::new(U256::zero()),
+            gas_prices: std::sync::Mutex::new(None),
+            records: std::sync::Mutex::new(Vec::new()),
+            accepted_nonces: std::sync::Mutex::new(Vec::new()),
+            failed_nonces: std::sync::Mutex::new(Vec::new()),
+            adaptive: config.adaptive_concurrency.then(|| {
+                let max = if config.max_concurrency > 0 { config.max_concurrency } else { ADAPTIVE_MAX };
+                info!("Adaptive concurrency: starting at {} in flight, up to {}", ADAPTIVE_START, max);
+                AdaptiveLimiter::new(ADAPTIVE_START, max)
+            }),
+        })
+    }
+
+    /// Warns when the chain id given in the config isn't the endpoint's. It is checked only once the
+    /// work is done, since skipping the `eth_chainId` round-trip up front is the point of setting it.
+    async fn check_chain_id(&self, ctx: &RunContext) {
+        let Some(expected) = self.config.chain_id else {
+            return;
+        };
+        match ctx.clients[0].provider().get_chainid().await {
+            Ok(actual) if actual.as_u64() != expected => warn!(
+                "Transactions were signed for chain {} but the endpoint reports chain {}; it will reject them",
+                expected, actual
+            ),
+            Ok(_) => {}
+            Err(e) => debug!("Could not verify the chain id: {}", e),
+        }
+    }
+
+    /// Sends `warmup` transactions one at a time so connections are established before measuring.
+    /// They advance the nonces but are left out of every metric. Returns how many were accepted.
+    #[instrument(name = "warmup", skip_all)]
+    async fn warm_up(&self, ctx: &RunContext) -> usize {
+        let config = &self.config;
+        if config.warmup == 0 {
+            return 0;
+        }
+        info!("Sending {} warm-up transactions...", config.warmup);
+        let mut sent = 0;
+        for i in 0..config.warmup {
+            if self.shutdown.is_requested() {
+                break;
+            }
+            let wallet_idx = (i % ctx.clients.len() as u64) as usize;
+            let client = &ctx.clients[wallet_idx];
+            let nonce = ctx.next_nonce(wallet_idx).await;
+            let result = async {
+                let tx = self.prepare_transaction(client, i, nonce, ctx.fee_mode).await?;
+                let raw_tx = self.sign_transaction(client, tx.clone()).await?;
+                self.send_with_nonce_recovery(ctx, i, wallet_idx, tx, raw_tx).await
+            }
+            .await;
+            match result {
+                Ok((hash, _)) => {
+                    debug!(tx = i + 1, nonce, ?hash, "warm-up sent");
+                    sent += 1;
+                }
+                Err(e) => warn!(tx = i + 1, error = %e, "warm-up send failed"),
+            }
+        }
+        // Retries during the warm-up shouldn't count against the measured run, and its transactions
+        // shouldn't be replaced or rebroadcast while confirming it
+        ctx.retries.store(0, Ordering::Relaxed);
+        ctx.sent.lock().expect("sent lock poisoned").clear();
+        ctx.broadcast.lock().expect("broadcast lock poisoned").clear();
+        info!("Warm-up done: {} of {} sent", sent, config.warmup);
+        sent
+    }
+
+    /// Builds `num_transactions` transactions spread over the wallets and signs them all up front
+    async fn prepare_and_sign(&self, ctx: &RunContext, num_transactions: u64) -> Result<SignedBatch> {
+        let clients = &ctx.clients;
+
+        info!("Preparing {} transactions...", num_transactions);
+
+        let mut prepared_txs = Vec::with_capacity(num_transactions as usize);
+        let mut total_failed = 0;
+
+        // First, create all transactions (without signing)
+        let prep_start = Instant::now();
+        async {
+            for i in 0..num_transactions {
+                if self.shutdown.is_requested() {
+                    break;
+                }
+                let wallet_idx = self.wallet_for(i);
+                let nonce = ctx.next_nonce(wallet_idx).await;
+
+                match self.prepare_transaction(&clients[wallet_idx], i, nonce, ctx.fee_mode).await {
+                    Ok(tx) => {
+                        debug!(tx = i + 1, nonce, "prepared");
+                        prepared_txs.push((i, wallet_idx, nonce, tx));
+                    },
+                    Err(e) => {
+                        warn!(tx = i + 1, error = %e, "failed to prepare");
+                        ctx.record_failed_nonce(wallet_idx, i, nonce, None);
+                        total_failed += 1;
+                    }
+                }
+            }
+        }
+        .instrument(info_span!("prepare", count = num_transactions))
+        .await;
+        let prep_duration = prep_start.elapsed();
+        info!("All transactions prepared in {:?} ({:.2} tx/s)",
+              prep_duration,
+              prepared_txs.len() as f64 / prep_duration.as_secs_f64());
+
+        // Sign everything up front so local crypto doesn't count against send throughput.
+        // Signing is CPU-bound, so it is spread over all cores off the async runtime.
+        info!("Signing {} transactions on {} threads...", prepared_txs.len(), rayon::current_num_threads());
+        let sign_start = Instant::now();
+        let wallets: Vec<LocalWallet> = clients.iter().map(|client| client.signer().clone()).collect();
+        let shutdown = self.shutdown.clone();
+        let eip155 = self.config.eip155;
+        let signed = task::spawn_blocking(move || {
+            prepared_txs
+                .into_par_iter()
+                .filter(|_| !shutdown.is_requested())
+                .map(|(i, wallet_idx, nonce, tx)| {
+                    let signed = sign_with(&wallets[wallet_idx], &tx, eip155);
+                    (i, wallet_idx, nonce, tx, signed)
+                })
+                .collect::<Vec<_>>()
+        })
+        .instrument(info_span!("sign"))
+        .await?;
+        let mut txs = Vec::with_capacity(signed.len());
+        for (i, wallet_idx, nonce, tx, signed) in signed {
+            match signed {
+                Ok(raw_tx) => txs.push((i, wallet_idx, nonce, tx, raw_tx)),
+                Err(e) => {
+                    warn!(tx = i + 1, error = %e, "failed to sign");
+                    ctx.record_failed_nonce(wallet_idx, i, nonce, Some(tx));
+                    total_failed += 1;
+                }
+            }
+        }
+        let sign_duration = sign_start.elapsed();
+        info!("All transactions signed in {:?} ({:.2} tx/s)",
+              sign_duration,
+              txs.len() as f64 / sign_duration.as_secs_f64());
+
+        Ok(SignedBatch { txs, failed: total_failed, prep_duration, sign_duration })
+    }
+
+
+    /// Prepares and signs all `count` transactions, then submits them in parallel
+    #[instrument(name = "batch", skip_all)]
+    async fn run_batch(&self, ctx: &RunContext, count: u64) -> Result<RunReport> {
+        let config = &self.config;
+
+        // Start timer for entire batch
+        let batch_start_time = Instant::now();
+
+        let SignedBatch { txs: signed_txs, failed: mut total_failed, prep_duration, sign_duration } =
+            self.prepare_and_sign(ctx, count).await?;
+        let total_signed = signed_txs.len();
+
+        // Now send all transactions in parallel without awaiting each one
+        match (config.max_concurrency, config.rate) {
+            (0, None) => info!("Submitting all transactions in parallel..."),
+            (0, Some(rate)) => info!("Submitting all transactions paced at {} tx/s...", rate),
+            (max, None) => info!("Submitting all transactions in parallel (max {} in flight)...", max),
+            (max, Some(rate)) => info!("Submitting all transactions paced at {} tx/s (max {} in flight)...", rate, max),
+        }
+        // Bound the number of in-flight sends when a concurrency limit is configured; in adaptive
+        // mode it is only the ceiling of the self-tuning limit
+        let adaptive = ctx.adaptive.as_ref();
+        let semaphore = (config.max_concurrency > 0 && adaptive.is_none()).then(|| Semaphore::new(config.max_concurrency));
+        let semaphore = semaphore.as_ref();
+        // Pace sends when a target rate is configured
+        let limiter = config.rate.map(|rate| self.limiter(rate));
+        let limiter = limiter.as_ref();
+
+        let mut futures = Vec::with_capacity(signed_txs.len());
+        let mut sent_txs = Vec::with_capacity(signed_txs.len());
+        let mut send_latencies = Vec::with_capacity(signed_txs.len());
+
+        // Create futures for all the transactions
+        for (i, wallet_idx, nonce, tx, raw_tx) in signed_txs {
+            futures.push(async move {
+                let _permit = match semaphore {
+                    Some(semaphore) => Some(semaphore.acquire().await.expect("semaphore is never closed")),
+                    None => None,
+                };
+                let _adaptive_permit = match adaptive {
+                    Some(adaptive) => Some(adaptive.acquire().await),
+                    None => None,
+                };
+                Self::pace(limiter).await;
+                let result = self.send_unless_shutdown(ctx, i, wallet_idx, tx, raw_tx).await;
+                (i, nonce, result)
+            });
+        }
+
+        // Execute all sends in parallel, or burst by burst with a pause in between
+        let sending_start = Instant::now();
+        let mut bursts = None;
+        let results = match config.burst {
+            Some(pattern) => {
+                let (results, report) = self.send_bursts(futures, pattern).instrument(info_span!("send")).await;
+                bursts = Some(report);
+                results
+            }
+            None => join_all(futures).instrument(info_span!("send")).await,
+        };
+        let sending_duration = sending_start.elapsed();
+
+        // Process results; sends skipped or abandoned on shutdown have none
+        for (i, nonce, result) in results {
+            let Some(result) = result else {
+                continue;
+            };
+            match result {
+                Ok((hash, send_duration)) => {
+                    debug!(tx = i + 1, nonce, ?hash, "sent");
+                    sent_txs.push(hash);
+                    send_latencies.push(send_duration);
+                },
+                Err(e) => {
+                    debug!(tx = i + 1, nonce, error = %e, "send failed");
+                    total_failed += 1;
+                }
+            }
+        }
+
+        info!("All transactions submitted in {:?} ({:.2} tx/s)",
+              sending_duration,
+              sent_txs.len() as f64 / sending_duration.as_secs_f64());
+
+        Ok(RunReport {
+            total_sent: sent_txs.len(),
+            total_failed,
+            batch_duration: batch_start_time.elapsed(),
+            prep_duration,
+            sign_duration,
+            total_signed,
+            send_duration: sending_duration,
+            send_latency: LatencyStats::from_samples(&send_latencies),
+            latency_histogram: LatencyHistogram::from_samples(&send_latencies),
+            hashes: sent_txs,
+            bursts,
+            ..Default::default()
+        })
+    }
+
+    /// Prepares and signs all `count` transactions like a batch run, then submits them to the bundle
+    /// relay in consecutive groups, every bundle targeting the same block
+    #[instrument(name = "bundles", skip_all)]
+    async fn run_bundles(&self, ctx: &RunContext, count: u64) -> Result<RunReport> {
+        let config = &self.config;
+        let relay = config.bundle.as_ref().expect("only called for bundle runs");
+        let batch_start_time = Instant::now();
+
+        let SignedBatch { txs: signed_txs, failed: mut total_failed, prep_duration, sign_duration } =
+            self.prepare_and_sign(ctx, count).await?;
+        let total_signed = signed_txs.len();
+        let block = match relay.block {
+            Some(block) => block,
+            None => ctx.clients[0].get_block_number().await?.as_u64() + 1,
+        };
+        info!(
+            "Submitting {} transactions to {} in bundles of {} for block {}...",
+            total_signed, relay.url, relay.size, block
+        );
+
+        let http = reqwest::Client::new();
+        let semaphore = (config.max_concurrency > 0).then(|| Semaphore::new(config.max_concurrency));
+        let semaphore = semaphore.as_ref();
+        let http = &http;
+        // Signing kept the transactions in index order, so each wallet's nonces ascend within a bundle
+        let sends = signed_txs.chunks(relay.size).map(|chunk| async move {
+            let _permit = match semaphore {
+                Some(semaphore) => Some(semaphore.acquire().await.expect("semaphore is never closed")),
+                None => None,
+            };
+            if self.shutdown.is_requested() {
+                return None;
+            }
+            let raw_txs: Vec<Bytes> = chunk.iter().map(|(.., raw_tx)| raw_tx.clone()).collect();
+            let send_start = Instant::now();
+            let result = relay.send(http, &raw_txs, block).await;
+            let latency = send_start.elapsed();
+            let outcome = BundleOutcome {
+                transactions: chunk.len(),
+                bundle_hash: result.as_ref().ok().copied().flatten(),
+                error: result.as_ref().err().map(|e| format!("{:#}", e)),
+                latency,
+            };
+            let mut hashes = Vec::with_capacity(chunk.len());
+            match &result {
+                Ok(_) => {
+                    let completed = ctx.started.elapsed();
+                    for (_, _, _, tx, raw_tx) in chunk {
+                        let hash = H256::from(keccak256(raw_tx));
+                        let gas_limit = tx.gas().copied();
+                        let gas_price = tx.gas_price().unwrap_or_default();
+                        let submission = Submission { at: Instant::now(), gas_limit };
+                        ctx.submissions.lock().expect("submissions lock poisoned").insert(hash, submission);
+                        ctx.completions.lock().expect("completions lock poisoned").push(completed);
+                        let max_cost = gas_limit.unwrap_or_default() * gas_price;
+                        *ctx.estimated_gas_cost.lock().expect("gas cost lock poisoned") += max_cost;
+                        let mut gas_prices = ctx.gas_prices.lock().expect("gas prices lock poisoned");
+                        *gas_prices = Some(GasPriceRange::include(*gas_prices, gas_price));
+                        self.metrics.record_sent(latency);
+                        hashes.push(hash);
+                    }
+                }
+                Err(e) => {
+                    warn!(error = %format!("{:#}", e), "bundle rejected");
+                    chunk.iter().for_each(|_| self.metrics.record_failed());
+                }
+            }
+            Some((outcome, hashes))
+        });
+        let sending_start = Instant::now();
+        let results = join_all(sends).instrument(info_span!("send")).await;
+        let sending_duration = sending_start.elapsed();
+
+        let mut outcomes = Vec::with_capacity(results.len());
+        let mut hashes = Vec::new();
+        let mut send_latencies = Vec::new();
+        for (outcome, accepted) in results.into_iter().flatten() {
+            if outcome.accepted() {
+                send_latencies.extend(std::iter::repeat_n(outcome.latency, accepted.len()));
+                hashes.extend(accepted);
+            } else {
+                total_failed += outcome.transactions;
+            }
+            outcomes.push(outcome);
+        }
+        let bundles = BundleReport { block, size: relay.size, bundles: outcomes };
+        info!(
+            "{} of {} bundles accepted in {:?}",
+            bundles.accepted(), bundles.bundles.len(), sending_duration
+        );
+
+        Ok(RunReport {
+            total_sent: hashes.len(),
+            total_failed,
+            batch_duration: batch_start_time.elapsed(),
+            prep_duration,
+            sign_duration,
+            total_signed,
+            send_duration: sending_duration,
+            send_latency: LatencyStats::from_samples(&send_latencies),
+            latency_histogram: LatencyHistogram::from_samples(&send_latencies),
+            hashes,
+            bundles: Some(bundles),
+            ..Default::default()
+        })
+    }
+
+    /// Runs `sends` in bursts of `pattern.size`, each as fast as possible, pausing `pattern.gap`
+    /// between them. Returns every send's result in order, plus how each burst went.
+    async fn send_bursts<F, T>(&self, sends: Vec<F>, pattern: BurstPattern) -> (Vec<(u64, u64, Option<Result<T>>)>, BurstReport)
+    where
+        F: Future<Output = (u64, u64, Option<Result<T>>)>,
+    {
+        let mut results = Vec::with_capacity(sends.len());
+        let mut report = BurstReport { size: pattern.size, gap: pattern.gap, bursts: Vec::new() };
+        let mut sends = sends.into_iter().peekable();
+        while sends.peek().is_some() {
+            if self.shutdown.is_requested() {
+                break;
+            }
+            let burst_start = Instant::now();
+            let burst = join_all(sends.by_ref().take(pattern.size)).await;
+            let duration = burst_start.elapsed();
+            let sent = burst.iter().filter(|(_, _, result)| matches!(result, Some(Ok(_)))).count();
+            let failed = burst.iter().filter(|(_, _, result)| matches!(result, Some(Err(_)))).count();
+            debug!(burst = report.bursts.len() + 1, sent, failed, ?duration, "burst done");
+            report.bursts.push(Burst { sent, failed, duration });
+            results.extend(burst);
+
+            if sends.peek().is_some() {
+                tokio::select! {
+                    _ = sleep(pattern.gap) => {}
+                    _ = self.shutdown.wait() => break,
+                }
+            }
+        }
+        (results, report)
+    }
+
+    /// Streams `count` transactions through a bounded channel: a producer builds and signs them while
+    /// consumers send, so sending starts right away and memory stays flat however large `count` is
+    #[instrument(name = "pipeline", skip_all)]
+    async fn run_pipeline(&self, ctx: &RunContext, count: u64) -> Result<RunReport> {
+        let config = &self.config;
+        let clients = &ctx.clients;
+        // In adaptive mode every possible slot gets a sender, and the limit decides how many are busy
+        let adaptive = ctx.adaptive.as_ref();
+        let consumers = match (config.max_concurrency, adaptive) {
+            (0, Some(_)) => ADAPTIVE_MAX,
+            (0, None) => DEFAULT_PIPELINE_CONSUMERS,
+            (max, _) => max,
+        };
+
+        info!(
+            "Streaming {} transactions through a pipeline ({} buffered, {} senders)...",
+            count, config.pipeline_buffer, consumers
+        );
+
+        let (sender, receiver) = mpsc::channel(config.pipeline_buffer.max(1));
+        let receiver = Mutex::new(receiver);
+        let limiter = config.rate.map(|rate| self.limiter(rate));
+        let limiter = limiter.as_ref();
+        let start = Instant::now();
+
+        // Builds and signs transactions in nonce order; blocks whenever the buffer is full
+        let producer = async move {
+            let mut prep_duration = Duration::ZERO;
+            let mut sign_duration = Duration::ZERO;
+            let mut failed = 0;
+            for i in 0..count {
+                if self.shutdown.is_requested() {
+                    break;
+                }
+                let wallet_idx = self.wallet_for(i);
+                let nonce = ctx.next_nonce(wallet_idx).await;
+
+                let prep_start = Instant::now();
+                let tx = match self.prepare_transaction(&clients[wallet_idx], i, nonce, ctx.fee_mode).await {
+                    Ok(tx) => tx,
+                    Err(e) => {
+                        warn!(tx = i + 1, error = %e, "failed to prepare");
+                        ctx.record_failed_nonce(wallet_idx, i, nonce, None);
+                        failed += 1;
+                        continue;
+                    }
+                };
+                prep_duration += prep_start.elapsed();
+
+                let sign_start = Instant::now();
+                let raw_tx = match self.sign_transaction(&clients[wallet_idx], tx.clone()).await {
+                    Ok(raw_tx) => raw_tx,
+                    Err(e) => {
+                        warn!(tx = i + 1, error = %e, "failed to sign");
+                        ctx.record_failed_nonce(wallet_idx, i, nonce, Some(tx));
+                        failed += 1;
+                        continue;
+                    }
+                };
+                sign_duration += sign_start.elapsed();
+
+                if sender.send((i, wallet_idx, nonce, tx, raw_tx)).await.is_err() {
+                    break;
+                }
+            }
+            // Dropping the sender lets the consumers drain the buffer and finish
+            drop(sender);
+            (prep_duration, sign_duration, failed)
+        };
+
+        // Each consumer pulls the next signed transaction and sends it
+        let receiver = &receiver;
+        let consumer = || async move {
+            let mut hashes = Vec::new();
+            let mut latencies = Vec::new();
+            let mut failed = 0;
+            loop {
+                let next = receiver.lock().await.recv().await;
+                let Some((i, wallet_idx, nonce, tx, raw_tx)) = next else {
+                    break;
+                };
+                let _adaptive_permit = match adaptive {
+                    Some(adaptive) => Some(adaptive.acquire().await),
+                    None => None,
+                };
+                Self::pace(limiter).await;
+                let Some(result) = self.send_unless_shutdown(ctx, i, wallet_idx, tx, raw_tx).await else {
+                    continue;
+                };
+                match result {
+                    Ok((hash, send_duration)) => {
+                        debug!(tx = i + 1, nonce, ?hash, "sent");
+                        hashes.push(hash);
+                        latencies.push(send_duration);
+                    },
+                    Err(e) => {
+                        debug!(tx = i + 1, nonce, error = %e, "send failed");
+                        failed += 1;
+                    }
+                }
+            }
+            (hashes, latencies, failed)
+        };
+
+        let ((prep_duration, sign_duration, mut total_failed), results) =
+            tokio::join!(producer, join_all((0..consumers).map(|_| consumer())));
+        let send_duration = start.elapsed();
+
+        let mut sent_txs = Vec::new();
+        let mut send_latencies = Vec::new();
+        for (hashes, latencies, failed) in results {
+            sent_txs.extend(hashes);
+            send_latencies.extend(latencies);
+            total_failed += failed;
+        }
+
+        info!("All transactions streamed in {:?} ({:.2} tx/s)",
+              send_duration,
+              sent_txs.len() as f64 / send_duration.as_secs_f64());
+
+        Ok(RunReport {
+            total_sent: sent_txs.len(),
+            total_failed,
+            batch_duration: send_duration,
+            prep_duration,
+            sign_duration,
+            send_duration,
+            send_latency: LatencyStats::from_samples(&send_latencies),
+            latency_histogram: LatencyHistogram::from_samples(&send_latencies),
+            hashes: sent_txs,
+            ..Default::default()
+        })
+    }
+
+    /// Runs one sustained window per target rate of `ramp`, until the error rate crosses the
+    /// threshold or the last rate is done, and reports each window
+    #[instrument(name = "ramp", skip_all)]
+    async fn run_ramp(&self, ctx: &RunContext, ramp: RampProfile) -> Result<RunReport> {
+        let mut report = RunReport::default();
+        let mut ramp_report = RampReport::default();
+
+        for target_tps in ramp.rates() {
+            info!("Ramp window at {:.2} tx/s", target_tps);
+            let limiter = self.limiter(target_tps);
+            let window = self.run_sustained(ctx, ramp.window, Some(&limiter)).await?;
+
+            let attempted = window.total_attempted();
+            let success_rate = if attempted == 0 {
+                0.0
+            } else {
+                window.total_sent as f64 / attempted as f64
+            };
+            let ramp_window = RampWindow {
+                target_tps,
+                achieved_tps: window.send_tps(),
+                sent: window.total_sent,
+                failed: window.total_failed,
+                success_rate,
+                send_latency: window.send_latency,
+            };
+            let exceeded = ramp_window.error_rate() > ramp.fail_threshold;
+            if !exceeded {
+                ramp_report.last_sustainable_tps = Some(target_tps);
+            }
+            ramp_report.windows.push(ramp_window);
+            report.absorb_window(window);
+
+            if self.shutdown.is_requested() {
+                break;
+            }
+            if exceeded {
+                warn!("Error rate crossed {:.1}% at {:.2} tx/s, stopping ramp", ramp.fail_threshold * 100.0, target_tps);
+                break;
+            }
+        }
+
+        // Overall latency is only kept per window; report the worst window's tail
+        report.send_latency = ramp_report
+            .windows
+            .iter()
+            .map(|window| window.send_latency)
+            .max_by_key(|latency| latency.p99)
+            .unwrap_or_default();
+        report.ramp = Some(ramp_report);
+        Ok(report)
+    }
+
+    /// Binary-searches the paced rate between `probe.min` and `probe.max`: each candidate runs for one
+    /// sustained window, and the bound it lands on moves depending on whether the window stayed within
+    /// the error and latency limits. Stops on convergence or after `probe.max_windows` windows.
+    #[instrument(name = "probe", skip_all)]
+    async fn run_probe(&self, ctx: &RunContext, probe: ProbeProfile) -> Result<RunReport> {
+        let mut report = RunReport::default();
+        let mut probe_report = ProbeReport::default();
+
+        while probe_report.windows.len() < probe.max_windows {
+            let low = probe_report.sustainable_tps.unwrap_or(probe.min);
+            let high = probe_report.failed_tps.unwrap_or(probe.max);
+            if probe.converged(low, high) {
+                probe_report.converged = true;
+                break;
+            }
+            let target_tps = (low + high) / 2.0;
+            info!("Probe window at {:.2} tx/s (between {:.2} and {:.2})", target_tps, low, high);
+            let limiter = self.limiter(target_tps);
+            let window = self.run_sustained(ctx, probe.window, Some(&limiter)).await?;
+
+            let attempted = window.total_attempted();
+            let mut probe_window = ProbeWindow {
+                target_tps,
+                achieved_tps: window.send_tps(),
+                sent: window.total_sent,
+                failed: window.total_failed,
+                error_rate: if attempted == 0 { 0.0 } else { window.total_failed as f64 / attempted as f64 },
+                send_latency: window.send_latency,
+                sustained: false,
+            };
+            probe_window.judge(&probe);
+            if probe_window.sustained {
+                probe_report.sustainable_tps = Some(target_tps);
+            } else {
+                probe_report.failed_tps = Some(target_tps);
+            }
+            probe_report.windows.push(probe_window);
+            report.absorb_window(window);
+
+            if self.shutdown.is_requested() {
+                break;
+            }
+        }
+
+        report.send_latency = probe_report
+            .windows
+            .iter()
+            .map(|window| window.send_latency)
+            .max_by_key(|latency| latency.p99)
+            .unwrap_or_default();
+        report.probe = Some(probe_report);
+        Ok(report)
+    }
+
+    /// Keeps building, signing and sending transactions until `duration` has elapsed, with nonces
+    /// advancing continuously. Sends still in flight at the deadline are abandoned rather than awaited.
+    /// When a `limiter` is given, each new transaction waits for a token first.
+    #[instrument(name = "sustained", skip_all)]
+    async fn run_sustained(&self, ctx: &RunContext, duration: Duration, limiter: Option<&TokenBucket>) -> Result<RunReport> {
+        let config = &self.config;
+        let clients = &ctx.clients;
+
+        info!("Sending transactions continuously for {:?}...", duration);
+
+        let mut in_flight = FuturesUnordered::new();
+        let mut sent_txs = Vec::new();
+        let mut send_latencies = Vec::new();
+        let mut total_failed = 0;
+        let mut prep_duration = Duration::ZERO;
+        let mut sign_duration = Duration::ZERO;
+        let mut index = 0u64;
+
+        let start = Instant::now();
+        let deadline = sleep(duration);
+        tokio::pin!(deadline);
+
+        loop {
+            let has_capacity = match &ctx.adaptive {
+                Some(adaptive) => in_flight.len() < adaptive.limit(),
+                None => config.max_concurrency == 0 || in_flight.len() < config.max_concurrency,
+            };
+            tokio::select! {
+                // Handle the deadline and completed sends before generating more work
+                biased;
+                _ = &mut deadline => break,
+                _ = self.shutdown.wait() => break,
+                Some((i, nonce, result)) = in_flight.next(), if !in_flight.is_empty() => {
+                    let Some(result) = result else {
+                        continue;
+                    };
+                    match result {
+                        Ok((hash, send_duration)) => {
+                            debug!(tx = i + 1, nonce, ?hash, "sent");
+                            sent_txs.push(hash);
+                            send_latencies.push(send_duration);
+                        },
+                        Err(e) => {
+                            debug!(tx = i + 1, nonce, error = %e, "send failed");
+                            total_failed += 1;
+                        }
+                    }
+                }
+                _ = Self::pace(limiter), if has_capacity => {
+                    let i = index;
+                    index += 1;
+                    let wallet_idx = self.wallet_for(i);
+                    let nonce = ctx.next_nonce(wallet_idx).await;
+
+                    let prep_start = Instant::now();
+                    let tx = match self.prepare_transaction(&clients[wallet_idx], i, nonce, ctx.fee_mode).await {
+                        Ok(tx) => tx,
+                        Err(e) => {
+                            warn!(tx = i + 1, error = %e, "failed to prepare");
+                            ctx.record_failed_nonce(wallet_idx, i, nonce, None);
+                            total_failed += 1;
+                            continue;
+                        }
+                    };
+                    prep_duration += prep_start.elapsed();
+
+                    let sign_start = Instant::now();
+                    let raw_tx = match self.sign_transaction(&clients[wallet_idx], tx.clone()).await {
+                        Ok(raw_tx) => raw_tx,
+                        Err(e) => {
+                            warn!(tx = i + 1, error = %e, "failed to sign");
+                            ctx.record_failed_nonce(wallet_idx, i, nonce, Some(tx));
+                            total_failed += 1;
+                            continue;
+                        }
+                    };
+                    sign_duration += sign_start.elapsed();
+
+                    in_flight.push(async move {
+                        let result = self.send_unless_shutdown(ctx, i, wallet_idx, tx, raw_tx).await;
+                        (i, nonce, result)
+                    });
+                }
+            }
+        }
+
+        // On shutdown, give in-flight sends their grace period instead of dropping them outright
+        if self.shutdown.is_requested() {
+            while let Some((i, nonce, result)) = in_flight.next().await {
+                match result {
+                    Some(Ok((hash, send_duration))) => {
+                        debug!(tx = i + 1, nonce, ?hash, "sent");
+                        sent_txs.push(hash);
+                        send_latencies.push(send_duration);
+                    },
+                    Some(Err(e)) => {
+                        debug!(tx = i + 1, nonce, error = %e, "send failed");
+                        total_failed += 1;
+                    }
+                    None => {}
+                }
+            }
+        }
+        let send_duration = start.elapsed();
+
+        let total_abandoned = in_flight.len();
+        drop(in_flight);
+        info!("Deadline reached after {:?}: {} sent, {} failed, {} still in flight abandoned ({:.2} tx/s)",
+              send_duration,
+              sent_txs.len(),
+              total_failed,
+              total_abandoned,
+              sent_txs.len() as f64 / send_duration.as_secs_f64());
+
+        Ok(RunReport {
+            total_sent: sent_txs.len(),
+            total_failed,
+            total_abandoned,
+            batch_duration: send_duration,
+            prep_duration,
+            sign_duration,
+            send_duration,
+            send_latency: LatencyStats::from_samples(&send_latencies),
+            latency_histogram: LatencyHistogram::from_samples(&send_latencies),
+            hashes: sent_txs,
+            ..Default::default()
+        })
+    }
+
+    /// Resends the failed nonces of each gapped wallet in nonce order so the transactions stuck behind
+    /// them can be mined. Transactions that failed to build are rebuilt. A wallet stops at its first
+    /// failure, since every later nonce would be stuck behind it again. An accepted resend is booked like
+    /// any other send and no longer counts as failed. Returns how many were accepted.
+    #[instrument(name = "fill_gaps", skip_all)]
+    async fn fill_gaps(&self, ctx: &RunContext, gaps: Vec<WalletGaps>, hashes: &mut Vec<H256>) -> usize {
+        let total: usize = gaps.iter().map(|gap| gap.missing.len()).sum();
+        info!("Resending {} failed transactions to fill nonce gaps...", total);
+        let mut filled = 0;
+        for gap in gaps {
+            let client = &ctx.clients[gap.wallet_idx];
+            for FailedNonce { index, nonce, tx, kind, .. } in gap.missing {
+                let result = async {
+                    let tx = match tx {
+                        Some(tx) => tx,
+                        None => self.prepare_transaction(client, index, nonce, ctx.fee_mode).await?,
+                    };
+                    let raw_tx = self.sign_transaction(client, tx.clone()).await?;
+                    let (hash, latency) = self.send_with_retry(&**client, raw_tx.clone(), &ctx.retries).await?;
+                    Ok::<_, anyhow::Error>((tx, raw_tx, hash, latency))
+                }
+                .await;
+                match result {
+                    Ok((tx, raw_tx, hash, latency)) => {
+                        debug!(nonce, ?hash, "gap filled");
+                        hashes.push(hash);
+                        // The resend replaces the failure this index was booked with
+                        if let Some(kind) = kind {
+                            let mut failures = ctx.failures.lock().expect("failures lock poisoned");
+                            if let Some(count) = failures.get_mut(&kind) {
+                                *count -= 1;
+                                if *count == 0 {
+                                    failures.remove(&kind);
+                                }
+                            }
+                        }
+                        ctx.records.lock().expect("records lock poisoned").retain(|record| record.index != index);
+                        self.record_accepted(ctx, index, gap.wallet_idx, &tx, hash, latency);
+                        self.track_broadcast(ctx, gap.wallet_idx, tx, hash, raw_tx);
+                        filled += 1;
+                    }
+                    Err(e) => {
+                        warn!(wallet = ?client.address(), nonce, error = %e, "failed to fill nonce gap");
+                        break;
+                    }
+                }
+            }
+        }
+        info!("Nonce gaps: {} of {} failed transactions resent", filled, total);
+        filled
+    }
+
+    /// Sends a transaction unless a shutdown was requested. Once one is, the send gets
+    /// `SHUTDOWN_GRACE` to finish before it is given up on. `None` when skipped or abandoned.
+    async fn send_unless_shutdown(
+        &self,
+        ctx: &RunContext,
+        index: u64,
+        wallet_idx: usize,
+        tx: TypedTransaction,
+        raw_tx: Bytes,
+    ) -> Option<Result<(H256, Duration)>> {
+        if self.shutdown.is_requested() {
+            return None;
+        }
+        let grace = async {
+            self.shutdown.wait().await;
+            sleep(SHUTDOWN_GRACE).await;
+        };
+        let nonce = tx.nonce().copied().unwrap_or_default().as_u64();
+        let send_start = Instant::now();
+        tokio::select! {
+            result = self.send_with_nonce_recovery(ctx, index, wallet_idx, tx.clone(), raw_tx) => {
+                if let Some(adaptive) = &ctx.adaptive {
+                    adaptive.record(send_start.elapsed(), result.is_ok());
+                }
+                match &result {
+                    Ok((hash, latency)) => self.record_accepted(ctx, index, wallet_idx, &tx, *hash, *latency),
+                    Err(e) => {
+                        self.metrics.record_failed();
+                        let wallet = ctx.clients[wallet_idx].address();
+                        let error = Some(format!("{:#}", e));
+                        self.record(ctx, TxRecord { index, nonce, wallet, hash: None, send_latency: None, error });
+                        let kind = FailureKind::classify(e);
+                        *ctx.failures.lock().expect("failures lock poisoned").entry(kind).or_default() += 1;
+                    }
+                }
+                Some(result)
+            }
+            _ = grace => {
+                ctx.abandoned.fetch_add(1, Ordering::Relaxed);
+                let wallet = ctx.clients[wallet_idx].address();
+                let error = Some("abandoned on shutdown".to_string());
+                self.record(ctx, TxRecord { index, nonce, wallet, hash: None, send_latency: None, error });
+                None
+            }
+        }
+    }
+
+    /// Books a send the node accepted: its completion time, what confirmation needs to know about it,
+    /// its worst-case cost and gas price, the live metrics and its per-transaction record
+    fn record_accepted(
+        &self,
+        ctx: &RunContext,
+        index: u64,
+        wallet_idx: usize,
+        tx: &TypedTransaction,
+        hash: H256,
+        latency: Duration,
+    ) {
+        // The most this transaction can cost, should it be mined
+        let gas_limit = tx.gas().copied();
+        let gas_price = tx.gas_price().unwrap_or_default();
+        let max_cost = gas_limit.unwrap_or_default() * gas_price;
+        let completed = ctx.started.elapsed();
+        ctx.completions.lock().expect("completions lock poisoned").push(completed);
+        let submission = Submission { at: Instant::now(), gas_limit };
+        ctx.submissions.lock().expect("submissions lock poisoned").insert(hash, submission);
+        *ctx.estimated_gas_cost.lock().expect("gas cost lock poisoned") += max_cost;
+        let mut gas_prices = ctx.gas_prices.lock().expect("gas prices lock poisoned");
+        *gas_prices = Some(GasPriceRange::include(*gas_prices, gas_price));
+        self.metrics.record_sent(latency);
+        let nonce = tx.nonce().copied().unwrap_or_default().as_u64();
+        let wallet = ctx.clients[wallet_idx].address();
+        let record = TxRecord { index, nonce, wallet, hash: Some(hash), send_latency: Some(latency), error: None };
+        self.record(ctx, record);
+    }
+
+    /// Keeps the per-transaction record of a send, when records are enabled
+    fn record(&self, ctx: &RunContext, record: TxRecord) {
+        if self.config.records {
+            ctx.records.lock().expect("records lock poisoned").push(record);
+        }
+    }
+
+    /// Pacer for `rate` tx/s following the configured arrival pattern.
+    /// Capacity 1 keeps sends from bursting to catch up after a stall.
+    fn limiter(&self, rate: f64) -> TokenBucket {
+        TokenBucket::with_arrival(rate, 1.0, self.config.arrival, Some(self.seed))
+    }
+
+    /// Resolves once the next transaction may be started
+    async fn pace(limiter: Option<&TokenBucket>) {
+        if let Some(limiter) = limiter {
+            limiter.acquire().await;
+        }
+    }
+
+    /// Builds and finishes a transaction from `client`'s wallet: attaches the access list and resolves
+    /// the gas limit, which may need the node for `eth_createAccessList` or gas estimation
+    async fn prepare_transaction(
+        &self,
+        client: &Client,
+        index: u64,
+        nonce: u64,
+        fee_mode: FeeMode,
+    ) -> Result<TypedTransaction> {
+        let mut tx = self.create_transaction(client.address(), index, nonce, fee_mode);
+        let to = *tx.to_addr().expect("create_transaction always sets a recipient");
+
+        // Access lists change the gas needed, so attach them before estimating
+        if let Some(access_list) = self.resolve_access_list(client, &tx, to).await? {
+            tx = match tx {
+                // Legacy fields plus an access list make an EIP-2930 transaction
+                TypedTransaction::Legacy(request) => Eip2930TransactionRequest::new(request, access_list).into(),
+                mut tx => {
+                    tx.set_access_list(access_list);
+                    tx
+                }
+            };
+        }
+
+        // 21000 for plain transfers, estimated for anything heavier
+        let gas_limit = self.resolve_gas_limit(client, &tx, to).await?;
+        tx.set_gas(gas_limit);
+
+        Ok(tx)
+    }
+
+    /// Creates the `index`-th transaction of a run, sent from `from` with the given nonce and fees,
+    /// and runs the [`TxHook`] on it. Needs no network: the access list and any gas limit not set explicitly are left for
+    /// [`Benchmarker::prepare_transaction`].
+    pub fn create_transaction(&self, from: Address, index: u64, nonce: u64, fee_mode: FeeMode) -> TypedTransaction {
+        let config = &self.config;
+
+        // Spread transactions round-robin over the recipients, self-sending when there are none
+        let recipients = &config.recipients;
+        let address = if recipients.is_empty() {
+            from
+        } else {
+            recipients[(index % recipients.len() as u64) as usize]
+        };
+
+        // Pick the envelope and fee fields from the requested fee mode, escalated per index if asked to
+        let fee_mode = match config.gas_escalation_pct {
+            Some(pct) => fee_mode.escalate(pct, index, config.max_gas_price),
+            None => fee_mode,
+        };
+        let mut tx: TypedTransaction = match fee_mode {
+            FeeMode::Legacy { gas_price } => TransactionRequest::new().gas_price(gas_price).into(),
+            FeeMode::Eip1559 { max_fee, max_priority_fee } => Eip1559TransactionRequest::new()
+                .max_fee_per_gas(max_fee)
+                .max_priority_fee_per_gas(max_priority_fee)
+                .into(),
+        };
+
+        // In ERC-20 mode the recipient and value move into a token transfer call
+        let value = self.transaction_value(index);
+        let (to, value, data) = match config.erc20 {
+            Some(token) => (token, U256::zero(), erc20_transfer(address, value)),
+            None => (address, value, config.data.clone()),
+        };
+
+        // Populate transaction with explicit nonce, value and calldata
+        tx.set_from(from);
+        tx.set_to(to);
+        tx.set_value(value);
+        tx.set_nonce(nonce);
+        if !data.is_empty() {
+            tx.set_data(data);
+        }
+        if let Some(gas_limit) = config.gas_limit {
+            tx.set_gas(gas_limit);
+        }
+        self.hook.modify(&mut tx, index);
+        tx
+    }
+
+    /// Highest per-gas price any transaction can be built with: that of the last index once fees
+    /// escalate, or the cap when a run without a fixed count has no last index
+    fn max_fee_per_gas(&self, fee_mode: FeeMode) -> U256 {
+        let Some(pct) = self.config.gas_escalation_pct else {
+            return fee_mode.max_fee_per_gas();
+        };
+        let Some(count) = self.config.fixed_count() else {
+            return self.config.max_gas_price.unwrap_or_else(|| fee_mode.max_fee_per_gas());
+        };
+        // Warm-up transactions take the first indices too
+        let last_index = count.max(self.config.warmup).saturating_sub(1);
+        fee_mode.escalate(pct, last_index, self.config.max_gas_price).max_fee_per_gas()
+    }
+
+    /// Value of the transaction at `index`: the configured value, jittered by up to
+    /// `value_jitter_pct` percent either way. The draw depends only on the seed and the index,
+    /// so a seeded run gets the same values whatever order transactions are built in.
+    pub fn transaction_value(&self, index: u64) -> U256 {
+        let pct = self.config.value_jitter_pct.min(100);
+        if pct == 0 || self.config.value.is_zero() {
+            return self.config.value;
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed ^ index.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        // In basis points of the configured value, so small percentages still vary smoothly
+        let basis_points = rng.gen_range(10_000 - pct * 100..=10_000 + pct * 100);
+        self.config.value * basis_points / 10_000
+    }
+
+    /// Wallet that signs the `index`-th transaction of the run: wallets in turn, or one drawn from
+    /// the seed so the same seed repeats the assignment
+    pub fn wallet_for(&self, index: u64) -> usize {
+        let wallets = self.config.wallets.len().max(1);
+        match self.config.wallet_selection {
+            WalletSelection::RoundRobin => (index % wallets as u64) as usize,
+            WalletSelection::Random => {
+                // A different stream from the value jitter, so the two don't move together
+                let seed = self.seed.rotate_left(32) ^ index.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+                StdRng::seed_from_u64(seed).gen_range(0..wallets)
+            }
+        }
+    }
+
+    /// Transactions out of `count` that each wallet signs
+    fn wallet_shares(&self, count: u64) -> Vec<u64> {
+        let wallets = self.config.wallets.len();
+        match self.config.wallet_selection {
+            WalletSelection::RoundRobin => (0..wallets).map(|idx| wallet_share(count, wallets, idx)).collect(),
+            WalletSelection::Random => {
+                let mut shares = vec![0; wallets];
+                for index in 0..count {
+                    shares[self.wallet_for(index)] += 1;
+                }
+                shares
+            }
+        }
+    }
+
+    /// Largest value `transaction_value` can return
+    fn max_transaction_value(&self) -> U256 {
+        self.config.value * (100 + self.config.value_jitter_pct.min(100)) / 100
+    }
+
+    /// Signs a prepared transaction with the client's wallet and returns its EIP-2718 encoding
+    pub async fn sign_transaction(&self, client: &Client, tx: TypedTransaction) -> Result<Bytes> {
+        sign_with(client.signer(), &tx, self.config.eip155)
+    }
+
+    /// Submits a pre-signed transaction via `eth_sendRawTransaction` without waiting for a receipt,
+    /// returning its hash and how long the send took. In dry-run mode nothing is broadcast and
+    /// the locally computed hash is returned instead.
+    pub async fn send_transaction<M: Middleware>(&self, client: &M, raw_tx: Bytes) -> Result<(H256, Duration)>
+    where
+        M::Error: 'static,
+    {
+        // Start measuring send time
+        let send_start = Instant::now();
+
+        if self.config.dry_run {
+            let tx_hash = H256::from(keccak256(&raw_tx));
+            debug!(hash = ?tx_hash, raw = %raw_tx, "not broadcast (dry run)");
+            return Ok((tx_hash, send_start.elapsed()));
+        }
+
+        // Send transaction, giving up on a hung call once the send timeout elapses
+        let send = client.send_raw_transaction(raw_tx.clone());
+        let result = match self.config.send_timeout {
+            Some(limit) => timeout(limit, send)
+                .await
+                .map_err(|_| SendError::Timeout(format!("eth_sendRawTransaction timed out after {:?}", limit)))
+                .and_then(|sent| sent.map_err(|e| SendError::from_provider(&e))),
+            None => send.await.map_err(|e| SendError::from_provider(&e)),
+        };
+        let tx_hash = match result {
+            Ok(pending_tx) => pending_tx.tx_hash(),
+            // Already in the node's mempool, so as good as accepted
+            Err(SendError::AlreadyKnown(_)) => {
+                self.already_known.fetch_add(1, Ordering::Relaxed);
+                let tx_hash = H256::from(keccak256(&raw_tx));
+                debug!(hash = ?tx_hash, "node already knows the transaction");
+                tx_hash
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        // Measure send time
+        let send_duration = send_start.elapsed();
+        debug!(?send_duration, hash = ?tx_hash, "eth_sendRawTransaction returned");
+
+        Ok((tx_hash, send_duration))
+    }
+
+    /// Sends a pre-signed transaction, retrying transient failures with exponential backoff.
+    /// Every retry issued is added to `retries`.
+    pub async fn send_with_retry<M: Middleware>(
+        &self,
+        client: &M,
+        raw_tx: Bytes,
+        retries: &AtomicU64,
+    ) -> Result<(H256, Duration)>
+    where
+        M::Error: 'static,
+    {
+        let policy = self.config.retry;
+        let mut attempt = 0;
+        loop {
+            match self.send_transaction(client, raw_tx.clone()).await {
+                Ok(sent) => return Ok(sent),
+                Err(e) if attempt < policy.retries && SendError::of(&e).is_retryable() => {
+                    attempt += 1;
+                    retries.fetch_add(1, Ordering::Relaxed);
+                    let delay = policy.delay(attempt);
+                    debug!(error = %e, attempt, retries = policy.retries, ?delay, "send failed, retrying");
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Submits raw transactions as one JSON-RPC batch, retrying the whole batch on transient
+    /// transport failures. Returns each transaction's hash or rejection, in order.
+    /// In dry-run mode nothing is broadcast and the locally computed hashes are returned instead.
+    async fn send_batch_with_retry(
+        &self,
+        transport: &Provider<FailoverTransport>,
+        raw_txs: &[Bytes],
+        retries: &AtomicU64,
+    ) -> Result<Vec<Result<H256, SendError>>> {
+        if self.config.dry_run {
+            return Ok(raw_txs.iter().map(|raw_tx| Ok(H256::from(keccak256(raw_tx)))).collect());
+        }
+        let policy = self.config.retry;
+        let mut attempt = 0;
+        loop {
+            let batch = transport.as_ref().send_raw_batch(raw_txs);
+            let result = match self.config.send_timeout {
+                Some(limit) => timeout(limit, batch)
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow!("JSON-RPC batch timed out after {:?}", limit))),
+                None => batch.await,
+            };
+            match result {
+                Err(e) if attempt < policy.retries && SendError::of(&e).is_retryable() => {
+                    attempt += 1;
+                    retries.fetch_add(1, Ordering::Relaxed);
+                    let delay = policy.delay(attempt);
+                    debug!(error = %e, attempt, retries = policy.retries, ?delay, "batch failed, retrying");
+                    sleep(delay).await;
+                }
+                Ok(results) => {
+                    // Transactions the node already has are in flight, so as good as accepted
+                    let results = raw_txs
+                        .iter()
+                        .zip(results)
+                        .map(|(raw_tx, result)| match result.map_err(|message| SendError::from_rejection(&message)) {
+                            Err(SendError::AlreadyKnown(_)) => {
+                                self.already_known.fetch_add(1, Ordering::Relaxed);
+                                Ok(H256::from(keccak256(raw_tx)))
+                            }
+                            result => result,
+                        })
+                        .collect();
+                    return Ok(results);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Sends a pre-signed transaction and, if the node rejects it with "nonce too low" (e.g. leftovers
+    /// from a previous run got mined), re-signs it once with a fresh nonce and sends it again.
+    /// The fresh nonce comes from the same per-wallet counter the generator uses, so re-sends never collide.
+    async fn send_with_nonce_recovery(
+        &self,
+        ctx: &RunContext,
+        index: u64,
+        wallet_idx: usize,
+        mut tx: TypedTransaction,
+        mut raw_tx: Bytes,
+    ) -> Result<(H256, Duration)> {
+        let client = ctx.clients[wallet_idx].clone();
+        let result = match self.send_with_retry(&*client, raw_tx.clone(), &ctx.retries).await {
+            Err(e) if matches!(SendError::of(&e), SendError::NonceTooLow(_)) => {
+                let nonce = ctx.nonces.reset(&*client, client.address()).await?;
+                debug!(old_nonce = %tx.nonce().copied().unwrap_or_default(), nonce, "nonce too low, re-signing");
+                tx.set_nonce(nonce);
+                raw_tx = self.sign_transaction(&client, tx.clone()).await?;
+                self.send_with_retry(&*client, raw_tx.clone(), &ctx.retries).await
+            }
+            result => result,
+        };
+        // Track which nonces made it, so gaps left by failures can be found after the send phase
+        let nonce = tx.nonce().copied().unwrap_or_default().as_u64();
+        match &result {
+            Ok(_) => ctx.accepted_nonces.lock().expect("accepted nonces lock poisoned").push((wallet_idx, nonce)),
+            // The nonce was used by something else, so there's no gap
+            Err(e) if matches!(SendError::of(e), SendError::NonceTooLow(_)) => {}
+            Err(e) => ctx.failed_nonces.lock().expect("failed nonces lock poisoned").push(FailedNonce {
+                wallet_idx,
+                index,
+                nonce,
+                tx: Some(tx.clone()),
+                kind: Some(FailureKind::classify(e)),
+            }),
+        }
+        if let Ok((hash, _)) = &result {
+            self.track_broadcast(ctx, wallet_idx, tx, *hash, raw_tx);
+        }
+        result
+    }
+
+    /// Keeps what was actually broadcast so confirmation can replace it with higher fees, and the
+    /// signed bytes, so a transaction reorged out while confirming can be broadcast again
+    fn track_broadcast(&self, ctx: &RunContext, wallet_idx: usize, tx: TypedTransaction, hash: H256, raw_tx: Bytes) {
+        if self.config.rbf.is_some() {
+            ctx.sent.lock().expect("sent lock poisoned").push(SentTx { wallet_idx, tx, hash });
+        }
+        if self.config.rebroadcast_reorged {
+            ctx.broadcast.lock().expect("broadcast lock poisoned").insert(hash, raw_tx);
+        }
+    }
+
+    /// Access list for a transaction to `to`: the configured one, or one generated with
+    /// `eth_createAccessList` and cached per recipient and calldata. A failed generation is
+    /// logged and leaves that recipient and calldata without a list.
+    async fn resolve_access_list(&self, client: &Client, tx: &TypedTransaction, to: Address) -> Result<Option<AccessList>> {
+        match &self.config.access_list {
+            None => Ok(None),
+            Some(AccessListSource::Fixed(access_list)) => Ok(Some(access_list.clone())),
+            Some(AccessListSource::Generated) => {
+                let key = (to, tx.data().cloned().unwrap_or_default());
+                let mut cache = self.gas_cache.lock().await;
+                if let Some(access_list) = cache.access_lists.get(&key) {
+                    return Ok(Some(access_list.clone()));
+                }
+                let access_list = match client.create_access_list(tx, None).await {
+                    Ok(generated) => generated.access_list,
+                    Err(e) => {
+                        warn!("eth_createAccessList failed for {} ({}), sending without an access list", to, e);
+                        AccessList::default()
+                    }
+                };
+                cache.access_lists.insert(key, access_list.clone());
+                Ok(Some(access_list))
+            }
+        }
+    }
+
+    /// Picks the gas limit for a transaction, estimating it for calldata or contract recipients
+    async fn resolve_gas_limit(&self, client: &Client, tx: &TypedTransaction, to: Address) -> Result<u64> {
+        let config = &self.config;
+        if let Some(gas_limit) = config.gas_limit {
+            return Ok(gas_limit);
+        }
+
+        let mut cache = self.gas_cache.lock().await;
+        let is_contract = match cache.is_contract.get(&to) {
+            Some(is_contract) => *is_contract,
+            None => {
+                let is_contract = !client.get_code(to, None).await?.is_empty();
+                cache.is_contract.insert(to, is_contract);
+                is_contract
+            }
+        };
+        if config.data.is_empty() && !is_contract {
+            // Intrinsic gas of a plain transfer, plus whatever its access list costs
+            let access_list_gas: u64 = tx
+                .access_list()
+                .map(|list| {
+                    list.0
+                        .iter()
+                        .map(|item| ACCESS_LIST_ADDRESS_GAS + ACCESS_LIST_STORAGE_KEY_GAS * item.storage_keys.len() as u64)
+                        .sum()
+                })
+                .unwrap_or(0);
+            return Ok(TRANSFER_GAS + access_list_gas);
+        }
+
+        let key = (to, config.data.clone());
+        if let Some(gas) = cache.estimates.get(&key) {
+            return Ok(*gas);
+        }
+        let gas = match client.estimate_gas(tx, None).await {
+            Ok(estimate) => (estimate * (100 + config.gas_buffer_pct) / 100).as_u64(),
+            Err(e) => {
+                warn!("Gas estimation failed for {} ({}), falling back to {}", to, e, TRANSFER_GAS);
+                TRANSFER_GAS
+            }
+        };
+        cache.estimates.insert(key, gas);
+        Ok(gas)
+    }
+}
+
+/// Number of transactions out of `count` that round-robin assigns to wallet `wallet_idx`
+fn wallet_share(count: u64, wallets: usize, wallet_idx: usize) -> u64 {
+    let wallets = wallets as u64;
+    count / wallets + u64::from((wallet_idx as u64) < count % wallets)
+}
+
+/// Signs `tx` with `wallet` and returns its EIP-2718 encoding; without `eip155` a legacy
+/// transaction is signed the pre-EIP-155 way, with no chain id. Local signing never awaits
+/// anything, so this is usable from blocking worker threads.
+pub(crate) fn sign_with(wallet: &LocalWallet, tx: &TypedTransaction, eip155: bool) -> Result<Bytes> {
+    let mut tx = tx.clone();
+    if !eip155 {
+        let TypedTransaction::Legacy(legacy) = &mut tx else {
+            bail!("only legacy transactions can be signed without EIP-155, e.g. not with an access list");
+        };
+        // `sign_transaction_sync` always folds the chain id into `v`, so sign the bare hash
+        legacy.chain_id = None;
+        let signature = wallet.sign_hash(tx.sighash())?;
+        return Ok(tx.rlp_signed(&signature));
+    }
+    // The chain id is part of the signed payload, so pin it before signing
+    tx.set_chain_id(wallet.chain_id());
+    let signature = wallet.sign_transaction_sync(&tx)?;
+    Ok(tx.rlp_signed(&signature))
+}
+
+/// Gas limit of a signed transaction in its EIP-2718 encoding; `None` if it doesn't decode
+fn signed_gas_limit(raw_tx: &Bytes) -> Option<U256> {
+    let (tx, _) = TypedTransaction::decode_signed(&ethers::utils::rlp::Rlp::new(raw_tx)).ok()?;
+    tx.gas().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::NonceSource, fees::GasStrategy, rate::Arrival, retry::RetryPolicy};
+    use reqwest::header::HeaderMap;
+
+    const FROM: &str = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266";
+
+    fn config(recipients: Vec<Address>) -> Config {
+        Config {
+            rpc_urls: Vec::new(),
+            rpc_headers: HeaderMap::new(),
+            chain_id: None,
+            wallets: Vec::new(),
+            nonce_source: NonceSource::Latest,
+            wallet_selection: WalletSelection::RoundRobin,
+            at_block: None,
+            funding: None,
+            nonce_state_file: None,
+            workload: Workload::Count(1),
+            warmup: 0,
+            ramp: None,
+            probe: None,
+            pipeline: false,
+            pipeline_buffer: 1,
+            gas_strategy: GasStrategy::default(),
+            max_gas_price: None,
+            gas_escalation_pct: None,
+            gas_sanity_factor: None,
+            force: false,
+            tx_type: TxType::Auto,
+            eip155: true,
+            recipients,
+            value: U256::from(7),
+            value_jitter_pct: 0,
+            erc20: None,
+            data: Bytes::default(),
+            access_list: None,
+            gas_limit: Some(TRANSFER_GAS),
+            gas_buffer_pct: 0,
+            max_concurrency: 0,
+            adaptive_concurrency: false,
+            rate: None,
+            arrival: Arrival::Uniform,
+            burst: None,
+            seed: None,
+            retry: RetryPolicy { retries: 0, base_delay: Duration::ZERO },
+            send_timeout: None,
+            batch_size: 1,
+            bundle: None,
+            fill_gaps: false,
+            dry_run: false,
+            confirm: false,
+            progress: false,
+            records: false,
+            rbf: None,
+            poll_interval: Duration::from_millis(100),
+            confirm_timeout: Duration::from_secs(1),
+            confirmations: 1,
+            rebroadcast_reorged: false,
+            cooldown: None,
+            inspect_mempool: false,
+        }
+    }
+
+    fn fee_modes() -> [FeeMode; 2] {
+        [
+            FeeMode::Legacy { gas_price: U256::from(3_000_000_000u64) },
+            FeeMode::Eip1559 { max_fee: U256::from(5_000_000_000u64), max_priority_fee: U256::from(1_000_000_000u64) },
+        ]
+    }
+
+    #[test]
+    fn self_send_for_each_fee_mode() {
+        let from: Address = FROM.parse().unwrap();
+        let benchmarker = Benchmarker::new(config(Vec::new()));
+        for fee_mode in fee_modes() {
+            let tx = benchmarker.create_transaction(from, 0, 42, fee_mode);
+            assert_eq!(tx.from(), Some(&from));
+            assert_eq!(tx.to_addr(), Some(&from));
+            assert_eq!(tx.value(), Some(&U256::from(7)));
+            assert_eq!(tx.nonce(), Some(&U256::from(42)));
+            assert_eq!(tx.gas(), Some(&U256::from(TRANSFER_GAS)));
+            assert_eq!(tx.gas_price(), Some(fee_mode.max_fee_per_gas()));
+            assert!(tx.data().is_none());
+        }
+    }
+
+    #[test]
+    fn fee_mode_picks_the_envelope() {
+        let from: Address = FROM.parse().unwrap();
+        let benchmarker = Benchmarker::new(config(Vec::new()));
+        let [legacy, eip1559] = fee_modes();
+
+        assert!(matches!(benchmarker.create_transaction(from, 0, 0, legacy), TypedTransaction::Legacy(_)));
+        match benchmarker.create_transaction(from, 0, 0, eip1559) {
+            TypedTransaction::Eip1559(tx) => {
+                assert_eq!(tx.max_fee_per_gas, Some(U256::from(5_000_000_000u64)));
+                assert_eq!(tx.max_priority_fee_per_gas, Some(U256::from(1_000_000_000u64)));
+            }
+            other => panic!("expected an EIP-1559 transaction, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gas_escalation_compounds_per_index_up_to_the_cap() {
+        let from: Address = FROM.parse().unwrap();
+        let mut config = config(Vec::new());
+        config.gas_escalation_pct = Some(10.0);
+        config.max_gas_price = Some(U256::from(4_000_000_000u64));
+        let benchmarker = Benchmarker::new(config);
+        let [legacy, _] = fee_modes();
+
+        let gas_price = |index| benchmarker.create_transaction(from, index, index, legacy).gas_price().unwrap();
+        assert_eq!(gas_price(0), U256::from(3_000_000_000u64));
+        assert_eq!(gas_price(1), U256::from(3_300_000_000u64));
+        assert_eq!(gas_price(2), U256::from(3_630_000_000u64));
+        assert_eq!(gas_price(3), U256::from(3_993_000_000u64));
+        assert_eq!(gas_price(4), U256::from(4_000_000_000u64));
+    }
+
+    #[test]
+    fn worst_case_fee_is_the_last_escalated_one_not_the_cap() {
+        let mut config = config(Vec::new());
+        config.workload = Workload::Count(10);
+        config.gas_escalation_pct = Some(1.0);
+        config.max_gas_price = Some(U256::from(1_000_000_000_000u64));
+        let [legacy, _] = fee_modes();
+        let last = Benchmarker::new(config.clone()).create_transaction(Address::zero(), 9, 9, legacy);
+        let worst_case = Benchmarker::new(config.clone()).max_fee_per_gas(legacy);
+        assert_eq!(worst_case, *last.gas_price().as_ref().unwrap());
+        assert!(worst_case < U256::from(3_300_000_000u64));
+
+        // Without a fixed count there is no last index, so only the cap bounds the fee
+        config.workload = Workload::Duration(Duration::from_secs(1));
+        assert_eq!(Benchmarker::new(config).max_fee_per_gas(legacy), U256::from(1_000_000_000_000u64));
+    }
+
+    #[test]
+    fn hook_runs_on_every_built_transaction() {
+        #[derive(Debug)]
+        struct IndexAsValue;
+        impl TxHook for IndexAsValue {
+            fn modify(&self, tx: &mut TypedTransaction, index: u64) {
+                tx.set_value(index);
+            }
+        }
+
+        let from: Address = FROM.parse().unwrap();
+        let benchmarker = Benchmarker::new(config(Vec::new())).with_hook(Arc::new(IndexAsValue));
+        for index in [0, 7] {
+            let tx = benchmarker.create_transaction(from, index, index, fee_modes()[0]);
+            assert_eq!(tx.value(), Some(&U256::from(index)));
+        }
+    }
+
+    #[test]
+    fn recipients_are_used_round_robin() {
+        let from: Address = FROM.parse().unwrap();
+        let recipients = vec![Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3)];
+        let benchmarker = Benchmarker::new(config(recipients.clone()));
+        for fee_mode in fee_modes() {
+            for index in 0..6u64 {
+                let tx = benchmarker.create_transaction(from, index, index, fee_mode);
+                assert_eq!(tx.from(), Some(&from));
+                assert_eq!(tx.to_addr(), Some(&recipients[index as usize % 3]));
+                assert_eq!(tx.nonce(), Some(&U256::from(index)));
+            }
+        }
+    }
+
+    #[test]
+    fn erc20_mode_moves_recipient_and_value_into_calldata() {
+        let from: Address = FROM.parse().unwrap();
+        let token = Address::repeat_byte(0xee);
+        let recipient = Address::repeat_byte(1);
+        let mut config = config(vec![recipient]);
+        config.erc20 = Some(token);
+        let benchmarker = Benchmarker::new(config);
+
+        let tx = benchmarker.create_transaction(from, 0, 0, fee_modes()[0]);
+        assert_eq!(tx.to_addr(), Some(&token));
+        assert_eq!(tx.value(), Some(&U256::zero()));
+        assert_eq!(tx.data(), Some(&erc20_transfer(recipient, U256::from(7))));
+    }
+
+    #[test]
+    fn gas_limit_is_left_unset_when_it_needs_estimating() {
+        let from: Address = FROM.parse().unwrap();
+        let mut config = config(Vec::new());
+        config.gas_limit = None;
+        let benchmarker = Benchmarker::new(config);
+
+        assert!(benchmarker.create_transaction(from, 0, 0, fee_modes()[0]).gas().is_none());
+    }
+
+    #[test]
+    fn value_jitter_stays_in_range_and_follows_the_seed() {
+        let mut jittered = config(Vec::new());
+        jittered.value = U256::from(1_000_000);
+        jittered.value_jitter_pct = 10;
+        jittered.seed = Some(42);
+        let benchmarker = Benchmarker::new(jittered.clone());
+        let values: Vec<U256> = (0..100).map(|index| benchmarker.transaction_value(index)).collect();
+        assert!(values.iter().all(|value| (900_000..=1_100_000).contains(&value.as_u64())));
+        assert!(values.iter().any(|value| *value != values[0]));
+        assert!(values.iter().all(|value| *value <= benchmarker.max_transaction_value()));
+
+        let again = Benchmarker::new(jittered);
+        assert_eq!(values, (0..100).map(|index| again.transaction_value(index)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drawn_seed_reproduces_the_run() {
+        let mut unseeded = config(Vec::new());
+        unseeded.value = U256::from(1_000_000);
+        unseeded.value_jitter_pct = 50;
+        let drawn = Benchmarker::new(unseeded.clone());
+
+        unseeded.seed = Some(drawn.seed());
+        let repeated = Benchmarker::new(unseeded);
+        assert_eq!(
+            (0..20).map(|index| drawn.transaction_value(index)).collect::<Vec<_>>(),
+            (0..20).map(|index| repeated.transaction_value(index)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn wallet_share_splits_the_remainder_over_the_first_wallets() {
+        assert_eq!((0..3).map(|idx| wallet_share(10, 3, idx)).collect::<Vec<_>>(), vec![4, 3, 3]);
+        assert_eq!((0..4).map(|idx| wallet_share(2, 4, idx)).sum::<u64>(), 2);
+    }
+
+    #[test]
+    fn signing_without_eip155_leaves_the_chain_id_out() {
+        let wallet = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(1337u64);
+        let tx: TypedTransaction = TransactionRequest::new().to(wallet.address()).gas(TRANSFER_GAS).gas_price(1).nonce(0).into();
+        let decode = |raw: Bytes| TypedTransaction::decode_signed(&ethers::utils::rlp::Rlp::new(&raw)).unwrap();
+
+        let (protected, signature) = decode(sign_with(&wallet, &tx, true).unwrap());
+        assert_eq!(protected.chain_id(), Some(1337.into()));
+        assert!(signature.v == 1337 * 2 + 35 || signature.v == 1337 * 2 + 36);
+
+        let (unprotected, signature) = decode(sign_with(&wallet, &tx, false).unwrap());
+        assert_eq!(unprotected.chain_id(), None);
+        assert!(signature.v == 27 || signature.v == 28);
+        assert_eq!(signature.recover(unprotected.sighash()).unwrap(), wallet.address());
+
+        let access_list = ethers::types::transaction::eip2930::AccessList::default();
+        let access_listed: TypedTransaction = tx.as_legacy_ref().unwrap().clone().with_access_list(access_list).into();
+        assert!(sign_with(&wallet, &access_listed, false).is_err());
+    }
+
+    #[test]
+    fn random_wallet_selection_is_seeded_and_counted_exactly() {
+        let mut random = config(Vec::new());
+        random.wallets = (1..=4u8).map(|byte| LocalWallet::from_bytes(&[byte; 32]).unwrap()).collect();
+        random.wallet_selection = WalletSelection::Random;
+        random.seed = Some(7);
+        let benchmarker = Benchmarker::new(random.clone());
+
+        let picks: Vec<usize> = (0..200).map(|index| benchmarker.wallet_for(index)).collect();
+        assert_eq!(picks, (0..200).map(|index| Benchmarker::new(random.clone()).wallet_for(index)).collect::<Vec<_>>());
+        assert_ne!(picks, (0..200).map(|index| (index % 4) as usize).collect::<Vec<_>>());
+
+        let shares = benchmarker.wallet_shares(200);
+        assert!(shares.iter().all(|&share| share > 0));
+        for (wallet_idx, share) in shares.into_iter().enumerate() {
+            assert_eq!(share, picks.iter().filter(|&&pick| pick == wallet_idx).count() as u64);
+        }
+    }
+}
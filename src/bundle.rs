@@ -0,0 +1,160 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Bytes, H256},
+    utils::keccak256,
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{fmt, time::Duration};
+
+use crate::gas_oracle::root_cause;
+
+/// How long the relay may take to answer one `eth_sendBundle`
+const RELAY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Header Flashbots-style relays authenticate searchers with
+pub const SIGNATURE_HEADER: &str = "X-Flashbots-Signature";
+
+/// Bundle relay to submit transactions to with `eth_sendBundle` instead of the public mempool
+#[derive(Debug, Clone)]
+pub struct BundleRelay {
+    pub url: String,
+    /// Transactions per bundle, except possibly the last
+    pub size: usize,
+    /// Block every bundle targets; `None` targets the block after the tip when sending starts
+    pub block: Option<u64>,
+    /// Key that signs each request body for the signature header. It only identifies the sender
+    /// to the relay, so it needs no funds and is best kept apart from the sending wallets.
+    pub auth_signer: LocalWallet,
+    /// Extra headers sent with every request, e.g. an API key some relays want instead
+    pub headers: HeaderMap,
+}
+
+impl BundleRelay {
+    /// Submits `txs` as one bundle for `block` and returns the bundle hash, when the relay gives one
+    pub async fn send(&self, client: &reqwest::Client, txs: &[Bytes], block: u64) -> Result<Option<H256>> {
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_sendBundle",
+            "params": [{ "txs": txs, "blockNumber": format!("{:#x}", block) }],
+        })
+        .to_string();
+        let mut signature = HeaderValue::from_str(&flashbots_signature(&self.auth_signer, &body).await?)?;
+        signature.set_sensitive(true);
+
+        let response = client
+            .post(&self.url)
+            .timeout(RELAY_TIMEOUT)
+            .headers(self.headers.clone())
+            .header(SIGNATURE_HEADER, signature)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            // The URL may carry an API key, so it stays out of the error
+            .map_err(|e| anyhow!("request failed: {}", root_cause(&e.without_url())))?;
+        let status = response.status();
+        let text = response.text().await.context("failed to read the relay's response")?;
+        let reply: Value = match serde_json::from_str(&text) {
+            Ok(reply) => reply,
+            Err(_) if !status.is_success() => bail!("relay answered {}: {}", status, text.trim()),
+            Err(_) => bail!("relay response is not JSON: {}", text.trim()),
+        };
+        if let Some(error) = reply.get("error") {
+            let message = error.get("message").and_then(Value::as_str).map(str::to_string);
+            bail!("{}", message.unwrap_or_else(|| error.to_string()));
+        }
+        if !status.is_success() {
+            bail!("relay answered {}: {}", status, text.trim());
+        }
+        // Some relays answer with a bare hash, others with `{"bundleHash": ...}`, some with nothing
+        let hash = match reply.get("result") {
+            Some(Value::String(hash)) => Some(hash.as_str()),
+            Some(result) => result.get("bundleHash").and_then(Value::as_str),
+            None => None,
+        };
+        hash.map(|hash| hash.parse().with_context(|| format!("relay returned an invalid bundle hash {:?}", hash)))
+            .transpose()
+    }
+}
+
+/// Value of the signature header for a request `body`: the signer's address and its EIP-191
+/// signature of the hex-encoded keccak hash of the body
+pub async fn flashbots_signature(signer: &LocalWallet, body: &str) -> Result<String> {
+    let digest = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+    let signature = signer.sign_message(digest).await?;
+    Ok(format!("{:?}:0x{}", signer.address(), signature))
+}
+
+/// How the relay took each bundle of a run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BundleReport {
+    /// Block every bundle targeted
+    pub block: u64,
+    /// Transactions per bundle, except possibly the last
+    pub size: usize,
+    pub bundles: Vec<BundleOutcome>,
+}
+
+/// The relay's answer to one bundle
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BundleOutcome {
+    pub transactions: usize,
+    /// Set when the relay accepted the bundle and named it
+    pub bundle_hash: Option<H256>,
+    /// Why the relay rejected the bundle; `None` when it was accepted
+    pub error: Option<String>,
+    /// Round-trip time of the `eth_sendBundle` call
+    #[serde(rename = "latency_ms", serialize_with = "crate::report::serialize_millis")]
+    pub latency: Duration,
+}
+
+impl BundleOutcome {
+    pub fn accepted(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl BundleReport {
+    /// Bundles the relay accepted
+    pub fn accepted(&self) -> usize {
+        self.bundles.iter().filter(|bundle| bundle.accepted()).count()
+    }
+
+    /// Bundles the relay rejected
+    pub fn rejected(&self) -> usize {
+        self.bundles.len() - self.accepted()
+    }
+}
+
+impl fmt::Display for BundleOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.error, self.bundle_hash) {
+            (Some(error), _) => write!(f, "{} transactions rejected in {:?}: {}", self.transactions, self.latency, error),
+            (None, Some(hash)) => write!(f, "{} transactions accepted as {:?} in {:?}", self.transactions, hash, self.latency),
+            (None, None) => write!(f, "{} transactions accepted in {:?}", self.transactions, self.latency),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, Signature};
+
+    #[tokio::test]
+    async fn signature_header_recovers_to_the_auth_signer() {
+        let signer: LocalWallet = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80".parse().unwrap();
+        let body = r#"{"jsonrpc":"2.0","id":1,"method":"eth_sendBundle","params":[]}"#;
+        let header = flashbots_signature(&signer, body).await.unwrap();
+
+        let (address, signature) = header.split_once(':').unwrap();
+        assert_eq!(address.parse::<Address>().unwrap(), signer.address());
+        let signature: Signature = signature.parse().unwrap();
+        let digest = format!("{:?}", H256::from(keccak256(body.as_bytes())));
+        assert_eq!(signature.recover(digest).unwrap(), signer.address());
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    abi::{self, token::LenientTokenizer, token::Tokenizer, Abi, AbiParser, Function, Token},
+    types::{Address, Bytes, U256},
+    utils::id,
+};
+use std::{fs, path::Path};
+
+/// ABI-encoded `transfer(address,uint256)` call of an ERC-20 token
+pub fn erc20_transfer(recipient: Address, amount: U256) -> Bytes {
+    let mut data = id("transfer(address,uint256)").to_vec();
+    data.extend(abi::encode(&[Token::Address(recipient), Token::Uint(amount)]));
+    data.into()
+}
+
+/// ABI-encodes a call of `method` with `args`. With an ABI file, `method` is a function name or a
+/// full signature such as `mint(address,uint256)`; without one it must be a human-readable
+/// signature like `mint(address to, uint256 amount)`.
+pub fn encode_call(abi_path: Option<&Path>, method: &str, args: &[String]) -> Result<Bytes> {
+    let function = match abi_path {
+        Some(path) => {
+            let json = fs::read_to_string(path).with_context(|| format!("failed to read ABI file {}", path.display()))?;
+            let abi: Abi = serde_json::from_str(&json).with_context(|| format!("invalid ABI file {}", path.display()))?;
+            find_function(&abi, method)?
+        }
+        None => AbiParser::default()
+            .parse_function(method)
+            .with_context(|| format!("invalid method signature {:?}", method))?,
+    };
+
+    if function.inputs.len() != args.len() {
+        bail!(
+            "{} takes {} arguments but {} were given",
+            function.signature(),
+            function.inputs.len(),
+            args.len()
+        );
+    }
+    let tokens = function
+        .inputs
+        .iter()
+        .zip(args)
+        .enumerate()
+        .map(|(idx, (param, arg))| {
+            LenientTokenizer::tokenize(&param.kind, arg).with_context(|| {
+                format!("invalid {} value {:?} for argument {} of {}", param.kind, arg, idx + 1, function.signature())
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(function.encode_input(&tokens)?.into())
+}
+
+/// Looks a function up by name, or by signature when the name is overloaded
+fn find_function(abi: &Abi, method: &str) -> Result<Function> {
+    match method.split_once('(') {
+        Some((name, _)) => abi
+            .functions_by_name(name)?
+            .iter()
+            .find(|function| function.signature() == method)
+            .cloned()
+            .ok_or_else(|| anyhow!("ABI has no function with signature {}", method)),
+        None => match abi.functions_by_name(method)?.as_slice() {
+            [function] => Ok(function.clone()),
+            overloads => bail!(
+                "{} is overloaded, pass one of the signatures: {}",
+                method,
+                overloads.iter().map(Function::signature).collect::<Vec<_>>().join(", ")
+            ),
+        },
+    }
+}
+
+/// Explains the data a reverted call returned: the message of a standard `Error(string)`,
+/// the code of a Solidity `Panic(uint256)`, or the selector of a custom error
+pub fn decode_revert_reason(data: &[u8]) -> String {
+    if data.is_empty() {
+        return "reverted without a reason".to_string();
+    }
+    let (selector, payload) = data.split_at(data.len().min(4));
+    if selector == id("Error(string)") {
+        if let Ok(tokens) = abi::decode(&[abi::ParamType::String], payload) {
+            if let Some(Token::String(reason)) = tokens.into_iter().next() {
+                return reason;
+            }
+        }
+    }
+    if selector == id("Panic(uint256)") {
+        if let Ok(tokens) = abi::decode(&[abi::ParamType::Uint(256)], payload) {
+            if let Some(Token::Uint(code)) = tokens.into_iter().next() {
+                return format!("panic 0x{:02x}", code);
+            }
+        }
+    }
+    format!("custom error 0x{}", hex::encode(selector))
+}
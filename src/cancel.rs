@@ -0,0 +1,215 @@
+use anyhow::{bail, Result};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, Provider},
+    signers::Signer,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Eip1559TransactionRequest, TransactionRequest,
+        H256, U256,
+    },
+};
+use serde::Serialize;
+use std::{collections::HashMap, fmt, ops::RangeInclusive};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::{Config, TxType},
+    confirm::{wait_for_receipts, ConfirmProgress},
+    fees::{apply_gas_multiplier, FeeMode},
+    shutdown::Shutdown,
+    transport::FailoverTransport,
+};
+
+/// Gas of the zero-value self-transfer that replaces a stuck transaction
+const CANCEL_GAS: u64 = 21000;
+
+/// What happened to one nonce of a cancel run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelOutcome {
+    /// The cancelling self-transfer was mined in place of whatever held the nonce
+    Replaced,
+    /// The nonce was already mined before or during the cancel run, by some other transaction
+    AlreadyMined,
+    /// The cancelling transaction was rejected, e.g. for not outbidding the one it replaces
+    Rejected,
+    /// The cancelling transaction was accepted but not mined before the timeout
+    Pending,
+}
+
+/// Outcome of a cancel run for a single wallet and nonce
+#[derive(Debug, Clone, Serialize)]
+pub struct CancelledNonce {
+    pub wallet: Address,
+    pub nonce: u64,
+    pub outcome: CancelOutcome,
+    /// Hash of the cancelling transaction, when it was accepted
+    pub hash: Option<H256>,
+    /// Why the node rejected the cancelling transaction
+    pub error: Option<String>,
+}
+
+/// Result of replacing a nonce range with zero-value self-transfers
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CancelReport {
+    pub nonces: Vec<CancelledNonce>,
+}
+
+impl CancelReport {
+    /// Nonces whose cancelling transaction was mined
+    pub fn replaced(&self) -> usize {
+        self.count(CancelOutcome::Replaced)
+    }
+
+    fn count(&self, outcome: CancelOutcome) -> usize {
+        self.nonces.iter().filter(|n| n.outcome == outcome).count()
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl fmt::Display for CancelReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== CANCEL SUMMARY =====")?;
+        for entry in &self.nonces {
+            let outcome = match entry.outcome {
+                CancelOutcome::Replaced => "replaced",
+                CancelOutcome::AlreadyMined => "already mined",
+                CancelOutcome::Rejected => "rejected",
+                CancelOutcome::Pending => "still pending",
+            };
+            write!(f, "{:?} nonce {}: {}", entry.wallet, entry.nonce, outcome)?;
+            if let Some(hash) = entry.hash {
+                write!(f, " ({:?})", hash)?;
+            }
+            if let Some(error) = &entry.error {
+                write!(f, ": {}", error)?;
+            }
+            writeln!(f)?;
+        }
+        write!(
+            f,
+            "Replaced {}, already mined {}, rejected {}, still pending {}",
+            self.replaced(),
+            self.count(CancelOutcome::AlreadyMined),
+            self.count(CancelOutcome::Rejected),
+            self.count(CancelOutcome::Pending)
+        )
+    }
+}
+
+/// Replaces every nonce in `nonces` of each configured wallet with a zero-value self-transfer,
+/// priced at the configured gas strategy scaled by `bump` so it outbids what is stuck there.
+/// Nonces the chain has already mined are skipped. Waits for the replacements and reports each nonce.
+#[instrument(name = "cancel", skip_all)]
+pub async fn cancel_nonces(config: &Config, nonces: RangeInclusive<u64>, bump: f64) -> Result<CancelReport> {
+    if nonces.is_empty() {
+        bail!("nonce range {}..={} is empty", nonces.start(), nonces.end());
+    }
+    if bump < 1.0 {
+        bail!("fee bump must be at least 1, got {}", bump);
+    }
+    let provider = Provider::new(FailoverTransport::connect(&config.rpc_urls, &config.rpc_headers).await?);
+    let chain_id = match config.chain_id {
+        Some(chain_id) => chain_id,
+        None => provider.get_chainid().await?.as_u64(),
+    };
+
+    let eip1559 = config.tx_type.resolve(&provider).await? == TxType::Eip1559;
+    let mut fee_mode = scale_fees(config.gas_strategy.fee_mode(&provider, eip1559).await?, bump);
+    if let Some(max_gas_price) = config.max_gas_price {
+        fee_mode = fee_mode.clamp(max_gas_price);
+    }
+    info!("Cancelling nonces {}..={} using {}", nonces.start(), nonces.end(), fee_mode);
+
+    let mut report = CancelReport::default();
+    let mut sent = Vec::new();
+    for wallet in &config.wallets {
+        let client = SignerMiddleware::new(provider.clone(), wallet.clone().with_chain_id(chain_id));
+        let address = client.address();
+        let mined = client
+            .get_transaction_count(address, Some(BlockNumber::Latest.into()))
+            .await?
+            .as_u64();
+
+        for nonce in nonces.clone() {
+            if nonce < mined {
+                report.nonces.push(CancelledNonce {
+                    wallet: address,
+                    nonce,
+                    outcome: CancelOutcome::AlreadyMined,
+                    hash: None,
+                    error: None,
+                });
+                continue;
+            }
+            let mut tx: TypedTransaction = match fee_mode {
+                FeeMode::Legacy { gas_price } => TransactionRequest::new().gas_price(gas_price).into(),
+                FeeMode::Eip1559 { max_fee, max_priority_fee } => Eip1559TransactionRequest::new()
+                    .max_fee_per_gas(max_fee)
+                    .max_priority_fee_per_gas(max_priority_fee)
+                    .into(),
+            };
+            tx.set_from(address);
+            tx.set_to(address);
+            tx.set_value(U256::zero());
+            tx.set_nonce(nonce);
+            tx.set_gas(CANCEL_GAS);
+            tx.set_chain_id(chain_id);
+
+            let signature = client.signer().sign_transaction(&tx).await?;
+            let (outcome, hash, error) = match client.send_raw_transaction(tx.rlp_signed(&signature)).await {
+                Ok(pending) => {
+                    info!("Nonce {} of {:?}: sent {:?}", nonce, address, pending.tx_hash());
+                    sent.push(report.nonces.len());
+                    (CancelOutcome::Pending, Some(pending.tx_hash()), None)
+                }
+                Err(e) => {
+                    warn!("Nonce {} of {:?}: rejected: {}", nonce, address, e);
+                    (CancelOutcome::Rejected, None, Some(e.to_string()))
+                }
+            };
+            report.nonces.push(CancelledNonce { wallet: address, nonce, outcome, hash, error });
+        }
+    }
+
+    if sent.is_empty() {
+        return Ok(report);
+    }
+    let hashes: Vec<H256> = sent.iter().filter_map(|&idx| report.nonces[idx].hash).collect();
+    let start_block = provider.get_block_number().await?.as_u64();
+    let progress = ConfirmProgress::default();
+    wait_for_receipts(&provider, &hashes, &HashMap::new(), start_block, config.poll_interval, config.confirm_timeout, &progress, &Shutdown::default())
+        .await?;
+
+    // A missing receipt either means the replacement is still queued or the original won the nonce
+    for idx in sent {
+        let entry = &mut report.nonces[idx];
+        let hash = entry.hash.expect("sent entries have a hash");
+        if provider.get_transaction_receipt(hash).await?.is_some() {
+            entry.outcome = CancelOutcome::Replaced;
+        } else {
+            let mined = provider
+                .get_transaction_count(entry.wallet, Some(BlockNumber::Latest.into()))
+                .await?
+                .as_u64();
+            if entry.nonce < mined {
+                entry.outcome = CancelOutcome::AlreadyMined;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Raises every fee component by `multiplier`
+fn scale_fees(fee_mode: FeeMode, multiplier: f64) -> FeeMode {
+    match fee_mode {
+        FeeMode::Legacy { gas_price } => FeeMode::Legacy { gas_price: apply_gas_multiplier(gas_price, multiplier) },
+        FeeMode::Eip1559 { max_fee, max_priority_fee } => FeeMode::Eip1559 {
+            max_fee: apply_gas_multiplier(max_fee, multiplier),
+            max_priority_fee: apply_gas_multiplier(max_priority_fee, multiplier),
+        },
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::{fmt, sync::Arc};
+use tracing::{info, instrument, warn};
+
+use crate::{benchmarker::Benchmarker, config::Config, report::{markdown_cell, RunReport}, shutdown::Shutdown, stats::LatencyStats};
+
+/// Outcome of the workload against one endpoint
+#[derive(Debug, Clone)]
+pub struct EndpointRun {
+    pub url: String,
+    /// Full report of the run, unless it could not complete
+    pub report: Option<RunReport>,
+    /// Why the run against this endpoint failed
+    pub error: Option<String>,
+}
+
+impl EndpointRun {
+    /// Failed sends out of those attempted
+    pub fn error_rate(&self) -> Option<f64> {
+        let report = self.report.as_ref()?;
+        let attempted = report.total_attempted();
+        (attempted > 0).then(|| report.total_failed as f64 / attempted as f64)
+    }
+}
+
+/// The same workload run against each endpoint in turn
+#[derive(Debug, Clone, Default)]
+pub struct ComparisonReport {
+    pub endpoints: Vec<EndpointRun>,
+}
+
+/// JSON shape of one endpoint's row in the comparison
+#[derive(Serialize)]
+struct ComparisonRow<'a> {
+    url: &'a str,
+    sent: Option<usize>,
+    failed: Option<usize>,
+    error_rate: Option<f64>,
+    send_tps: Option<f64>,
+    send_latency: Option<LatencyStats>,
+    error: Option<&'a str>,
+}
+
+impl ComparisonReport {
+    /// Renders one row per endpoint as pretty-printed JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let rows: Vec<ComparisonRow> = self
+            .endpoints
+            .iter()
+            .map(|run| ComparisonRow {
+                url: &run.url,
+                sent: run.report.as_ref().map(|report| report.total_sent),
+                failed: run.report.as_ref().map(|report| report.total_failed),
+                error_rate: run.error_rate(),
+                send_tps: run.report.as_ref().map(|report| report.send_tps()),
+                send_latency: run.report.as_ref().map(|report| report.send_latency),
+                error: run.error.as_deref(),
+            })
+            .collect();
+        serde_json::to_string_pretty(&rows)
+    }
+
+    /// Renders one table row per endpoint as Markdown, in the order the endpoints were run
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from(
+            "## Endpoint comparison\n\n| Endpoint | Sent | Failed | Error % | tx/s | p50 ms | p95 ms | p99 ms |\n\
+             | --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: |\n",
+        );
+        for run in &self.endpoints {
+            match &run.report {
+                Some(report) => {
+                    let latency = report.send_latency;
+                    md.push_str(&format!(
+                        "| {} | {} | {} | {:.2} | {:.2} | {:.1} | {:.1} | {:.1} |\n",
+                        markdown_cell(&run.url),
+                        report.total_sent,
+                        report.total_failed,
+                        run.error_rate().unwrap_or(0.0) * 100.0,
+                        report.send_tps(),
+                        latency.p50.as_secs_f64() * 1000.0,
+                        latency.p95.as_secs_f64() * 1000.0,
+                        latency.p99.as_secs_f64() * 1000.0,
+                    ));
+                }
+                None => md.push_str(&format!(
+                    "| {} | failed: {} | | | | | | |\n",
+                    markdown_cell(&run.url),
+                    markdown_cell(run.error.as_deref().unwrap_or("unknown error"))
+                )),
+            }
+        }
+        md
+    }
+}
+
+impl fmt::Display for ComparisonReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== ENDPOINT COMPARISON =====")?;
+        let width = self.endpoints.iter().map(|run| run.url.len()).max().unwrap_or(0).max("endpoint".len());
+        write!(
+            f,
+            "{:<width$}  {:>7}  {:>7}  {:>7}  {:>9}  {:>9}  {:>9}  {:>9}",
+            "endpoint", "sent", "failed", "error %", "tx/s", "p50 ms", "p95 ms", "p99 ms",
+        )?;
+        for run in &self.endpoints {
+            write!(f, "\n{:<width$}", run.url)?;
+            match (&run.report, &run.error) {
+                (Some(report), _) => {
+                    let latency = report.send_latency;
+                    write!(
+                        f,
+                        "  {:>7}  {:>7}  {:>7.2}  {:>9.2}  {:>9.1}  {:>9.1}  {:>9.1}",
+                        report.total_sent,
+                        report.total_failed,
+                        run.error_rate().unwrap_or(0.0) * 100.0,
+                        report.send_tps(),
+                        latency.p50.as_secs_f64() * 1000.0,
+                        latency.p95.as_secs_f64() * 1000.0,
+                        latency.p99.as_secs_f64() * 1000.0,
+                    )?;
+                }
+                (None, error) => write!(f, "  failed: {}", error.as_deref().unwrap_or("unknown error"))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs the configured workload against each of `urls` one after another, so the endpoints don't
+/// compete for the wallets or the network. Every run connects afresh and reads its starting
+/// nonces from the endpoint under test, since each may see different pending state. A run that
+/// fails is recorded and the comparison moves on; a shutdown stops after the current endpoint.
+#[instrument(name = "compare", skip_all)]
+pub async fn compare_endpoints(config: &Config, urls: &[String], shutdown: Arc<Shutdown>) -> Result<ComparisonReport> {
+    let mut comparison = ComparisonReport::default();
+    for url in urls {
+        if shutdown.is_requested() {
+            break;
+        }
+        info!("Benchmarking endpoint {}...", url);
+        let mut endpoint_config = config.clone();
+        endpoint_config.rpc_urls = vec![url.clone()];
+        // Nonces seen by one endpoint say nothing about another's pending state
+        endpoint_config.nonce_state_file = None;
+
+        let benchmarker = Benchmarker::new(endpoint_config).with_shutdown(shutdown.clone());
+        let run = match benchmarker.run().await {
+            Ok(report) => EndpointRun { url: url.clone(), report: Some(report), error: None },
+            Err(e) => {
+                warn!("Run against {} failed: {:#}", url, e);
+                EndpointRun { url: url.clone(), report: None, error: Some(format!("{:#}", e)) }
+            }
+        };
+        comparison.endpoints.push(run);
+    }
+    Ok(comparison)
+}
@@ -0,0 +1,258 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::ValueEnum;
+use ethers::{
+    providers::Middleware,
+    signers::LocalWallet,
+    types::{transaction::eip2930::AccessList, Address, BlockNumber, Bytes, U256},
+};
+use reqwest::header::HeaderMap;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::{
+    bundle::BundleRelay, confirm::RbfPolicy, fees::{gwei, GasStrategy}, funding::FundingConfig, probe::ProbeProfile,
+    ramp::RampProfile, rate::{Arrival, BurstPattern}, retry::RetryPolicy,
+};
+
+/// Transaction envelope to build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TxType {
+    /// EIP-1559 when the latest block reports a base fee, legacy otherwise
+    Auto,
+    Legacy,
+    Eip1559,
+}
+
+impl TxType {
+    /// Resolves `Auto` to EIP-1559 when the latest block has a base fee and to legacy otherwise,
+    /// logging which was picked and why. An explicit type is kept, with a warning when it doesn't
+    /// suit the chain.
+    pub async fn resolve<M: Middleware>(self, client: &M) -> Result<TxType>
+    where
+        M::Error: 'static,
+    {
+        let latest = client.get_block(BlockNumber::Latest).await.context("failed to read the latest block")?;
+        let base_fee = latest.and_then(|block| block.base_fee_per_gas);
+        match (self, base_fee) {
+            (TxType::Auto, Some(base_fee)) => {
+                info!("Latest block has a base fee of {} gwei, sending EIP-1559 transactions", gwei(base_fee));
+                Ok(TxType::Eip1559)
+            }
+            (TxType::Auto, None) => {
+                info!("Latest block has no base fee, sending legacy transactions");
+                Ok(TxType::Legacy)
+            }
+            (TxType::Eip1559, None) => {
+                warn!("EIP-1559 transactions were asked for, but the latest block has no base fee; the node may reject them");
+                Ok(TxType::Eip1559)
+            }
+            (TxType::Legacy, Some(_)) => {
+                info!("Sending legacy transactions on an EIP-1559 chain; each pays its full gas price however low the base fee is");
+                Ok(TxType::Legacy)
+            }
+            (tx_type, _) => Ok(tx_type),
+        }
+    }
+}
+
+/// Block tag the starting nonce of each wallet is read at
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NonceSource {
+    /// Nonce after the latest mined block; collides with transactions still pending from a previous run
+    Latest,
+    /// Nonce including transactions already in the mempool, so back-to-back runs continue where the last left off
+    Pending,
+}
+
+impl NonceSource {
+    /// Block tag to pass to `eth_getTransactionCount`
+    pub fn block_number(self) -> BlockNumber {
+        match self {
+            NonceSource::Latest => BlockNumber::Latest,
+            NonceSource::Pending => BlockNumber::Pending,
+        }
+    }
+}
+
+/// How each transaction is assigned the wallet that signs it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum WalletSelection {
+    /// Wallets take turns in order
+    RoundRobin,
+    /// A wallet drawn at random per transaction from the run's seed, like uneven multi-user traffic
+    Random,
+}
+
+/// Where the access list attached to each transaction comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessListSource {
+    /// The same list on every transaction
+    Fixed(AccessList),
+    /// Generated with `eth_createAccessList` once per recipient and calldata
+    Generated,
+}
+
+/// How much a run sends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Workload {
+    /// A fixed number of transactions
+    Count(u64),
+    /// A continuous stream until this much time has passed
+    Duration(Duration),
+}
+
+/// Everything a benchmark run needs, independent of how it was collected
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// RPC endpoints to submit transactions to, in failover order
+    pub rpc_urls: Vec<String>,
+    /// Extra headers sent with every HTTP request, e.g. an API key; keep values marked sensitive
+    /// so they stay out of debug output
+    pub rpc_headers: HeaderMap,
+    /// Chain id to sign for; fetched with `eth_chainId` when unset
+    pub chain_id: Option<u64>,
+    /// Wallets signing the transactions, used round-robin with independent nonce sequences
+    pub wallets: Vec<LocalWallet>,
+    /// Where each wallet's starting nonce is read from
+    pub nonce_source: NonceSource,
+    /// How transactions are spread over the wallets; each wallet still uses its own nonce sequence
+    pub wallet_selection: WalletSelection,
+    /// Block the starting nonces and balances are read at instead, e.g. the block a forked node is
+    /// pinned to
+    pub at_block: Option<BlockNumber>,
+    /// Master wallet that tops the benchmark wallets up before the run
+    pub funding: Option<FundingConfig>,
+    /// File the next unused nonce of each wallet is kept in between runs
+    pub nonce_state_file: Option<PathBuf>,
+    /// Number of transactions to send, or how long to keep sending
+    pub workload: Workload,
+    /// Transactions sent before the measured workload and excluded from its metrics
+    pub warmup: u64,
+    /// Step through increasing paced rates instead of the workload
+    pub ramp: Option<RampProfile>,
+    /// Search for the highest sustainable paced rate instead of the workload
+    pub probe: Option<ProbeProfile>,
+    /// Stream a fixed count of transactions through a producer/consumer pipeline instead of preparing them all first
+    pub pipeline: bool,
+    /// Signed transactions the pipeline buffers ahead of the senders
+    pub pipeline_buffer: usize,
+    /// How transaction fees are chosen
+    pub gas_strategy: GasStrategy,
+    /// Ceiling on the per-gas price in wei; computed fees above it are clamped
+    pub max_gas_price: Option<U256>,
+    /// Percentage each transaction's fees are raised by over the previous index, compounding, so
+    /// later nonces always pay more; clamped by `max_gas_price`
+    pub gas_escalation_pct: Option<f64>,
+    /// Refuse to run when the fee per gas is more than this factor above or below the node's
+    /// `eth_gasPrice`, which catches multiplier and unit mistakes; `None` skips the check, and
+    /// so does a `GasStrategy::Fixed` price
+    pub gas_sanity_factor: Option<f64>,
+    /// Run even when a safety check fails, with a warning instead
+    pub force: bool,
+    pub tx_type: TxType,
+    /// Sign with the chain id as EIP-155 requires. Turning it off makes legacy transactions
+    /// replayable on any chain, so it is only for test chains that reject replay-protected ones.
+    pub eip155: bool,
+    /// Destinations used round-robin; empty means self-send
+    pub recipients: Vec<Address>,
+    /// Value in wei attached to each transaction
+    pub value: U256,
+    /// Each transaction's value is drawn uniformly within this many percent (0-100) either side of `value`
+    pub value_jitter_pct: u64,
+    /// Token contract to send ERC-20 `transfer(recipient, value)` calls to instead of plain transfers
+    pub erc20: Option<Address>,
+    /// Calldata attached to each transaction
+    pub data: Bytes,
+    /// Access list to attach, turning legacy transactions into EIP-2930 ones
+    pub access_list: Option<AccessListSource>,
+    /// Explicit gas limit; when `None` it is estimated for calldata or contract recipients
+    pub gas_limit: Option<u64>,
+    /// Percentage added on top of estimated gas limits
+    pub gas_buffer_pct: u64,
+    /// Maximum number of sends in flight at once; 0 means unbounded
+    pub max_concurrency: usize,
+    /// Tune the concurrency limit to the endpoint instead of fixing it; `max_concurrency` becomes the ceiling
+    pub adaptive_concurrency: bool,
+    /// Target send rate in tx/s, paced with a token bucket; `None` sends as fast as possible
+    pub rate: Option<f64>,
+    /// How paced sends are spread out around `rate`
+    pub arrival: Arrival,
+    /// Send the batch in bursts separated by pauses instead of all at once
+    pub burst: Option<BurstPattern>,
+    /// Seed for every random choice, such as value jitter and Poisson arrivals; `None` draws a fresh
+    /// one each run, which the report records
+    pub seed: Option<u64>,
+    /// Retry behaviour for transient send failures
+    pub retry: RetryPolicy,
+    /// Longest a single send may take before it is counted as a timeout; `None` waits indefinitely
+    pub send_timeout: Option<Duration>,
+    /// Raw transactions packed into each JSON-RPC batch request when replaying; 1 sends them one by one
+    pub batch_size: usize,
+    /// Submit a fixed-count batch to this relay in `eth_sendBundle` calls instead of sending each
+    /// transaction to the RPC endpoint
+    pub bundle: Option<BundleRelay>,
+    /// Build and sign transactions but never broadcast them
+    pub dry_run: bool,
+    /// Wait for receipts after the send phase
+    pub confirm: bool,
+    /// Show how many receipts are in while waiting for them
+    pub progress: bool,
+    /// Keep the outcome of every send in `RunReport::records`
+    pub records: bool,
+    /// Resend failed transactions after the send phase when later nonces were accepted
+    pub fill_gaps: bool,
+    /// Rebroadcast transactions that stay unconfirmed with bumped fees
+    pub rbf: Option<RbfPolicy>,
+    /// Delay between receipt polling rounds
+    pub poll_interval: Duration,
+    /// How long to wait for receipts before counting the rest as unconfirmed
+    pub confirm_timeout: Duration,
+    /// Blocks a transaction must be under, counting its own, before it counts as confirmed; 1 means
+    /// included
+    pub confirmations: u64,
+    /// Broadcast transactions reorged out while waiting for depth again, instead of only counting them
+    pub rebroadcast_reorged: bool,
+    /// After sending, wait up to this long for the wallets' nonces to catch up with what was sent
+    pub cooldown: Option<Duration>,
+    /// Read the node's mempool size with `txpool_status` before and after sending
+    pub inspect_mempool: bool,
+}
+
+impl Config {
+    /// Transactions a fixed-count run sends; `None` when the run sends for a duration, ramps or
+    /// probes instead and so has no set number
+    pub fn fixed_count(&self) -> Option<u64> {
+        match self.workload {
+            Workload::Count(count) if self.ramp.is_none() && self.probe.is_none() => Some(count),
+            _ => None,
+        }
+    }
+}
+
+/// Reads recipient addresses from a file, one per line.
+/// Blank lines and `#` comments are ignored, and an invalid address is reported with its line number.
+pub fn load_recipients(path: &Path) -> Result<Vec<Address>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read recipients file {}", path.display()))?;
+
+    let mut recipients = Vec::new();
+    for (line_idx, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let address = line.parse::<Address>().map_err(|e| {
+            anyhow!("{}:{}: invalid address {:?}: {}", path.display(), line_idx + 1, line, e)
+        })?;
+        recipients.push(address);
+    }
+
+    if recipients.is_empty() {
+        bail!("recipients file {} contains no addresses", path.display());
+    }
+    Ok(recipients)
+}
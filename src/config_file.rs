@@ -0,0 +1,139 @@
+use anyhow::{bail, Context, Result};
+use clap::{parser::ValueSource, Arg, ArgMatches, Command};
+use std::{ffi::OsString, fs, path::Path};
+use toml::Value;
+
+/// Flags read from a `--config` file, ready to go in front of the command line's own
+#[derive(Debug, Clone, Default)]
+pub struct FileArgs {
+    pub args: Vec<OsString>,
+    /// Keys that don't name a flag; they are skipped
+    pub unknown: Vec<String>,
+}
+
+/// Reads a TOML file of top-level flag values, e.g. `rpc-url = "http://..."` or `max_concurrency = 50`,
+/// and turns it into command-line arguments for `command`. Keys use the long flag name with either
+/// dashes or underscores; arrays repeat the flag and `true` sets a switch. Flags already given on the
+/// command line or through their environment variable, as recorded in `matches`, are left out so
+/// they take precedence over the file, and so are flags that conflict with one of those, e.g. a
+/// file's `count` when the command line asks for a `--duration`.
+pub fn load(path: &Path, command: &Command, matches: &ArgMatches) -> Result<FileArgs> {
+    let contents = fs::read_to_string(path).with_context(|| format!("failed to read config file {}", path.display()))?;
+    let table: toml::Table =
+        toml::from_str(&contents).with_context(|| format!("config file {} is not valid TOML", path.display()))?;
+
+    let given: Vec<&Arg> = command.get_arguments().filter(|arg| given(matches, arg)).collect();
+    let mut file_args = FileArgs::default();
+    for (key, value) in table {
+        let long = key.replace('_', "-");
+        let Some(arg) = command.get_arguments().find(|arg| arg.get_long() == Some(long.as_str())) else {
+            file_args.unknown.push(key);
+            continue;
+        };
+        let id = arg.get_id().as_str();
+        if id == "config" {
+            file_args.unknown.push(key);
+            continue;
+        }
+        if given.iter().any(|given| given.get_id() == arg.get_id() || conflict(command, arg, given)) {
+            continue;
+        }
+
+        let flag = OsString::from(format!("--{}", long));
+        let takes_value = arg.get_action().takes_values();
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            match (value, takes_value) {
+                (Value::Boolean(true), false) => file_args.args.push(flag.clone()),
+                (Value::Boolean(false), false) => {}
+                (Value::String(value), true) => file_args.args.extend([flag.clone(), value.into()]),
+                (value @ (Value::Integer(_) | Value::Float(_) | Value::Boolean(_)), true) => {
+                    file_args.args.extend([flag.clone(), value.to_string().into()])
+                }
+                (value, _) => bail!("config file {}: {:?} can't be set to {}", path.display(), key, value),
+            }
+        }
+    }
+    Ok(file_args)
+}
+
+/// Whether the command line or the environment set `arg`
+fn given(matches: &ArgMatches, arg: &Arg) -> bool {
+    matches!(matches.value_source(arg.get_id().as_str()), Some(ValueSource::CommandLine | ValueSource::EnvVariable))
+}
+
+/// Whether either argument conflicts with the other; clap only records a conflict on the argument
+/// that declares it
+fn conflict(command: &Command, a: &Arg, b: &Arg) -> bool {
+    let conflicts_with = |arg: &Arg, other: &Arg| {
+        command.get_arg_conflicts_with(arg).iter().any(|conflict| conflict.get_id() == other.get_id())
+    };
+    conflicts_with(a, b) || conflicts_with(b, a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ArgAction;
+
+    fn command() -> Command {
+        Command::new("dump")
+            .arg(Arg::new("config").long("config"))
+            .arg(Arg::new("rpc_url").long("rpc-url"))
+            .arg(Arg::new("count").long("count").conflicts_with("duration"))
+            .arg(Arg::new("duration").long("duration"))
+            .arg(Arg::new("rpc_header").long("rpc-header").action(ArgAction::Append))
+            .arg(Arg::new("confirm").long("confirm").action(ArgAction::SetTrue))
+    }
+
+    /// Loads `contents` as a config file under a command line of `args`
+    fn load_with(name: &str, contents: &str, args: &[&str]) -> Result<FileArgs> {
+        let path = std::env::temp_dir().join(format!("config-file-{}-{}.toml", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        let matches = command().try_get_matches_from([&"dump"].into_iter().chain(args)).unwrap();
+        let file_args = load(&path, &command(), &matches);
+        fs::remove_file(&path).unwrap();
+        file_args
+    }
+
+    fn strings(file_args: &FileArgs) -> Vec<&str> {
+        file_args.args.iter().map(|arg| arg.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn file_values_fill_in_only_what_the_command_line_leaves_out() {
+        let file = r#"
+            rpc-url = "http://file"
+            count = 1000
+            rpc_header = ["x-a: 1", "x-b: 2"]
+            confirm = true
+            colour = "blue"
+            config = "other.toml"
+        "#;
+        let file_args = load_with("precedence", file, &[]).unwrap();
+        assert_eq!(
+            strings(&file_args),
+            ["--confirm", "--count", "1000", "--rpc-url", "http://file", "--rpc-header", "x-a: 1", "--rpc-header", "x-b: 2"]
+        );
+        assert_eq!(file_args.unknown, ["colour", "config"]);
+
+        let file_args = load_with("overridden", file, &["--rpc-url", "http://cli"]).unwrap();
+        assert!(!strings(&file_args).contains(&"--rpc-url"));
+        assert!(load_with("false-switch", "confirm = false", &[]).unwrap().args.is_empty());
+        assert!(load_with("table", "count = { n = 1 }", &[]).is_err());
+    }
+
+    #[test]
+    fn file_values_conflicting_with_the_command_line_are_dropped() {
+        let file_args = load_with("conflict", "count = 1000
+confirm = true", &["--duration", "30s"]).unwrap();
+        assert_eq!(strings(&file_args), ["--confirm"]);
+
+        // The conflict is declared on `count` only, but holds the other way round too
+        let file_args = load_with("reverse-conflict", "duration = \"30s\"", &["--count", "10"]).unwrap();
+        assert!(file_args.args.is_empty());
+    }
+}
@@ -0,0 +1,987 @@
+use anyhow::{Context, Result};
+use ethers::{
+    providers::{Middleware, MiddlewareError, Provider, StreamExt, Ws},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockId, BlockNumber, Bytes, TransactionReceipt, TransactionRequest,
+        H256, U256,
+    },
+};
+use futures::future::join_all;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    future::Future,
+    io::{IsTerminal, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::time::{sleep, timeout as timeout_after};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    benchmarker::{sign_with, Client},
+    calldata::decode_revert_reason,
+    shutdown::Shutdown, send_error::SendError, stats::LatencyStats,
+    transport::FailoverTransport,
+};
+
+/// Most reverted transactions replayed with `eth_call` to find out why they failed
+const MAX_REVERT_REPLAYS: usize = 100;
+
+/// How often the live confirmation line is redrawn on a terminal
+const PROGRESS_REDRAW: Duration = Duration::from_millis(250);
+
+/// How often confirmation progress is logged when stdout isn't a terminal
+const PROGRESS_LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Share of its gas limit a transaction may use before it counts as close to running out
+pub const NEAR_LIMIT_UTILIZATION: f64 = 0.95;
+
+/// Smallest fee increase nodes accept for a same-nonce replacement
+pub const MIN_REPLACEMENT_BUMP_PCT: u64 = 10;
+
+/// When and how unconfirmed transactions are rebroadcast with higher fees
+#[derive(Debug, Clone, Copy)]
+pub struct RbfPolicy {
+    /// How long a transaction may go without a receipt before it is replaced
+    pub after: Duration,
+    /// Percentage every fee component is raised by on each replacement, at least 10
+    pub bump_pct: u64,
+    /// Most replacements per transaction
+    pub max_attempts: u32,
+    /// Replacements are not bumped past this per-gas price
+    pub max_gas_price: Option<U256>,
+}
+
+/// A broadcast transaction as signed, kept so it can be replaced with higher fees
+#[derive(Debug, Clone)]
+pub struct SentTx {
+    pub wallet_idx: usize,
+    pub tx: TypedTransaction,
+    pub hash: H256,
+}
+
+/// Outcome of waiting for receipts of the sent transactions
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfirmationReport {
+    /// Transactions mined with a successful status
+    pub mined: usize,
+    /// Transactions mined but reverted
+    pub reverted: usize,
+    /// Transactions without a receipt, or not yet deep enough, when the timeout expired
+    pub unconfirmed: usize,
+    /// Blocks a transaction needed to be under, counting its own, to count as confirmed; 0 or 1
+    /// means included
+    pub depth: u64,
+    /// Times an included transaction was seen leaving its block while waiting for depth, whether it
+    /// then turned up in another block or not
+    pub reorgs: usize,
+    /// Transactions seen included whose receipt then disappeared before they were deep enough
+    pub reorged_out: usize,
+    /// Reorged-out transactions broadcast again
+    pub rebroadcast: usize,
+    /// Average number of blocks between the start of the send phase and inclusion
+    pub avg_blocks_to_confirm: f64,
+    /// Time from the node accepting each transaction to its receipt being seen, so it includes mempool
+    /// propagation and is only as precise as the poll interval. Absent when no send times were known.
+    pub confirm_latency: Option<LatencyStats>,
+    /// Transactions that were only included after at least one fee bump
+    pub bumped: usize,
+    /// Fees actually paid by the included transactions: gas used times effective gas price
+    pub gas_cost: U256,
+    /// Why reverted transactions failed, as replayed with `eth_call`, with how many failed that way
+    pub revert_reasons: BTreeMap<String, usize>,
+    /// How much of their gas limit the included transactions used. Absent when no limits were known.
+    pub gas_efficiency: Option<GasEfficiency>,
+    /// Receipt of each included transaction by the hash it was first sent under, for per-transaction records
+    #[serde(skip)]
+    pub inclusions: HashMap<H256, Inclusion>,
+    /// Set when a stop cut the wait short; what was still pending then counts as unconfirmed
+    pub interrupted: bool,
+}
+
+/// What the receipt of an included transaction says
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inclusion {
+    /// Hash the transaction was mined under, a replacement's when its fees were bumped
+    pub tx_hash: H256,
+    pub block_number: Option<u64>,
+    pub block_hash: Option<H256>,
+    pub gas_used: Option<U256>,
+    pub success: bool,
+}
+
+/// What is known about an accepted transaction when its receipt comes in
+#[derive(Debug, Clone, Copy)]
+pub struct Submission {
+    /// When the node accepted it
+    pub at: Instant,
+    /// Gas limit it was signed with
+    pub gas_limit: Option<U256>,
+}
+
+/// Gas used against the gas limit set, over the included transactions
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct GasEfficiency {
+    /// Transactions whose limit was known
+    pub samples: usize,
+    /// Mean share of the gas limit used (0.0-1.0)
+    pub avg_utilization: f64,
+    /// Lowest share of the gas limit used
+    pub min_utilization: f64,
+    /// Transactions that used at least `NEAR_LIMIT_UTILIZATION` of their limit
+    pub near_limit: usize,
+}
+
+impl GasEfficiency {
+    /// Summarises `(gas used, gas limit)` pairs; limits must be non-zero
+    fn from_samples(samples: &[(U256, U256)]) -> Self {
+        let utilizations: Vec<f64> =
+            samples.iter().map(|(used, limit)| used.as_u128() as f64 / limit.as_u128() as f64).collect();
+        Self {
+            samples: utilizations.len(),
+            avg_utilization: utilizations.iter().sum::<f64>() / utilizations.len() as f64,
+            min_utilization: utilizations.iter().copied().fold(f64::INFINITY, f64::min),
+            near_limit: utilizations.iter().filter(|&&utilization| utilization >= NEAR_LIMIT_UTILIZATION).count(),
+        }
+    }
+}
+
+/// Running totals behind the averages and distributions of a [`ConfirmationReport`]
+#[derive(Default)]
+struct Tally {
+    blocks: u64,
+    latencies: Vec<Duration>,
+    /// Gas used and gas limit of each inclusion whose limit is known
+    gas: Vec<(U256, U256)>,
+}
+
+impl ConfirmationReport {
+    /// Counts the inclusion of the transaction first sent as `hash`, `start_block` being the chain tip
+    /// right before sending and `submission` what is known of the transaction as sent. Returns
+    /// whether the transaction reverted.
+    fn record(
+        &mut self,
+        hash: H256,
+        receipt: &TransactionReceipt,
+        start_block: u64,
+        submission: Option<Submission>,
+        tally: &mut Tally,
+    ) -> bool {
+        let reverted = self.include(hash, receipt);
+        let block = receipt.block_number.map(|b| b.as_u64()).unwrap_or(start_block);
+        tally.blocks += block.saturating_sub(start_block);
+        if let Some(submission) = submission {
+            tally.latencies.push(submission.at.elapsed());
+            if let (Some(gas_used), Some(gas_limit)) = (receipt.gas_used, submission.gas_limit.filter(|limit| !limit.is_zero())) {
+                tally.gas.push((gas_used, gas_limit));
+            }
+        }
+        self.gas_cost += receipt.gas_used.unwrap_or_default() * receipt.effective_gas_price.unwrap_or_default();
+        reverted
+    }
+
+    /// Counts the transaction first sent as `hash` as mined or reverted by its receipt, without
+    /// the figures only the first inclusion adds to. Returns whether it reverted.
+    fn include(&mut self, hash: H256, receipt: &TransactionReceipt) -> bool {
+        let reverted = !receipt.status.map(|s| s.low_u64() == 1).unwrap_or(false);
+        self.inclusions.insert(
+            hash,
+            Inclusion {
+                tx_hash: receipt.transaction_hash,
+                block_number: receipt.block_number.map(|b| b.as_u64()),
+                block_hash: receipt.block_hash,
+                gas_used: receipt.gas_used,
+                success: !reverted,
+            },
+        );
+        if reverted {
+            self.reverted += 1;
+        } else {
+            self.mined += 1;
+        }
+        reverted
+    }
+
+    /// Takes back the inclusion of the transaction first sent as `hash`
+    fn uncount(&mut self, hash: H256) {
+        match self.inclusions.remove(&hash) {
+            Some(inclusion) if inclusion.success => self.mined -= 1,
+            Some(_) => self.reverted -= 1,
+            None => {}
+        }
+    }
+
+    fn finish(&mut self, unconfirmed: usize, tally: Tally) {
+        self.unconfirmed = unconfirmed;
+        let included = self.mined + self.reverted;
+        if included > 0 {
+            self.avg_blocks_to_confirm = tally.blocks as f64 / included as f64;
+        }
+        if !tally.latencies.is_empty() {
+            self.confirm_latency = Some(LatencyStats::from_samples(&tally.latencies));
+        }
+        if !tally.gas.is_empty() {
+            self.gas_efficiency = Some(GasEfficiency::from_samples(&tally.gas));
+        }
+    }
+}
+
+/// Receipts seen so far while confirming, shared with whatever displays the progress
+#[derive(Debug, Default)]
+pub struct ConfirmProgress {
+    expected: usize,
+    mined: AtomicUsize,
+    reverted: AtomicUsize,
+}
+
+impl ConfirmProgress {
+    /// Progress of confirming `expected` transactions
+    pub fn new(expected: usize) -> Self {
+        Self { expected, ..Self::default() }
+    }
+
+    fn record(&self, reverted: bool) {
+        let counter = if reverted { &self.reverted } else { &self.mined };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Transactions mined successfully, reverted and still without a receipt
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let mined = self.mined.load(Ordering::Relaxed);
+        let reverted = self.reverted.load(Ordering::Relaxed);
+        (mined, reverted, self.expected.saturating_sub(mined + reverted))
+    }
+}
+
+impl fmt::Display for ConfirmProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mined, reverted, pending) = self.counts();
+        write!(f, "confirmed: {}/{}, reverted: {}, pending: {}", mined, self.expected, reverted, pending)
+    }
+}
+
+/// Runs `confirming` while showing `progress`: a line redrawn in place when stdout is a terminal,
+/// periodic log lines otherwise
+pub async fn with_progress<T>(progress: Arc<ConfirmProgress>, confirming: impl Future<Output = T>) -> T {
+    let (stop, stopped) = tokio::sync::oneshot::channel();
+    let display = tokio::spawn(show_progress(progress, stopped));
+    let result = confirming.await;
+    let _ = stop.send(());
+    let _ = display.await;
+    result
+}
+
+async fn show_progress(progress: Arc<ConfirmProgress>, stop: tokio::sync::oneshot::Receiver<()>) {
+    let terminal = std::io::stdout().is_terminal();
+    let interval = if terminal { PROGRESS_REDRAW } else { PROGRESS_LOG_INTERVAL };
+    tokio::pin!(stop);
+    loop {
+        let stopping = tokio::select! {
+            _ = &mut stop => true,
+            _ = sleep(interval) => false,
+        };
+        if terminal {
+            // Clear the line first, since it can only get shorter as pending drains
+            let mut stdout = std::io::stdout().lock();
+            let _ = write!(stdout, "\r\x1b[2K{}", progress);
+            if stopping {
+                let _ = writeln!(stdout);
+            }
+            let _ = stdout.flush();
+        } else if !stopping {
+            info!("Receipts {}", progress);
+        }
+        if stopping {
+            break;
+        }
+    }
+}
+
+/// Polls receipts for `hashes` until all are mined or `timeout` expires.
+/// `start_block` is the chain tip observed right before sending, used for blocks-to-confirmation,
+/// and `submissions` holds when each hash was accepted and its gas limit, for latency and gas efficiency.
+/// A `shutdown` stops the wait at the next poll.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "confirm", skip_all)]
+pub async fn wait_for_receipts<M: Middleware>(
+    client: &M,
+    hashes: &[H256],
+    submissions: &HashMap<H256, Submission>,
+    start_block: u64,
+    poll_interval: Duration,
+    timeout: Duration,
+    progress: &ConfirmProgress,
+    shutdown: &Shutdown,
+) -> Result<ConfirmationReport> {
+    let deadline = Instant::now() + timeout;
+    let mut pending: Vec<H256> = hashes.to_vec();
+    let mut report = ConfirmationReport::default();
+    let mut tally = Tally::default();
+    let mut reverted = Vec::new();
+
+    info!("Waiting for {} receipts...", pending.len());
+    while !pending.is_empty() {
+        // Query every pending receipt in parallel, keeping the ones still missing for the next round
+        let receipts = join_all(pending.iter().map(|hash| client.get_transaction_receipt(*hash))).await;
+        let mut still_pending = Vec::with_capacity(pending.len());
+        for (hash, receipt) in pending.into_iter().zip(receipts) {
+            match receipt {
+                Ok(Some(receipt)) => {
+                    let failed = report.record(hash, &receipt, start_block, submissions.get(&hash).copied(), &mut tally);
+                    progress.record(failed);
+                    if failed {
+                        reverted.push(receipt);
+                    }
+                }
+                Ok(None) => still_pending.push(hash),
+                Err(e) => {
+                    debug!(?hash, error = %e, "failed to fetch receipt");
+                    still_pending.push(hash);
+                }
+            }
+        }
+        pending = still_pending;
+
+        if pending.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        if !pause(poll_interval, shutdown).await {
+            report.interrupted = true;
+            break;
+        }
+    }
+
+    report.finish(pending.len(), tally);
+    report.revert_reasons = explain_reverts(client, &reverted).await;
+    Ok(report)
+}
+
+/// Sleeps for `duration` unless a stop is requested first; returns whether it slept the whole time
+async fn pause(duration: Duration, shutdown: &Shutdown) -> bool {
+    tokio::select! {
+        _ = sleep(duration) => true,
+        _ = shutdown.wait() => false,
+    }
+}
+
+/// Waits for receipts the cheapest way the endpoint allows: by watching new blocks over a WebSocket
+/// subscription, or by polling every pending receipt over HTTP or when the subscription is refused
+#[allow(clippy::too_many_arguments)]
+pub async fn confirm_receipts(
+    provider: &Provider<FailoverTransport>,
+    hashes: &[H256],
+    submissions: &HashMap<H256, Submission>,
+    start_block: u64,
+    poll_interval: Duration,
+    timeout: Duration,
+    progress: &ConfirmProgress,
+    shutdown: &Shutdown,
+) -> Result<ConfirmationReport> {
+    if let Some(ws) = provider.as_ref().ws() {
+        let watching = watch_for_receipts(ws, provider, hashes, submissions, start_block, poll_interval, timeout, progress, shutdown);
+        match watching.await {
+            Ok(report) => return Ok(report),
+            Err(e) => warn!("Falling back to polling receipts: {:#}", e),
+        }
+    }
+    wait_for_receipts(provider, hashes, submissions, start_block, poll_interval, timeout, progress, shutdown).await
+}
+
+/// Like [`wait_for_receipts`], but instead of polling every pending receipt it subscribes to new heads
+/// and looks for the sent hashes in each new block, fetching receipts only for the ones it finds.
+/// Blocks mined before the subscription started, or whose heads were missed, are caught up on by
+/// number. Should the subscription end early, the chain tip is polled every `poll_interval` instead.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "confirm", skip_all)]
+pub async fn watch_for_receipts(
+    ws: Ws,
+    client: &Provider<FailoverTransport>,
+    hashes: &[H256],
+    submissions: &HashMap<H256, Submission>,
+    start_block: u64,
+    poll_interval: Duration,
+    timeout: Duration,
+    progress: &ConfirmProgress,
+    shutdown: &Shutdown,
+) -> Result<ConfirmationReport> {
+    let deadline = Instant::now() + timeout;
+    let ws = Provider::new(ws);
+    let mut heads = Some(ws.subscribe_blocks().await.context("failed to subscribe to new heads")?);
+    let mut pending: HashSet<H256> = hashes.iter().copied().collect();
+    // Found in a block, but fetching the receipt failed; retried after the next head
+    let mut unfetched: Vec<H256> = Vec::new();
+    let mut report = ConfirmationReport::default();
+    let mut tally = Tally::default();
+    let mut reverted = Vec::new();
+    let mut next_block = start_block + 1;
+    let mut tip = client.get_block_number().await?.as_u64();
+
+    info!("Watching new blocks for {} receipts...", pending.len());
+    loop {
+        // Check every block since the last one, including any whose head was missed
+        while next_block <= tip && !pending.is_empty() {
+            match client.get_block(next_block).await {
+                Ok(Some(block)) => {
+                    unfetched.extend(block.transactions.iter().filter(|hash| pending.remove(*hash)));
+                    next_block += 1;
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    debug!(block = next_block, error = %e, "failed to fetch block");
+                    break;
+                }
+            }
+        }
+        let found = std::mem::take(&mut unfetched);
+        let receipts = join_all(found.iter().map(|hash| client.get_transaction_receipt(*hash))).await;
+        for (hash, receipt) in found.into_iter().zip(receipts) {
+            match receipt {
+                Ok(Some(receipt)) => {
+                    let failed = report.record(hash, &receipt, start_block, submissions.get(&hash).copied(), &mut tally);
+                    progress.record(failed);
+                    if failed {
+                        reverted.push(receipt);
+                    }
+                }
+                Ok(None) => unfetched.push(hash),
+                Err(e) => {
+                    debug!(?hash, error = %e, "failed to fetch receipt");
+                    unfetched.push(hash);
+                }
+            }
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if (pending.is_empty() && unfetched.is_empty()) || remaining.is_zero() {
+            break;
+        }
+        if shutdown.is_requested() {
+            report.interrupted = true;
+            break;
+        }
+        let next_tip = match heads.as_mut() {
+            Some(stream) => {
+                let head = tokio::select! {
+                    head = timeout_after(remaining, stream.next()) => head,
+                    _ = shutdown.wait() => {
+                        report.interrupted = true;
+                        break;
+                    }
+                };
+                match head {
+                    Ok(Some(head)) => head.number.map(|number| number.as_u64()),
+                    Ok(None) => {
+                        warn!("Block subscription ended, polling the chain tip instead");
+                        heads = None;
+                        None
+                    }
+                    Err(_) => break,
+                }
+            }
+            None => {
+                if !pause(poll_interval.min(remaining), shutdown).await {
+                    report.interrupted = true;
+                    break;
+                }
+                client.get_block_number().await.ok().map(|number| number.as_u64())
+            }
+        };
+        tip = tip.max(next_tip.unwrap_or(tip));
+    }
+
+    report.finish(pending.len() + unfetched.len(), tally);
+    report.revert_reasons = explain_reverts(client, &reverted).await;
+    Ok(report)
+}
+
+/// Waits until `deadline` for every transaction `report` counts as included to be `depth` blocks
+/// deep: the chain tip is at least `depth - 1` blocks past its inclusion block. Each poll re-reads
+/// the receipts, so a transaction that left the block it was seen in is caught as a reorg: one that
+/// turns up in another block waits from there, and one whose receipt is gone was reorged out. When
+/// `rebroadcast` holds its raw bytes, a reorged-out transaction is sent again and waited for like
+/// the rest; otherwise it is counted apart. Those still too shallow at the deadline become
+/// unconfirmed, as are those still waiting when `shutdown` stops the wait. Latency and gas figures
+/// keep measuring the first inclusion.
+#[instrument(name = "depth", skip_all)]
+pub async fn wait_for_depth<M: Middleware>(
+    client: &M,
+    report: &mut ConfirmationReport,
+    depth: u64,
+    rebroadcast: Option<&HashMap<H256, Bytes>>,
+    poll_interval: Duration,
+    deadline: Instant,
+    shutdown: &Shutdown,
+) -> Result<()>
+where
+    M::Error: 'static,
+{
+    report.depth = depth;
+    let mut shallow: Vec<H256> = report.inclusions.keys().copied().collect();
+    // Reorged out and broadcast again, by the hash first sent under and the hash mined under
+    let mut resent: Vec<(H256, H256)> = Vec::new();
+    info!("Waiting for {} transactions to be {} blocks deep...", shallow.len(), depth);
+    while !shallow.is_empty() || !resent.is_empty() {
+        let tip = client.get_block_number().await?.as_u64();
+        let mined_as: Vec<H256> = shallow.iter().map(|hash| report.inclusions[hash].tx_hash).collect();
+        let receipts = join_all(mined_as.iter().map(|hash| client.get_transaction_receipt(*hash))).await;
+        let mut still_shallow = Vec::with_capacity(shallow.len());
+        for ((hash, tx_hash), receipt) in shallow.into_iter().zip(mined_as).zip(receipts) {
+            match receipt {
+                Ok(Some(receipt)) => {
+                    let inclusion = report.inclusions.get_mut(&hash).expect("only included transactions are tracked");
+                    if receipt.block_hash != inclusion.block_hash {
+                        debug!(?hash, from = ?inclusion.block_number, to = ?receipt.block_number, "included in another block");
+                        report.reorgs += 1;
+                        inclusion.block_number = receipt.block_number.map(|number| number.as_u64());
+                        inclusion.block_hash = receipt.block_hash;
+                    }
+                    if inclusion.block_number.is_none_or(|block| tip + 1 < block + depth) {
+                        still_shallow.push(hash);
+                    }
+                }
+                Ok(None) => {
+                    report.reorgs += 1;
+                    report.uncount(hash);
+                    let raw_tx = rebroadcast.and_then(|raw_txs| raw_txs.get(&hash));
+                    if let Some(raw_tx) = raw_tx {
+                        if resend(client, raw_tx).await {
+                            warn!(?hash, "transaction was reorged out after being included, broadcast it again");
+                            report.rebroadcast += 1;
+                            resent.push((hash, tx_hash));
+                            continue;
+                        }
+                    }
+                    warn!(?hash, "transaction was reorged out after being included");
+                    report.reorged_out += 1;
+                }
+                Err(e) => {
+                    debug!(?hash, error = %e, "failed to fetch receipt");
+                    still_shallow.push(hash);
+                }
+            }
+        }
+        // Rebroadcast transactions count again once they are back in a block
+        let receipts = join_all(resent.iter().map(|(_, tx_hash)| client.get_transaction_receipt(*tx_hash))).await;
+        let mut still_resent = Vec::with_capacity(resent.len());
+        for ((hash, tx_hash), receipt) in resent.into_iter().zip(receipts) {
+            match receipt {
+                Ok(Some(receipt)) => {
+                    debug!(?hash, block = ?receipt.block_number, "rebroadcast transaction included again");
+                    report.include(hash, &receipt);
+                    still_shallow.push(hash);
+                }
+                Ok(None) => still_resent.push((hash, tx_hash)),
+                Err(e) => {
+                    debug!(?hash, error = %e, "failed to fetch receipt");
+                    still_resent.push((hash, tx_hash));
+                }
+            }
+        }
+        shallow = still_shallow;
+        resent = still_resent;
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if (shallow.is_empty() && resent.is_empty()) || remaining.is_zero() {
+            break;
+        }
+        if !pause(poll_interval.min(remaining), shutdown).await {
+            report.interrupted = true;
+            break;
+        }
+    }
+    for hash in shallow {
+        report.uncount(hash);
+        report.unconfirmed += 1;
+    }
+    // Rebroadcast but never seen in a block again
+    report.reorged_out += resent.len();
+    Ok(())
+}
+
+/// Broadcasts a reorged-out transaction again. Returns whether the node has it now, which it
+/// may well have had already, having put it back in its mempool after the reorg.
+async fn resend<M: Middleware>(client: &M, raw_tx: &Bytes) -> bool {
+    match client.send_raw_transaction(raw_tx.clone()).await {
+        Ok(_) => true,
+        Err(e) => match SendError::from_provider(&e) {
+            SendError::AlreadyKnown(_) => true,
+            error => {
+                warn!(error = %error, "failed to rebroadcast a reorged-out transaction");
+                false
+            }
+        },
+    }
+}
+
+/// Replays reverted transactions with `eth_call` at the block they were mined in and groups them
+/// by decoded revert reason. Only the first `MAX_REVERT_REPLAYS` are replayed.
+async fn explain_reverts<M: Middleware>(client: &M, reverted: &[TransactionReceipt]) -> BTreeMap<String, usize> {
+    let mut reasons = BTreeMap::new();
+    if reverted.is_empty() {
+        return reasons;
+    }
+    info!("Replaying {} reverted transactions to decode their revert reasons...", reverted.len().min(MAX_REVERT_REPLAYS));
+    let replays = reverted.iter().take(MAX_REVERT_REPLAYS).map(|receipt| revert_reason(client, receipt));
+    for reason in join_all(replays).await {
+        *reasons.entry(reason).or_default() += 1;
+    }
+    if reverted.len() > MAX_REVERT_REPLAYS {
+        reasons.insert("not replayed".to_string(), reverted.len() - MAX_REVERT_REPLAYS);
+    }
+    reasons
+}
+
+/// Why a reverted transaction failed, from re-running it as a call on top of the block it was mined in
+async fn revert_reason<M: Middleware>(client: &M, receipt: &TransactionReceipt) -> String {
+    let tx = match client.get_transaction(receipt.transaction_hash).await {
+        Ok(Some(tx)) => tx,
+        Ok(None) => return "transaction not found for replay".to_string(),
+        Err(e) => return format!("replay failed: {}", e),
+    };
+    // Running out of gas leaves no revert data, but uses up the whole limit
+    if receipt.gas_used == Some(tx.gas) {
+        return "out of gas".to_string();
+    }
+    // No fees, so the replay doesn't depend on the sender's balance
+    let call: TypedTransaction = TransactionRequest {
+        from: Some(tx.from),
+        to: tx.to.map(Into::into),
+        gas: Some(tx.gas),
+        value: Some(tx.value),
+        data: Some(tx.input.clone()),
+        ..Default::default()
+    }
+    .into();
+    let block = receipt.block_number.map(|number| BlockId::Number(number.into()));
+    match client.call(&call, block).await {
+        // The state it depended on changed within the block
+        Ok(_) => "succeeds when replayed".to_string(),
+        Err(e) => match e.as_error_response().and_then(|response| response.as_revert_data()) {
+            Some(data) => decode_revert_reason(&data),
+            None => format!("replay failed: {}", e),
+        },
+    }
+}
+
+/// Rate at which the chain actually mined the wallets' transactions, from their nonces over time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct MinedThroughput {
+    /// Wallet nonces consumed on chain while sampling
+    pub mined: u64,
+    /// From the start of sampling to the last sample that saw a nonce move
+    #[serde(rename = "duration_ms", serialize_with = "crate::report::serialize_millis")]
+    pub duration: Duration,
+}
+
+impl MinedThroughput {
+    /// Mined transactions per second
+    pub fn tps(&self) -> f64 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        self.mined as f64 / self.duration.as_secs_f64()
+    }
+}
+
+/// Polls the latest nonce of every wallet each `poll_interval` until `stop` resolves, and reports how
+/// many of them got mined and how long that took. Unlike receipts this sees inclusion as it happens,
+/// so it gives mined TPS rather than submission TPS.
+pub async fn sample_mined_nonces(
+    clients: Vec<Arc<Client>>,
+    poll_interval: Duration,
+    stop: tokio::sync::oneshot::Receiver<()>,
+) -> MinedThroughput {
+    let started = Instant::now();
+    let Some(initial) = latest_nonce_total(&clients).await else {
+        warn!("could not read wallet nonces, mined TPS is unavailable");
+        return MinedThroughput::default();
+    };
+    let mut last_total = initial;
+    let mut last_increase = started;
+    tokio::pin!(stop);
+    loop {
+        let stopping = tokio::select! {
+            _ = &mut stop => true,
+            _ = sleep(poll_interval) => false,
+        };
+        if let Some(total) = latest_nonce_total(&clients).await {
+            if total > last_total {
+                last_total = total;
+                last_increase = Instant::now();
+            }
+        }
+        if stopping {
+            break;
+        }
+    }
+    MinedThroughput { mined: last_total - initial, duration: last_increase - started }
+}
+
+/// How the wallets' transactions cleared the mempool once sending stopped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DrainReport {
+    /// Whether every sent nonce was mined before the timeout
+    pub drained: bool,
+    /// From the end of sending until the last nonce was mined, or until the timeout
+    #[serde(rename = "duration_ms", serialize_with = "crate::report::serialize_millis")]
+    pub duration: Duration,
+    /// Transactions mined during the cooldown
+    pub mined: u64,
+    /// Transactions still pending when the cooldown ended
+    pub pending: u64,
+    /// Set when a stop ended the cooldown early
+    pub interrupted: bool,
+}
+
+impl DrainReport {
+    /// Transactions cleared per second during the cooldown
+    pub fn clearance_rate(&self) -> f64 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        self.mined as f64 / self.duration.as_secs_f64()
+    }
+}
+
+/// Polls the latest nonce of every wallet each `poll_interval` until it reaches the wallet's next
+/// nonce in `sent`, i.e. everything sent was mined, or until `timeout` elapses. Measures how fast
+/// the mempool clears, as opposed to how fast it accepts. A `shutdown` ends the wait early.
+pub async fn wait_for_drain(
+    clients: &[Arc<Client>],
+    sent: &BTreeMap<Address, u64>,
+    poll_interval: Duration,
+    timeout: Duration,
+    shutdown: &Shutdown,
+) -> DrainReport {
+    let started = Instant::now();
+    let mut initial = None;
+    let mut pending = None;
+    let mut interrupted = false;
+    loop {
+        if let Some(behind) = pending_nonces(clients, sent).await {
+            initial.get_or_insert(behind);
+            pending = Some(behind);
+            if behind == 0 {
+                break;
+            }
+        }
+        if started.elapsed() >= timeout {
+            break;
+        }
+        if !pause(poll_interval.min(timeout.saturating_sub(started.elapsed())), shutdown).await {
+            interrupted = true;
+            break;
+        }
+    }
+    let Some(pending) = pending else {
+        warn!("could not read wallet nonces, the mempool drain is unknown");
+        return DrainReport { duration: started.elapsed(), interrupted, ..DrainReport::default() };
+    };
+    DrainReport {
+        drained: pending == 0,
+        duration: started.elapsed(),
+        mined: initial.unwrap_or(pending) - pending,
+        pending,
+        interrupted,
+    }
+}
+
+/// Sent transactions not yet mined across the wallets, or `None` if any nonce could not be read
+async fn pending_nonces(clients: &[Arc<Client>], sent: &BTreeMap<Address, u64>) -> Option<u64> {
+    let counts = join_all(clients.iter().map(|client| {
+        client.get_transaction_count(client.address(), Some(BlockNumber::Latest.into()))
+    }))
+    .await;
+    let mut pending = 0;
+    for (client, count) in clients.iter().zip(counts) {
+        match count {
+            Ok(count) => {
+                let next = sent.get(&client.address()).copied().unwrap_or_default();
+                pending += next.saturating_sub(count.as_u64());
+            }
+            Err(e) => {
+                debug!(error = %e, "failed to read a wallet nonce");
+                return None;
+            }
+        }
+    }
+    Some(pending)
+}
+
+/// Sum of the wallets' latest nonces, or `None` if any could not be read
+async fn latest_nonce_total(clients: &[Arc<Client>]) -> Option<u64> {
+    let counts = join_all(clients.iter().map(|client| {
+        client.get_transaction_count(client.address(), Some(BlockNumber::Latest.into()))
+    }))
+    .await;
+    let mut total = 0;
+    for count in counts {
+        match count {
+            Ok(count) => total += count.as_u64(),
+            Err(e) => {
+                debug!(error = %e, "failed to read a wallet nonce");
+                return None;
+            }
+        }
+    }
+    Some(total)
+}
+
+/// A sent transaction still waiting for a receipt, with every hash it was broadcast under
+struct Replaceable {
+    sent: SentTx,
+    hashes: Vec<H256>,
+    last_broadcast: Instant,
+    bumps: u32,
+    /// Set once the fee cap stops further replacements
+    capped: bool,
+}
+
+/// Like [`wait_for_receipts`], but a transaction without a receipt after `policy.after` is re-signed
+/// at the same nonce with its fees raised by `policy.bump_pct` and broadcast again, up to
+/// `policy.max_attempts` times. Any of its hashes being mined counts as its inclusion. Replacements
+/// are signed like the originals, without EIP-155 replay protection unless `eip155`. A `shutdown`
+/// stops the wait at the next poll.
+#[allow(clippy::too_many_arguments)]
+#[instrument(name = "confirm", skip_all)]
+pub async fn wait_with_replacement(
+    clients: &[Arc<Client>],
+    sent: Vec<SentTx>,
+    submissions: &HashMap<H256, Submission>,
+    start_block: u64,
+    policy: RbfPolicy,
+    eip155: bool,
+    poll_interval: Duration,
+    timeout: Duration,
+    progress: &ConfirmProgress,
+    shutdown: &Shutdown,
+) -> Result<ConfirmationReport> {
+    let deadline = Instant::now() + timeout;
+    let started = Instant::now();
+    let mut pending: Vec<Replaceable> = sent
+        .into_iter()
+        .map(|sent| Replaceable {
+            hashes: vec![sent.hash],
+            sent,
+            last_broadcast: started,
+            bumps: 0,
+            capped: false,
+        })
+        .collect();
+    let mut report = ConfirmationReport::default();
+    let mut tally = Tally::default();
+    let mut reverted = Vec::new();
+    let client = &clients[0];
+
+    info!("Waiting for {} receipts, replacing any still pending after {:?}...", pending.len(), policy.after);
+    while !pending.is_empty() {
+        // Query every hash of every pending transaction in parallel, like `wait_for_receipts` does
+        let receipts = join_all(pending.iter().map(|entry| {
+            join_all(entry.hashes.iter().map(|hash| client.get_transaction_receipt(*hash)))
+        }))
+        .await;
+        let mut still_pending = Vec::with_capacity(pending.len());
+        let mut due = Vec::new();
+        for (entry, receipts) in pending.into_iter().zip(receipts) {
+            match receipts.into_iter().find_map(|receipt| receipt.ok().flatten()) {
+                Some(receipt) => {
+                    // Latency counts from the first broadcast, whichever replacement got mined
+                    let submission = submissions.get(&entry.sent.hash).copied();
+                    let failed = report.record(entry.sent.hash, &receipt, start_block, submission, &mut tally);
+                    progress.record(failed);
+                    if failed {
+                        reverted.push(receipt);
+                    }
+                    if entry.bumps > 0 {
+                        report.bumped += 1;
+                    }
+                }
+                None => {
+                    let elapsed = entry.last_broadcast.elapsed() >= policy.after;
+                    if elapsed && entry.bumps < policy.max_attempts && !entry.capped {
+                        due.push(entry);
+                    } else {
+                        still_pending.push(entry);
+                    }
+                }
+            }
+        }
+        // Replacements go out in parallel too, so a slow node doesn't stretch a round per transaction
+        join_all(due.iter_mut().map(|entry| replace(&clients[entry.sent.wallet_idx], entry, &policy, eip155))).await;
+        still_pending.extend(due);
+        pending = still_pending;
+
+        if pending.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        if !pause(poll_interval, shutdown).await {
+            report.interrupted = true;
+            break;
+        }
+    }
+
+    report.finish(pending.len(), tally);
+    report.revert_reasons = explain_reverts(&**client, &reverted).await;
+    Ok(report)
+}
+
+/// Re-signs a pending transaction with bumped fees and broadcasts it under the same nonce
+async fn replace(client: &Client, entry: &mut Replaceable, policy: &RbfPolicy, eip155: bool) {
+    let nonce = entry.sent.tx.nonce().copied().unwrap_or_default();
+    let mut tx = entry.sent.tx.clone();
+    if !bump_fees(&mut tx, policy.bump_pct, policy.max_gas_price) {
+        warn!(%nonce, "not replacing: a further bump would exceed the gas price cap");
+        entry.capped = true;
+        return;
+    }
+    // Bump from the last attempt even when its broadcast fails, so the next try outbids it too
+    entry.sent.tx = tx.clone();
+    entry.bumps += 1;
+    entry.last_broadcast = Instant::now();
+    let signed = match sign_with(client.signer(), &tx, eip155) {
+        Ok(signed) => signed,
+        Err(e) => {
+            warn!(%nonce, error = %e, "failed to sign replacement");
+            return;
+        }
+    };
+    match client.send_raw_transaction(signed).await {
+        Ok(pending_tx) => {
+            debug!(%nonce, attempt = entry.bumps, hash = ?pending_tx.tx_hash(), "replacement sent");
+            entry.hashes.push(pending_tx.tx_hash());
+        }
+        // Typically an earlier attempt was just mined; its receipt is picked up next round
+        Err(e) => debug!(%nonce, attempt = entry.bumps, error = %e, "replacement rejected"),
+    }
+}
+
+/// Raises every fee field of `tx` by `pct` percent, rounding up. Returns false, leaving `tx`
+/// untouched, when the result would go over `max_gas_price`.
+fn bump_fees(tx: &mut TypedTransaction, pct: u64, max_gas_price: Option<U256>) -> bool {
+    let bump = |fee: U256| (fee * (100 + pct) + 99) / 100;
+    let over_cap = |fee: U256| max_gas_price.is_some_and(|cap| fee > cap);
+    match tx {
+        TypedTransaction::Eip1559(inner) => {
+            let max_fee = bump(inner.max_fee_per_gas.unwrap_or_default());
+            let max_priority_fee = bump(inner.max_priority_fee_per_gas.unwrap_or_default());
+            if over_cap(max_fee) {
+                return false;
+            }
+            inner.max_fee_per_gas = Some(max_fee);
+            inner.max_priority_fee_per_gas = Some(max_priority_fee);
+        }
+        _ => {
+            let gas_price = bump(tx.gas_price().unwrap_or_default());
+            if over_cap(gas_price) {
+                return false;
+            }
+            tx.set_gas_price(gas_price);
+        }
+    }
+    true
+}
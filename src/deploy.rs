@@ -0,0 +1,135 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, Provider},
+    signers::Signer,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Bytes, Eip1559TransactionRequest, TransactionRequest, H256, U256,
+    },
+};
+use serde::Serialize;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+use tokio::time::timeout;
+use tracing::{info, instrument};
+
+use crate::{
+    config::{Config, TxType},
+    fees::FeeMode,
+    transport::FailoverTransport,
+};
+
+/// Creation code of the bundled benchmark target, the `Counter` of the Foundry template:
+/// `increment()` adds one to `number`, `setNumber(uint256)` overwrites it and `number()` reads
+/// it; anything else reverts. Hand-assembled, so it needs no compiler: an 11-byte constructor
+/// that returns the 71-byte runtime code after it.
+const COUNTER_INIT_CODE: &str = concat!(
+    "604780600b6000396000f3",
+    "60003560e01c8063d09de08a1460285780633fb5c1cb1460335780638381f58a14603b57600080fd",
+    "5b600054600101600055005b600435600055005b60005460005260206000f3",
+);
+
+/// Method of the bundled counter benchmarked unless another is chosen
+pub const COUNTER_METHOD: &str = "increment()";
+
+/// Creation code of the bundled counter contract
+pub fn counter_init_code() -> Bytes {
+    COUNTER_INIT_CODE.parse().expect("bundled bytecode is valid hex")
+}
+
+/// Contract deployed ahead of a benchmark
+#[derive(Debug, Clone, Serialize)]
+pub struct Deployment {
+    pub address: Address,
+    pub tx_hash: H256,
+    pub block: u64,
+    pub gas_used: U256,
+    /// From sending the deployment until its receipt was seen
+    #[serde(rename = "duration_ms", serialize_with = "crate::report::serialize_millis")]
+    pub duration: Duration,
+}
+
+impl fmt::Display for Deployment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} in block {} ({} gas, {:?})",
+            self.address, self.block, self.gas_used, self.duration
+        )
+    }
+}
+
+/// Deploys `init_code` from the first configured wallet, priced by the configured gas strategy,
+/// and waits up to the confirmation timeout for it to be mined
+#[instrument(name = "deploy", skip_all)]
+pub async fn deploy_contract(config: &Config, init_code: Bytes) -> Result<Deployment> {
+    let wallet = config.wallets.first().context("deploying needs a wallet")?;
+    let provider = Provider::new(FailoverTransport::connect(&config.rpc_urls, &config.rpc_headers).await?);
+    let chain_id = match config.chain_id {
+        Some(chain_id) => chain_id,
+        None => provider.get_chainid().await?.as_u64(),
+    };
+
+    let eip1559 = config.tx_type.resolve(&provider).await? == TxType::Eip1559;
+    let mut fee_mode = config.gas_strategy.fee_mode(&provider, eip1559).await?;
+    if let Some(max_gas_price) = config.max_gas_price {
+        fee_mode = fee_mode.clamp(max_gas_price);
+    }
+    let mut tx: TypedTransaction = match fee_mode {
+        FeeMode::Legacy { gas_price } => TransactionRequest::new().gas_price(gas_price).into(),
+        FeeMode::Eip1559 { max_fee, max_priority_fee } => Eip1559TransactionRequest::new()
+            .max_fee_per_gas(max_fee)
+            .max_priority_fee_per_gas(max_priority_fee)
+            .into(),
+    };
+    tx.set_data(init_code);
+
+    // The signer fills in the nonce and estimates the gas limit
+    let client = SignerMiddleware::new(provider, wallet.clone().with_chain_id(chain_id));
+    let started = Instant::now();
+    let pending = client.send_transaction(tx, None).await.context("failed to send the deployment")?;
+    let tx_hash = pending.tx_hash();
+    info!("Deploying from {:?} in {:?}", client.address(), tx_hash);
+
+    let receipt = timeout(config.confirm_timeout, pending.interval(config.poll_interval))
+        .await
+        .map_err(|_| anyhow!("deployment {:?} was not mined within {:?}", tx_hash, config.confirm_timeout))?
+        .context("failed to wait for the deployment receipt")?
+        .with_context(|| format!("deployment {:?} was dropped from the mempool", tx_hash))?;
+    if receipt.status != Some(1.into()) {
+        bail!("deployment {:?} reverted", tx_hash);
+    }
+    let address = receipt
+        .contract_address
+        .with_context(|| format!("receipt of deployment {:?} has no contract address", tx_hash))?;
+    Ok(Deployment {
+        address,
+        tx_hash,
+        block: receipt.block_number.unwrap_or_default().as_u64(),
+        gas_used: receipt.gas_used.unwrap_or_default(),
+        duration: started.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::utils::id;
+
+    #[test]
+    fn counter_dispatches_the_template_selectors() {
+        let init_code = counter_init_code();
+        let (constructor, runtime) = init_code.split_at(11);
+        // PUSH1 <runtime length> DUP1 PUSH1 <constructor length> ...
+        assert_eq!(constructor[1] as usize, runtime.len());
+        assert_eq!(constructor[4] as usize, constructor.len());
+
+        for signature in [COUNTER_METHOD, "setNumber(uint256)", "number()"] {
+            // PUSH4 <selector>
+            let push = [&[0x63][..], &id(signature)[..]].concat();
+            assert!(runtime.windows(5).any(|window| window == push), "{} is not dispatched", signature);
+        }
+    }
+}
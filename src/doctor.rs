@@ -0,0 +1,132 @@
+use anyhow::Result;
+use ethers::{
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::BlockNumber,
+};
+use reqwest::header::HeaderMap;
+use serde::Serialize;
+use std::fmt;
+
+use crate::{fees::ether, transport::FailoverTransport};
+
+/// Result of one setup check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not run because a check it depends on failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    /// What was found, or what to fix
+    pub detail: String,
+}
+
+/// Checklist of a `doctor` run, which verifies the setup without sending anything
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DoctorReport {
+    pub checks: Vec<Check>,
+}
+
+impl DoctorReport {
+    /// Records a check that passed with `Ok` and failed with `Err`, returning whether it passed
+    pub fn record(&mut self, name: impl Into<String>, outcome: Result<String, String>) -> bool {
+        let (status, detail) = match outcome {
+            Ok(detail) => (CheckStatus::Pass, detail),
+            Err(detail) => (CheckStatus::Fail, detail),
+        };
+        self.checks.push(Check { name: name.into(), status, detail });
+        status == CheckStatus::Pass
+    }
+
+    /// Records a check that could not run because of an earlier failure
+    pub fn skip(&mut self, name: impl Into<String>, reason: &str) {
+        self.checks.push(Check { name: name.into(), status: CheckStatus::Skipped, detail: reason.to_string() });
+    }
+
+    /// Whether every check passed
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.status == CheckStatus::Pass)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl fmt::Display for DoctorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== DOCTOR =====")?;
+        for check in &self.checks {
+            let status = match check.status {
+                CheckStatus::Pass => "PASS",
+                CheckStatus::Fail => "FAIL",
+                CheckStatus::Skipped => "SKIP",
+            };
+            writeln!(f, "[{}] {}: {}", status, check.name, check.detail)?;
+        }
+        let failed = self.checks.iter().filter(|check| check.status != CheckStatus::Pass).count();
+        if failed == 0 {
+            write!(f, "All {} checks passed", self.checks.len())
+        } else {
+            write!(f, "{} of {} checks did not pass", failed, self.checks.len())
+        }
+    }
+}
+
+/// Checks that the endpoint answers and that every wallet has funds and a readable nonce, recording
+/// each step in `report`. Only read calls are made.
+pub async fn check_endpoint(report: &mut DoctorReport, rpc_urls: &[String], headers: &HeaderMap, wallets: &[LocalWallet]) {
+    let wallet_checks = |report: &mut DoctorReport, reason: &str| {
+        for wallet in wallets {
+            report.skip(format!("Balance of {:?}", wallet.address()), reason);
+            report.skip(format!("Nonce of {:?}", wallet.address()), reason);
+        }
+    };
+
+    let provider = match FailoverTransport::connect(rpc_urls, headers).await {
+        Ok(transport) => Provider::new(transport),
+        Err(e) => {
+            report.record("RPC reachable", Err(format!("{:#}", e)));
+            report.skip("Chain id", "the RPC is unreachable");
+            wallet_checks(report, "the RPC is unreachable");
+            return;
+        }
+    };
+    let url = provider.as_ref().current_url().to_string();
+    let reachable = match provider.get_block_number().await {
+        Ok(block) => report.record("RPC reachable", Ok(format!("{} is at block {}", url, block))),
+        Err(e) => report.record("RPC reachable", Err(format!("eth_blockNumber on {} failed: {}", url, e))),
+    };
+    if !reachable {
+        report.skip("Chain id", "the RPC is unreachable");
+        wallet_checks(report, "the RPC is unreachable");
+        return;
+    }
+
+    match provider.get_chainid().await {
+        Ok(chain_id) => report.record("Chain id", Ok(chain_id.to_string())),
+        Err(e) => report.record("Chain id", Err(format!("eth_chainId failed: {}", e))),
+    };
+
+    for wallet in wallets {
+        let address = wallet.address();
+        let balance = match provider.get_balance(address, None).await {
+            Ok(balance) if balance.is_zero() => Err("0 ETH; fund the wallet or pass --fund-from".to_string()),
+            Ok(balance) => Ok(format!("{} ETH", ether(balance))),
+            Err(e) => Err(format!("eth_getBalance failed: {}", e)),
+        };
+        report.record(format!("Balance of {:?}", address), balance);
+        let nonce = match provider.get_transaction_count(address, Some(BlockNumber::Pending.into())).await {
+            Ok(nonce) => Ok(format!("next pending nonce {}", nonce)),
+            Err(e) => Err(format!("eth_getTransactionCount failed: {}", e)),
+        };
+        report.record(format!("Nonce of {:?}", address), nonce);
+    }
+}
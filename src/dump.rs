@@ -1,165 +1,1354 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use dotenv::dotenv;
 use ethers::{
-    middleware::SignerMiddleware,
-    providers::{Http, Middleware, Provider},
-    signers::{LocalWallet, Signer},
-    types::{transaction::eip2718::TypedTransaction, H256, U256},
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer},
+    types::{transaction::eip2930::AccessList, Address, BlockNumber, Bytes, U256},
+    utils::parse_units,
 };
-use futures::future::join_all;
-use std::{env, sync::Arc, time::Instant};
-
-/// Creates a transaction that can be sent
-async fn create_transaction(
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    nonce: u64,
-    gas_price: U256,
-) -> Result<TypedTransaction> {
-    let address = client.address();
-    
-    // Populate transaction with explicit nonce and hardcoded gas values
-    let mut tx = TypedTransaction::default();
-    tx.set_to(address);
-    tx.set_value(U256::zero());
-    tx.set_nonce(nonce);
-    
-    // Set fixed gas limit - 21000 is the cost of a simple ETH transfer
-    tx.set_gas(21000);
-    
-    // Use the gas price passed from the main function
-    tx.set_gas_price(gas_price);
-    
-    Ok(tx)
-}
-
-/// Sends a transaction without waiting for confirmation or receipt
-async fn send_transaction(
-    client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
-    tx: TypedTransaction,
-) -> Result<H256> {
-    // Start measuring send time
-    let send_start = Instant::now();
-    
-    // Send transaction
-    let pending_tx = client.send_transaction(tx, None).await?;
-    let tx_hash = pending_tx.tx_hash();
-    
-    // Measure send time
-    let send_duration = send_start.elapsed();
-    println!("TX sent in {:?}, hash: {}", send_duration, tx_hash);
-    
-    Ok(tx_hash)
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
+use rust_web3_utils::{
+    calldata::encode_call,
+    baseline::{Baseline, Regression},
+    bundle::BundleRelay,
+    cancel::cancel_nonces,
+    compare::compare_endpoints,
+    doctor::{check_endpoint, DoctorReport},
+    config::{load_recipients, AccessListSource, Config, NonceSource, TxType, WalletSelection, Workload},
+    config_file,
+    confirm::{RbfPolicy, MIN_REPLACEMENT_BUMP_PCT},
+    deploy::{counter_init_code, deploy_contract, COUNTER_METHOD},
+    fees::{parse_percentile, GasStrategy},
+    funding::FundingConfig,
+    gas_oracle::GasOracle,
+    metrics::{report_live, serve as serve_metrics},
+    probe::ProbeProfile,
+    ramp::RampProfile,
+    rate::{Arrival, BurstPattern},
+    retry::RetryPolicy,
+    rounds::{run_rounds, RoundsReport},
+    wallet_lock::WalletLock,
+    Benchmarker, RawTxFile, RunReport,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
+
+/// Conventional exit status for a run stopped by SIGINT
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Exit status for a run stopped by `--max-runtime`, the one `timeout(1)` uses
+const EXIT_TIMED_OUT: i32 = 124;
+
+/// How long a run may take to wind down after `--max-runtime` before it is abandoned
+const MAX_RUNTIME_GRACE: Duration = Duration::from_secs(15);
+
+/// Floods an RPC endpoint with pre-built zero-value transactions and reports submission throughput
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// TOML file of flag values, keyed by long flag name (e.g. `rpc-url`, `max-concurrency`).
+    /// Flags given on the command line or through their environment variable take precedence
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Keys of the config file that don't name a flag, warned about once logging is up
+    #[arg(skip)]
+    unknown_config_keys: Vec<String>,
+
+    /// Number of transactions to send; a run needs either this or --duration
+    #[arg(short = 'n', long)]
+    count: Option<u64>,
+
+    /// Transactions sent before the measured run to warm up connections; excluded from all metrics
+    #[arg(long, default_value_t = 0)]
+    warmup: u64,
+
+    /// Keep sending for this long instead of a fixed count, e.g. `60s`
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with = "count")]
+    duration: Option<Duration>,
+
+    /// Repeat the whole run this many times, including confirmation, and summarise each round and
+    /// the mean and standard deviation of TPS and p95 latency across them
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = [
+            "baseline_file",
+            "timeseries_file",
+            "latency_histogram_file",
+            "records_file",
+            "no_wait"
+        ]
+    )]
+    rounds: u64,
+
+    /// Overlap preparation and sending through a bounded pipeline instead of preparing everything first;
+    /// keeps memory flat for very large counts
+    #[arg(long, conflicts_with_all = ["duration", "ramp"])]
+    pipeline: bool,
+
+    /// Signed transactions the pipeline buffers ahead of the senders
+    #[arg(long, default_value_t = 1000, requires = "pipeline")]
+    pipeline_buffer: usize,
+
+    /// Ramp the paced send rate as `start,end,step` tx/s, one sustained window per rate
+    #[arg(long, value_parser = parse_ramp, conflicts_with_all = ["count", "duration"])]
+    ramp: Option<(f64, f64, f64)>,
+
+    /// Length of each ramp window, e.g. `10s`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "10s")]
+    ramp_window: Duration,
+
+    /// Fraction of failed sends (0.0-1.0) in a ramp window at which the ramp stops
+    #[arg(long, default_value_t = 0.05)]
+    ramp_fail_threshold: f64,
+
+    /// Multiplier applied to the node's suggested gas price, or only to the tip for EIP-1559 transactions;
+    /// shorthand for `--gas-strategy multiple:<factor>`
+    #[arg(long, default_value_t = 3.0)]
+    gas_multiplier: f64,
+
+    /// How fees are chosen: `multiple:<factor>` of the node's gas price, `fixed:<gwei>`,
+    /// `oracle` for base fee plus a tip from the node's fee suggestions, or `percentile:<p>`
+    #[arg(long, conflicts_with = "gas_multiplier")]
+    gas_strategy: Option<GasStrategy>,
+
+    /// Take the EIP-1559 tip from this reward percentile of recent blocks, with a max fee of twice
+    /// the base fee plus the tip; shorthand for `--gas-strategy percentile:<p>`
+    #[arg(long, value_parser = parse_percentile, conflicts_with_all = ["gas_multiplier", "gas_strategy"])]
+    fee_percentile: Option<f64>,
+
+    /// Take the gas price from this external estimator, which must answer a GET with JSON holding
+    /// a gwei value; the node's gas price is used when it can't be reached
+    #[arg(long, conflicts_with_all = ["gas_multiplier", "gas_strategy", "fee_percentile"])]
+    gas_oracle: Option<String>,
+
+    /// Sign with this gas price in gwei as is, with no multiplier and no gas price or fee history
+    /// lookups; shorthand for `--gas-strategy fixed:<gwei>`
+    #[arg(long, value_parser = parse_gwei, conflicts_with_all = ["gas_multiplier", "gas_strategy", "fee_percentile", "gas_oracle"])]
+    gas_price: Option<U256>,
+
+    /// Dot-separated path to the gwei value in the oracle's response, e.g. `result.ProposeGasPrice`
+    #[arg(long, default_value = "gasPrice", requires = "gas_oracle")]
+    gas_oracle_field: String,
+
+    /// Ceiling on the gas price in gwei; higher computed fees are clamped, and the run aborts
+    /// if the network minimum is already above it
+    #[arg(long, value_parser = parse_gwei)]
+    max_gas_price: Option<U256>,
+
+    /// Raise each transaction's fees this many percent over the previous one's, compounding, so
+    /// later nonces always outbid earlier ones; needs `--max-gas-price` to clamp it
+    #[arg(long)]
+    gas_escalation_pct: Option<f64>,
+
+    /// Abort when the computed fee per gas is more than this factor above or below the node's
+    /// `eth_gasPrice`, which catches multiplier and unit mistakes; `--force` sends anyway. A literal
+    /// `--gas-price` isn't checked.
+    #[arg(long, default_value_t = 10.0)]
+    gas_sanity_factor: f64,
+
+    /// Skip the gas price sanity check, for intentionally aggressive or tiny fees
+    #[arg(long, conflicts_with = "gas_sanity_factor")]
+    no_gas_sanity_check: bool,
+
+    /// RPC endpoint to submit transactions to; `ws://`/`wss://` URLs connect over WebSocket.
+    /// A comma-separated list fails over to the next endpoint when one is unreachable.
+    /// Required except for `compare`, which takes its endpoints from `--rpc`
+    #[arg(long, env = "RPC_PROVIDER", value_delimiter = ',')]
+    rpc_url: Vec<String>,
+
+    /// Extra HTTP header sent with every RPC request, e.g. `"x-api-key: <key>"`; repeatable.
+    /// Keep secrets out of shell history by setting them in `RPC_HEADERS` instead, one per line:
+    /// header values may contain commas but never newlines
+    #[arg(long = "rpc-header", env = "RPC_HEADERS", value_delimiter = '\n', value_parser = parse_header, hide_env_values = true)]
+    rpc_headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Chain id to sign for, skipping the `eth_chainId` call; a mismatch with the endpoint is
+    /// warned about once the run is done
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// Private key of the first sending wallet, without the 0x prefix; required unless `--mnemonic`
+    /// or `--keystore` is given or the transactions are replayed from a file
+    #[arg(long, env = "PRIVATE_KEY_1", hide_env_values = true)]
+    private_key: Option<String>,
+
+    /// Number of sending wallets; wallets 2..N are read from PRIVATE_KEY_2..PRIVATE_KEY_N
+    #[arg(long, default_value_t = 1)]
+    wallets: usize,
+
+    /// Derive the sending wallets from this mnemonic (path m/44'/60'/0'/0/i) instead of private keys
+    #[arg(long, env = "MNEMONIC", hide_env_values = true)]
+    mnemonic: Option<String>,
+
+    /// Index of the first account derived from `--mnemonic`
+    #[arg(long, default_value_t = 0, requires = "mnemonic")]
+    account_start: u32,
+
+    /// Number of accounts derived from `--mnemonic`
+    #[arg(long, default_value_t = 1, requires = "mnemonic")]
+    account_count: u32,
+
+    /// Encrypted Web3 Secret Storage (keystore) JSON file of a sending wallet; repeat for more wallets
+    #[arg(long)]
+    keystore: Vec<PathBuf>,
+
+    /// Password of the keystore files; prompted for when omitted
+    #[arg(long, env = "KEYSTORE_PASSWORD", hide_env_values = true, requires = "keystore")]
+    keystore_password: Option<String>,
+
+    /// Private key of a master wallet that tops up every benchmark wallet to `--fund-amount`
+    /// before the run
+    #[arg(long, env = "FUND_FROM_KEY", hide_env_values = true, requires = "fund_amount")]
+    fund_from: Option<String>,
+
+    /// Balance in wei each benchmark wallet is funded to; wallets already holding it are skipped
+    #[arg(long, value_parser = parse_u256)]
+    fund_amount: Option<U256>,
+
+    /// Block tag the starting nonces are read at. `pending` counts transactions still in the mempool,
+    /// so a run started right after another one doesn't reuse its nonces
+    #[arg(long, value_enum, default_value_t = NonceSource::Pending)]
+    nonce_source: NonceSource,
+
+    /// How transactions are assigned to the wallets: in turn, or at random per transaction
+    /// (reproducible with --seed)
+    #[arg(long, value_enum, default_value_t = WalletSelection::RoundRobin)]
+    wallet_selection: WalletSelection,
+
+    /// Read the starting nonces and balances at this block instead: a number, `latest` or `pending`.
+    /// Useful against a forked node whose relevant state isn't at its tip
+    #[arg(long, value_parser = parse_block, conflicts_with = "nonce_source")]
+    at_block: Option<BlockNumber>,
+
+    /// Remember each wallet's next nonce in this file after a run and start from at least that
+    /// on the next one, so back-to-back runs don't collide before transactions are mined
+    #[arg(long)]
+    nonce_state_file: Option<PathBuf>,
+
+    /// Transaction envelope to build; `auto` picks EIP-1559 when the latest block has a base fee
+    #[arg(long, value_enum, default_value_t = TxType::Auto)]
+    tx_type: TxType,
+
+    /// Sign legacy transactions without a chain id, as before EIP-155, for test chains that reject
+    /// replay-protected signatures. Such transactions are valid on every chain, so never use keys
+    /// that hold real funds. Needs `--tx-type legacy`.
+    #[arg(long, conflicts_with_all = ["access_list", "access_list_from_tx"])]
+    no_eip155: bool,
+
+    /// Destination address for every transaction, e.g. the contract to call; defaults to sending to self
+    #[arg(long, visible_alias = "to")]
+    recipient: Option<Address>,
+
+    /// File with one destination address per line, used round-robin; blank lines and `#` comments are ignored
+    #[arg(long, conflicts_with = "recipient")]
+    recipients_file: Option<PathBuf>,
+
+    /// Value in wei attached to each transaction
+    #[arg(long, value_parser = parse_u256, default_value = "0")]
+    value: U256,
+
+    /// Randomise each transaction's value within this many percent either side of --value;
+    /// reproducible with --seed
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u64).range(0..=100))]
+    value_jitter_pct: u64,
+
+    /// Gas limit for each transaction; estimated via eth_estimateGas for calldata or contract recipients when omitted
+    #[arg(long)]
+    gas_limit: Option<u64>,
+
+    /// Hex-encoded calldata attached to each transaction
+    #[arg(long)]
+    data: Option<Bytes>,
+
+    /// Send ERC-20 `transfer(recipient, value)` calls to this token instead of plain transfers;
+    /// `--value` becomes the token amount in base units
+    #[arg(long, conflicts_with = "data")]
+    erc20: Option<Address>,
+
+    /// Access list JSON to attach to every transaction, e.g. `[{"address":"0x…","storageKeys":["0x…"]}]`;
+    /// legacy transactions become EIP-2930 ones
+    #[arg(long, value_parser = parse_access_list, conflicts_with = "access_list_from_tx")]
+    access_list: Option<AccessList>,
+
+    /// Generate the access list with `eth_createAccessList`, once per recipient and calldata
+    #[arg(long)]
+    access_list_from_tx: bool,
+
+    /// Contract method to call, ABI-encoded from `--args`: a name or signature found in `--abi`, or
+    /// a human-readable signature such as `mint(address to, uint256 amount)` without one
+    #[arg(long, conflicts_with_all = ["data", "erc20"])]
+    method: Option<String>,
+
+    /// JSON ABI file the `--method` is looked up in
+    #[arg(long, requires = "method")]
+    abi: Option<PathBuf>,
+
+    /// Arguments of `--method`, in order
+    #[arg(long, num_args = 1.., requires = "method")]
+    args: Vec<String>,
+
+    /// Percentage added on top of estimated gas limits
+    #[arg(long, default_value_t = 0)]
+    gas_buffer_pct: u64,
+
+    /// Maximum number of sends in flight at once; 0 means unbounded
+    #[arg(long, default_value_t = 0)]
+    max_concurrency: usize,
+
+    /// Threads of the async runtime driving the sends; defaults to one per CPU core. Signing runs on
+    /// its own thread pool, so this only bounds how fast requests are issued and responses handled
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    worker_threads: Option<u64>,
+
+    /// Tune concurrency to the endpoint: grow it while latency stays flat, halve it on errors or latency
+    /// spikes. --max-concurrency becomes the ceiling.
+    #[arg(long)]
+    adaptive: bool,
+
+    /// Pace submissions evenly at this many transactions per second instead of bursting them
+    #[arg(long, value_parser = parse_rate, conflicts_with = "ramp")]
+    rate: Option<f64>,
+
+    /// Send in bursts instead of all at once: `burst:size=100,gap=500ms` sends 100 transactions as fast
+    /// as possible, sleeps 500ms, and repeats until --count is exhausted
+    #[arg(long, conflicts_with_all = ["rate", "duration", "ramp", "pipeline"])]
+    pattern: Option<BurstPattern>,
+
+    /// How paced sends are spread out: evenly, or with exponential gaps like organic traffic
+    #[arg(long, value_enum, default_value_t = Arrival::Uniform)]
+    arrival: Arrival,
+
+    /// Seed for every random choice (value jitter, Poisson arrivals) so a run can be reproduced
+    /// exactly; without it one is drawn and printed in the summary
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Retries for transient send failures such as timeouts or rate limiting
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Delay before the first retry, doubled on each subsequent one
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "100ms")]
+    retry_backoff: Duration,
+
+    /// Give up on a send that has not returned after this long and count it as a timeout (e.g. 5s)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    send_timeout: Option<Duration>,
+
+    /// After sending, resend failed transactions whose nonces leave later accepted ones stuck
+    #[arg(long)]
+    fill_gaps: bool,
+
+    /// Submit the transactions to this bundle relay in `eth_sendBundle` calls instead of sending
+    /// them one by one; the RPC endpoint is still used for nonces, fees and receipts
+    #[arg(
+        long,
+        conflicts_with_all = ["duration", "ramp", "pipeline", "rate", "pattern", "adaptive", "rbf", "fill_gaps", "dry_run"]
+    )]
+    bundle_relay: Option<String>,
+
+    /// Transactions per bundle
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    bundle_size: u64,
+
+    /// Block every bundle targets; defaults to the block after the tip when sending starts
+    #[arg(long, requires = "bundle_relay")]
+    bundle_block: Option<u64>,
+
+    /// Private key that signs each bundle request for the `X-Flashbots-Signature` header. It only
+    /// identifies you to the relay and needs no funds; a throwaway key is used when none is given
+    #[arg(long, env = "BUNDLE_AUTH_KEY", hide_env_values = true)]
+    bundle_auth_key: Option<String>,
+
+    /// Extra HTTP header sent with every bundle request, for relays that take an API key instead
+    /// of or on top of the signature; repeatable, or one per line in `BUNDLE_HEADERS`
+    #[arg(
+        long = "bundle-header",
+        env = "BUNDLE_HEADERS",
+        value_delimiter = '\n',
+        value_parser = parse_header,
+        hide_env_values = true
+    )]
+    bundle_headers: Vec<(HeaderName, HeaderValue)>,
+
+    /// Build and sign every transaction and log its hash and raw bytes, but never broadcast it
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Override safety guards: run even when another run holds the lock on one of the wallets, or
+    /// when the gas price fails the sanity check
+    #[arg(long)]
+    force: bool,
+
+    /// Wait for receipts after sending and report how many were mined
+    #[arg(long)]
+    confirm: bool,
+
+    /// Don't show the live count of confirmed, reverted and pending transactions while waiting for
+    /// receipts; it is redrawn in place on a terminal and logged every few seconds otherwise
+    #[arg(long, requires = "confirm")]
+    no_progress: bool,
+
+    /// Fire and forget: hand every send to a background task and exit once all are submitted,
+    /// printing only how many were; nothing is measured or confirmed
+    #[arg(long, conflicts_with_all = ["confirm", "dry_run", "duration", "ramp", "pipeline", "pattern", "warmup"])]
+    no_wait: bool,
+
+    /// Stop the run like Ctrl-C once it has gone on this long, e.g. `10m`, print what completed and
+    /// exit with status 124. A run stalled on a dead endpoint is abandoned shortly after.
+    #[arg(long, value_parser = humantime::parse_duration)]
+    max_runtime: Option<Duration>,
+
+    /// While confirming, rebroadcast transactions that stay unmined with bumped fees (replace-by-fee)
+    #[arg(long, requires = "confirm")]
+    rbf: bool,
+
+    /// How long a transaction may stay unmined before it is replaced, e.g. `20s`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "15s", requires = "rbf")]
+    rbf_after: Duration,
+
+    /// Percentage each replacement raises the fees by; nodes require at least 10
+    #[arg(
+        long,
+        default_value_t = 12,
+        value_parser = clap::value_parser!(u64).range(MIN_REPLACEMENT_BUMP_PCT..),
+        requires = "rbf"
+    )]
+    rbf_bump_pct: u64,
+
+    /// Most replacements per transaction
+    #[arg(long, default_value_t = 3, requires = "rbf")]
+    rbf_max_attempts: u32,
+
+    /// Delay between receipt polling rounds, e.g. `500ms`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "500ms")]
+    poll_interval: Duration,
+
+    /// How long to wait for receipts before counting the rest as unconfirmed, e.g. `2m`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "60s")]
+    confirm_timeout: Duration,
+
+    /// Count a transaction as confirmed only once it is this many blocks deep, its own block
+    /// included, and report the ones reorged out before then; the confirm timeout covers the wait
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    confirmations: u64,
+
+    /// Broadcast transactions reorged out while waiting for `--confirmations` again and keep
+    /// waiting for them, instead of only counting them
+    #[arg(long, requires = "confirm", conflicts_with_all = ["rbf", "bundle_relay"])]
+    rebroadcast_reorged: bool,
+
+    /// After sending, wait up to this long for the mempool to clear the run's transactions and
+    /// report how long it took, e.g. `30s`
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with_all = ["dry_run", "no_wait"])]
+    cooldown: Option<Duration>,
+
+    /// Print the node's pending and queued transaction counts (`txpool_status`) before and after
+    /// sending; skipped with a warning when the node doesn't support it
+    #[arg(long)]
+    inspect_mempool: bool,
+
+    /// Format of the final report
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Write the final report to this file instead of stdout
+    #[arg(long)]
+    output_file: Option<PathBuf>,
+
+    /// Serve live Prometheus metrics on this port at `/metrics` while the run is going
+    #[arg(long)]
+    metrics_port: Option<u16>,
+
+    /// Log the TPS and error rate of the last window this often while the run is going, e.g. `10s`
+    #[arg(long, value_parser = humantime::parse_duration)]
+    report_interval: Option<Duration>,
+
+    /// JSON report of an earlier run to compare against; created from this run when missing
+    #[arg(long)]
+    baseline_file: Option<PathBuf>,
+
+    /// Fail the run when send TPS dropped by more than this many percent from the baseline
+    #[arg(long, default_value_t = 10.0, requires = "baseline_file")]
+    regression_threshold: f64,
+
+    /// Compare against the baseline but leave the file as it is
+    #[arg(long, requires = "baseline_file")]
+    no_update_baseline: bool,
+
+    /// Write the per-second TPS time series to this CSV file
+    #[arg(long)]
+    timeseries_file: Option<PathBuf>,
+
+    /// Write the send latency distribution to this CSV file as `lower_ms,upper_ms,count` rows, one
+    /// per log-spaced bucket (four per decade), for plotting
+    #[arg(long)]
+    latency_histogram_file: Option<PathBuf>,
+
+    /// Write one CSV row per transaction to this file: index, nonce, wallet, hash, send latency,
+    /// result and error, plus block, gas used and status with `--confirm`
+    #[arg(long)]
+    records_file: Option<PathBuf>,
+
+    /// Log level for the benchmark, e.g. `warn` to hide per-phase progress or `debug` to see every
+    /// transaction; full `RUST_LOG` directives such as `info,reqwest=debug` are passed through as is
+    #[arg(long, env = "RUST_LOG", default_value = "info")]
+    log_level: String,
+
+    /// Format of log lines, written to stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Maintenance task to run instead of the benchmark
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Tasks other than the benchmark itself; they share the connection, wallet and fee options
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Replace stuck transactions in a nonce range with zero-value self-transfers at a higher fee
+    Cancel(CancelArgs),
+    /// Build and sign `count` transactions and write them to a file instead of sending them
+    Generate(GenerateArgs),
+    /// Submit pre-signed transactions from a file written by `generate`; no wallet is needed
+    Replay(ReplayArgs),
+    /// Run the same workload against several endpoints one after another and compare them
+    Compare(CompareArgs),
+    /// Binary-search the highest paced rate the endpoint sustains within error and latency bounds
+    Probe(ProbeArgs),
+    /// Check the RPC endpoint and wallets are set up correctly, without sending anything
+    Doctor,
+    /// Deploy the bundled counter contract from the first wallet, then benchmark calls to it;
+    /// `--method` picks the call, `increment()` by default
+    DeployAndBench,
+}
+
+#[derive(Debug, Args)]
+struct ProbeArgs {
+    /// Lowest rate to consider, in tx/s
+    #[arg(long, value_parser = parse_rate, default_value_t = 1.0)]
+    min_rate: f64,
+
+    /// Highest rate to consider, in tx/s
+    #[arg(long, value_parser = parse_rate)]
+    max_rate: f64,
+
+    /// Length of the window run at each candidate rate, e.g. `10s`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "10s")]
+    window: Duration,
+
+    /// Fraction of failed sends (0.0-1.0) above which a rate counts as unsustainable
+    #[arg(long, default_value_t = 0.01)]
+    max_error_rate: f64,
+
+    /// p95 send latency above which a rate counts as unsustainable, e.g. `500ms`
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "1s")]
+    max_p95: Duration,
+
+    /// Stop once the sustained and unsustained rates are within this fraction (0.0-1.0) of each other
+    #[arg(long, default_value_t = 0.05)]
+    resolution: f64,
+
+    /// Most windows to run, however wide the search still is
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+    max_windows: u64,
+}
+
+#[derive(Debug, Args)]
+struct CompareArgs {
+    /// Endpoints to compare, in the order they are benchmarked; repeat the flag or separate with commas
+    #[arg(long = "rpc", value_delimiter = ',', required = true)]
+    rpcs: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+struct GenerateArgs {
+    /// File the raw transactions are written to, one hex string per line
+    #[arg(long)]
+    out_file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct ReplayArgs {
+    /// File of raw transactions to submit, as written by `generate`
+    #[arg(long)]
+    in_file: PathBuf,
+
+    /// Pack this many `eth_sendRawTransaction` calls into each JSON-RPC batch request (HTTP only)
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+    batch_size: u64,
+}
+
+#[derive(Debug, Args)]
+struct CancelArgs {
+    /// First nonce to cancel
+    #[arg(long)]
+    from_nonce: u64,
+
+    /// Last nonce to cancel, inclusive
+    #[arg(long)]
+    to_nonce: u64,
+
+    /// Factor applied to the fees from the gas options, so the replacements outbid what they replace
+    #[arg(long, default_value_t = 2.0)]
+    bump: f64,
+}
+
+/// Rendering of log lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, for log ingestion
+    Json,
+}
+
+/// Rendering of the final run report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable summary
+    Text,
+    /// Machine-readable JSON
+    Json,
+    /// Markdown tables, for pasting into a PR description or wiki
+    Markdown,
+}
+
+impl Cli {
+    /// Validates the arguments and turns them into a benchmark configuration
+    fn to_config(&self) -> Result<Config> {
+        if self.rpc_url.is_empty() && !matches!(self.command, Some(Command::Compare(_))) {
+            bail!("--rpc-url (or RPC_PROVIDER) is required");
+        }
+        // Resolve recipients up front so a bad address fails before any RPC calls
+        let recipients = match (self.recipient, &self.recipients_file) {
+            (Some(recipient), _) => vec![recipient],
+            (None, Some(path)) => load_recipients(path)?,
+            (None, None) => Vec::new(),
+        };
+        if self.no_eip155 && self.tx_type != TxType::Legacy {
+            bail!("--no-eip155 only applies to legacy transactions; pass --tx-type legacy");
+        }
+        let deploying = matches!(self.command, Some(Command::DeployAndBench));
+        if deploying {
+            if !recipients.is_empty() || self.erc20.is_some() {
+                bail!("deploy-and-bench sends to the contract it deploys; drop --to, --recipients-file and --erc20");
+            }
+            if self.dry_run {
+                bail!("deploy-and-bench can't dry-run, since the benchmark needs the deployed contract");
+            }
+        }
+        if self.bundle_relay.is_some() && !matches!(self.command, None | Some(Command::DeployAndBench)) {
+            bail!("--bundle-relay only applies to a benchmark run, not a subcommand");
+        }
+        let bundle = match &self.bundle_relay {
+            Some(url) => Some(BundleRelay {
+                url: url.clone(),
+                size: self.bundle_size as usize,
+                block: self.bundle_block,
+                auth_signer: match &self.bundle_auth_key {
+                    Some(key) => key.parse().context("--bundle-auth-key is not a valid private key")?,
+                    None => LocalWallet::new(&mut rand::thread_rng()),
+                },
+                headers: self.bundle_headers.iter().cloned().collect(),
+            }),
+            None => None,
+        };
+        // Encode contract calls up front too, so a bad signature or argument fails early
+        let data = match (&self.method, &self.data) {
+            (Some(method), _) => encode_call(self.abi.as_deref(), method, &self.args)?,
+            (None, Some(data)) => data.clone(),
+            (None, None) if deploying => encode_call(None, COUNTER_METHOD, &[])?,
+            (None, None) => Bytes::default(),
+        };
+        // Replayed transactions are already signed
+        let wallets = match self.command {
+            Some(Command::Replay(_)) => Vec::new(),
+            _ => self.wallets()?,
+        };
+        let probing = matches!(self.command, Some(Command::Probe(_)));
+        if self.arrival == Arrival::Poisson && self.rate.is_none() && self.ramp.is_none() && !probing {
+            bail!("--arrival poisson needs a target rate from --rate, --ramp or probe");
+        }
+
+        if self.cooldown.is_some() && matches!(self.command, Some(Command::Replay(_))) {
+            bail!("--cooldown is not supported for replay, which doesn't know the wallets it sends from");
+        }
+        if self.records_file.is_some() && matches!(self.command, Some(Command::Replay(_))) {
+            bail!("--records-file is not supported for replay, which doesn't know the transactions it sends");
+        }
+        if self.rebroadcast_reorged && self.confirmations < 2 {
+            bail!("--rebroadcast-reorged needs --confirmations 2 or more, since reorgs are watched for while waiting for depth");
+        }
+        if !(self.gas_sanity_factor >= 1.0 && self.gas_sanity_factor.is_finite()) {
+            bail!("--gas-sanity-factor must be at least 1, got {}", self.gas_sanity_factor);
+        }
+        if let Some(pct) = self.gas_escalation_pct {
+            // Fees compound in hundredths of a percent
+            if !(pct >= 0.01 && pct.is_finite()) {
+                bail!("--gas-escalation-pct must be at least 0.01, got {}", pct);
+            }
+            if self.max_gas_price.is_none() {
+                bail!("--gas-escalation-pct needs --max-gas-price, or fees compound without bound");
+            }
+        }
+
+        let ramp = self.ramp.map(|(start, end, step)| RampProfile {
+            start,
+            end,
+            step,
+            window: self.ramp_window,
+            fail_threshold: self.ramp_fail_threshold,
+        });
+
+        let probe = match &self.command {
+            Some(Command::Probe(args)) => {
+                if self.ramp.is_some()
+                    || self.count.is_some()
+                    || self.duration.is_some()
+                    || self.rate.is_some()
+                    || self.pattern.is_some()
+                    || self.pipeline
+                {
+                    bail!("probe picks its own rates and windows; drop --ramp, --count, --duration, --rate, --pattern and --pipeline");
+                }
+                if args.min_rate >= args.max_rate {
+                    bail!("--min-rate ({}) must be below --max-rate ({})", args.min_rate, args.max_rate);
+                }
+                Some(ProbeProfile {
+                    min: args.min_rate,
+                    max: args.max_rate,
+                    window: args.window,
+                    max_error_rate: args.max_error_rate,
+                    max_p95: args.max_p95,
+                    resolution: args.resolution,
+                    max_windows: args.max_windows as usize,
+                })
+            }
+            _ => None,
+        };
+
+        // Ramp, probe, replay and cancel decide for themselves how much to send; the rest need to be told
+        let sized_by_command = self.ramp.is_some()
+            || matches!(self.command, Some(Command::Probe(_) | Command::Replay(_) | Command::Cancel(_)));
+        let workload = match (self.count, self.duration) {
+            (Some(_), Some(_)) => bail!("--count and --duration are alternatives; pass only one"),
+            (Some(count), None) => Workload::Count(count),
+            (None, Some(_)) if matches!(self.command, Some(Command::Generate(_))) => {
+                bail!("generate signs a fixed batch; pass --count instead of --duration")
+            }
+            (None, Some(duration)) => Workload::Duration(duration),
+            (None, None) if sized_by_command => Workload::Count(0),
+            (None, None) => bail!("pass --count <n> to send a fixed batch or --duration <time> for a sustained run"),
+        };
+
+        let funding = match (&self.fund_from, self.fund_amount) {
+            (Some(key), Some(amount)) => Some(FundingConfig {
+                funder: key.parse().context("--fund-from is not a valid private key")?,
+                amount,
+            }),
+            _ => None,
+        };
+
+        Ok(Config {
+            rpc_urls: self.rpc_url.clone(),
+            rpc_headers: self.rpc_headers.iter().cloned().collect::<HeaderMap>(),
+            chain_id: self.chain_id,
+            wallets,
+            nonce_source: self.nonce_source,
+            wallet_selection: self.wallet_selection,
+            at_block: self.at_block,
+            nonce_state_file: self.nonce_state_file.clone(),
+            funding,
+            workload,
+            warmup: self.warmup,
+            ramp,
+            probe,
+            pipeline: self.pipeline,
+            pipeline_buffer: self.pipeline_buffer,
+            gas_strategy: match (&self.gas_strategy, self.fee_percentile, &self.gas_oracle, self.gas_price) {
+                (Some(strategy), _, _, _) => strategy.clone(),
+                (None, Some(percentile), _, _) => GasStrategy::Percentile(percentile),
+                (None, None, Some(url), _) => GasStrategy::External(GasOracle { url: url.clone(), field: self.gas_oracle_field.clone() }),
+                (None, None, None, Some(gas_price)) => GasStrategy::Fixed(gas_price),
+                (None, None, None, None) => GasStrategy::MultipleOfBase(self.gas_multiplier),
+            },
+            max_gas_price: self.max_gas_price,
+            gas_escalation_pct: self.gas_escalation_pct,
+            gas_sanity_factor: (!self.no_gas_sanity_check).then_some(self.gas_sanity_factor),
+            force: self.force,
+            tx_type: self.tx_type,
+            eip155: !self.no_eip155,
+            recipients,
+            value: self.value,
+            value_jitter_pct: self.value_jitter_pct,
+            erc20: self.erc20,
+            data,
+            access_list: match (&self.access_list, self.access_list_from_tx) {
+                (Some(access_list), _) => Some(AccessListSource::Fixed(access_list.clone())),
+                (None, true) => Some(AccessListSource::Generated),
+                (None, false) => None,
+            },
+            gas_limit: self.gas_limit,
+            gas_buffer_pct: self.gas_buffer_pct,
+            max_concurrency: self.max_concurrency,
+            adaptive_concurrency: self.adaptive,
+            rate: self.rate,
+            arrival: self.arrival,
+            burst: self.pattern,
+            seed: self.seed,
+            retry: RetryPolicy {
+                retries: self.retries,
+                base_delay: self.retry_backoff,
+            },
+            send_timeout: self.send_timeout,
+            batch_size: match &self.command {
+                Some(Command::Replay(args)) => args.batch_size as usize,
+                _ => 1,
+            },
+            bundle,
+            fill_gaps: self.fill_gaps,
+            dry_run: self.dry_run,
+            confirm: self.confirm,
+            progress: !self.no_progress,
+            records: self.records_file.is_some(),
+            rbf: self.rbf.then_some(RbfPolicy {
+                after: self.rbf_after,
+                bump_pct: self.rbf_bump_pct,
+                max_attempts: self.rbf_max_attempts,
+                max_gas_price: self.max_gas_price,
+            }),
+            poll_interval: self.poll_interval,
+            confirm_timeout: self.confirm_timeout,
+            confirmations: self.confirmations,
+            rebroadcast_reorged: self.rebroadcast_reorged,
+            cooldown: self.cooldown,
+            inspect_mempool: self.inspect_mempool,
+        })
+    }
+
+    /// Loads the sending wallets from whichever key source was given: a mnemonic, keystore files or
+    /// private keys. More than one is refused, since it's unclear which wallets were meant.
+    fn wallets(&self) -> Result<Vec<LocalWallet>> {
+        let sources: Vec<&str> = [
+            (self.private_key.is_some(), "--private-key (or PRIVATE_KEY_1)"),
+            (self.mnemonic.is_some(), "--mnemonic (or MNEMONIC)"),
+            (!self.keystore.is_empty(), "--keystore"),
+        ]
+        .into_iter()
+        .filter_map(|(given, name)| given.then_some(name))
+        .collect();
+        if sources.len() > 1 {
+            bail!("only one key source can be used, got {}", sources.join(" and "));
+        }
+
+        if !self.keystore.is_empty() {
+            let password = match &self.keystore_password {
+                Some(password) => password.clone(),
+                None => prompt_password("Keystore password: ")?,
+            };
+            return self
+                .keystore
+                .iter()
+                .map(|path| {
+                    LocalWallet::decrypt_keystore(path, &password)
+                        .with_context(|| format!("failed to decrypt keystore {}; is the password right?", path.display()))
+                })
+                .collect();
+        }
+
+        if let Some(mnemonic) = &self.mnemonic {
+            if self.account_count == 0 {
+                bail!("--account-count must be at least 1");
+            }
+            let end = self.account_start.checked_add(self.account_count).context("account range overflows")?;
+            return (self.account_start..end)
+                .map(|index| {
+                    MnemonicBuilder::<English>::default()
+                        .phrase(mnemonic.as_str())
+                        .index(index)?
+                        .build()
+                        .with_context(|| format!("failed to derive account {} from the mnemonic", index))
+                })
+                .collect();
+        }
+
+        if self.wallets == 0 {
+            bail!("--wallets must be at least 1");
+        }
+        let private_key = self.private_key.as_ref().context("--private-key, --mnemonic or --keystore is required")?;
+        let mut wallets: Vec<LocalWallet> =
+            vec![private_key.parse().context("--private-key (or PRIVATE_KEY_1) is not a valid private key")?];
+        for i in 2..=self.wallets {
+            let var = format!("PRIVATE_KEY_{}", i);
+            let key = env::var(&var).with_context(|| format!("{} must be set for --wallets {}", var, self.wallets))?;
+            wallets.push(key.parse().with_context(|| format!("{} is not a valid private key", var))?);
+        }
+        Ok(wallets)
+    }
+}
+
+/// Parses a `Name: value` HTTP header. The value is marked sensitive so it never shows up in debug output.
+fn parse_header(s: &str) -> Result<(HeaderName, HeaderValue), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("invalid header {:?}: expected `Name: value`", s))?;
+    let name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|e| format!("invalid header name {:?}: {}", name, e))?;
+    let mut value = HeaderValue::from_str(value.trim()).map_err(|e| format!("invalid value for header {}: {}", name, e))?;
+    value.set_sensitive(true);
+    Ok((name, value))
+}
+
+/// Parses an access list given as JSON
+fn parse_access_list(s: &str) -> Result<AccessList, String> {
+    serde_json::from_str(s).map_err(|e| format!("invalid access list: {}", e))
+}
+
+/// Parses a decimal integer into a U256 (`U256::from_str` would treat it as hex)
+fn parse_u256(s: &str) -> Result<U256, String> {
+    U256::from_dec_str(s).map_err(|e| format!("invalid integer {:?}: {}", s, e))
+}
+
+/// Parses a decimal block number or a `latest` or `pending` tag for `--at-block`
+fn parse_block(s: &str) -> Result<BlockNumber, String> {
+    match s {
+        "latest" => Ok(BlockNumber::Latest),
+        "pending" => Ok(BlockNumber::Pending),
+        _ => s
+            .parse::<u64>()
+            .map(BlockNumber::from)
+            .map_err(|_| format!("expected a block number, latest or pending, got {:?}", s)),
+    }
+}
+
+/// Parses a gwei amount, fractions allowed, into wei
+fn parse_gwei(s: &str) -> Result<U256, String> {
+    parse_units(s, "gwei")
+        .map(Into::into)
+        .map_err(|e| format!("invalid gwei amount {:?}: {}", s, e))
+}
+
+/// Parses a positive send rate for `--rate`
+fn parse_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(rate) if rate > 0.0 && rate.is_finite() => Ok(rate),
+        Ok(rate) => Err(format!("rate must be positive, got {}", rate)),
+        Err(e) => Err(format!("invalid rate {:?}: {}", s, e)),
+    }
+}
+
+/// Parses `start,end,step` send rates for `--ramp`
+fn parse_ramp(s: &str) -> Result<(f64, f64, f64), String> {
+    let parts: Vec<f64> = s
+        .split(',')
+        .map(|part| part.trim().parse::<f64>().map_err(|e| format!("invalid rate {:?}: {}", part, e)))
+        .collect::<Result<_, _>>()?;
+    let [start, end, step] = parts[..] else {
+        return Err(format!("expected start,end,step, got {:?}", s));
+    };
+    if start <= 0.0 || step <= 0.0 {
+        return Err("start and step must be positive".to_string());
+    }
+    if end < start {
+        return Err(format!("end rate {} is below start rate {}", end, start));
+    }
+    Ok((start, end, step))
+}
+
+/// Asks for a password on stderr and reads it from stdin, hiding the input when it's a terminal
+fn prompt_password(prompt: &str) -> Result<String> {
+    use std::io::{BufRead, IsTerminal, Write};
+    let stdin = std::io::stdin();
+    let hide = stdin.is_terminal();
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+    let set_echo = |on: bool| {
+        let _ = std::process::Command::new("stty").arg(if on { "echo" } else { "-echo" }).status();
+    };
+    if hide {
+        set_echo(false);
+    }
+    let mut password = String::new();
+    let read = stdin.lock().read_line(&mut password);
+    if hide {
+        set_echo(true);
+        eprintln!();
+    }
+    read.context("failed to read the keystore password")?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Runs the setup checklist, exiting with status 1 if anything failed
+async fn doctor(cli: &Cli) -> Result<()> {
+    let mut report = DoctorReport::default();
+    let has_rpc = report.record(
+        "RPC URL set",
+        match cli.rpc_url.is_empty() {
+            true => Err("set RPC_PROVIDER or pass --rpc-url".to_string()),
+            false => Ok(cli.rpc_url.join(", ")),
+        },
+    );
+    let has_keys = report.record(
+        "Wallet key set",
+        match (&cli.private_key, &cli.mnemonic, cli.keystore.is_empty()) {
+            (Some(_), _, _) => Ok("from --private-key or PRIVATE_KEY_1".to_string()),
+            (_, Some(_), _) => Ok("from --mnemonic or MNEMONIC".to_string()),
+            (_, _, false) => Ok("from --keystore".to_string()),
+            (None, None, true) => Err("set PRIVATE_KEY_1, or pass --private-key, --mnemonic or --keystore".to_string()),
+        },
+    );
+    let wallets = if has_keys {
+        match cli.wallets() {
+            Ok(wallets) => {
+                let addresses: Vec<String> = wallets.iter().map(|wallet| format!("{:?}", wallet.address())).collect();
+                report.record("Wallets parse", Ok(addresses.join(", ")));
+                wallets
+            }
+            Err(e) => {
+                report.record("Wallets parse", Err(format!("{:#}", e)));
+                Vec::new()
+            }
+        }
+    } else {
+        report.skip("Wallets parse", "no wallet key is set");
+        Vec::new()
+    };
+
+    if has_rpc {
+        check_endpoint(&mut report, &cli.rpc_url, &cli.rpc_headers.iter().cloned().collect(), &wallets).await;
+    } else {
+        report.skip("RPC reachable", "no RPC URL is set");
+    }
+
+    let rendered = match cli.output {
+        OutputFormat::Json => report.to_json()?,
+        OutputFormat::Text | OutputFormat::Markdown => report.to_string(),
+    };
+    write_report(cli, rendered)?;
+    if !report.passed() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Sends logs to stderr so stdout only carries the report
+fn init_logging(cli: &Cli) -> Result<()> {
+    // A bare level only applies to this crate so `debug` doesn't turn on every dependency's logs
+    let directives = if cli.log_level.contains('=') {
+        cli.log_level.clone()
+    } else {
+        format!("warn,rust_web3_utils={0},dump={0}", cli.log_level)
+    };
+    let filter = EnvFilter::try_new(&directives).with_context(|| format!("invalid log level {:?}", cli.log_level))?;
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter).with_writer(std::io::stderr).with_target(false);
+    match cli.log_format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    // Load .env before parsing so that env-backed flags can fall back to it
     dotenv().ok();
-    
-    // Parse command line arguments
-    let args: Vec<String> = std::env::args().collect();
-    let num_transactions = if args.len() > 1 {
-        args[1].parse::<u64>().unwrap_or(10)
+
+    // Parse command line arguments first, since they size the runtime
+    let cli = parse_cli();
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    runtime.enable_all();
+    if let Some(worker_threads) = cli.worker_threads {
+        runtime.worker_threads(worker_threads as usize);
+    }
+    runtime.build().context("failed to start the async runtime")?.block_on(run(cli))
+}
+
+/// Parses the command line, filling in flags it leaves out from the `--config` file if one is given
+fn parse_cli() -> Cli {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let Some(path) = &cli.config else {
+        return cli;
+    };
+    let file_args = match config_file::load(path, &Cli::command(), &matches) {
+        Ok(file_args) => file_args,
+        Err(e) => Cli::command().error(clap::error::ErrorKind::Io, format!("{:#}", e)).exit(),
+    };
+    // The file's flags go first so the subcommand and its arguments stay at the end
+    let mut args: Vec<OsString> = env::args_os().collect();
+    args.splice(1..1, file_args.args);
+    let mut cli = Cli::parse_from(args);
+    cli.unknown_config_keys = file_args.unknown;
+    cli
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    init_logging(&cli)?;
+    for key in &cli.unknown_config_keys {
+        warn!("Ignoring unknown key {:?} in the config file", key);
+    }
+    if let Some(Command::Doctor) = &cli.command {
+        return doctor(&cli).await;
+    }
+    let config = cli.to_config()?;
+
+    // Two runs sharing a key would hand out the same nonces; generating or dry-running broadcasts nothing
+    let broadcasts = !config.dry_run && !matches!(cli.command, Some(Command::Generate(_)));
+    let wallet_lock = if broadcasts {
+        Some(WalletLock::acquire(config.wallets.iter().map(Signer::address), cli.force)?)
     } else {
-        10 // Default to 10 transactions
+        None
     };
-    
-    // Setup connection
-    let rpc_url = env::var("RPC_PROVIDER").expect("RPC_PROVIDER must be set");
-    let private_key = env::var("PRIVATE_KEY_1").expect("PRIVATE_KEY_1 must be set");
-    
-    let rpc_url_display = rpc_url.clone();
-    let provider = Provider::<Http>::try_from(rpc_url)?;
-    let wallet: LocalWallet = private_key.parse()?;
-    let wallet_address = wallet.address();
-    let chain_id = provider.get_chainid().await?;
-    let wallet = wallet.with_chain_id(chain_id.as_u64());
-    
-    let client = Arc::new(SignerMiddleware::new(provider, wallet));
-    
-    // Make necessary RPC calls before the transaction loop
-    let starting_nonce = client.get_transaction_count(wallet_address, None).await?.as_u64();
-    let default_gas_price = client.get_gas_price().await?;
-    let gas_price: U256 = default_gas_price * 3; // Use 3x the default gas price
-    
-    // Display info
-    println!("RPC URL: {}", rpc_url_display);
-    println!("Chain ID: {}", chain_id);
-    println!("Wallet address: {}", wallet_address);
-    println!("Starting nonce: {}", starting_nonce);
-    println!("Default gas price: {} gwei", default_gas_price.as_u64() / 1_000_000_000);
-    println!("Using gas price (3x): {} gwei", gas_price.as_u64() / 1_000_000_000);
-    
-    // Start timer for entire batch
-    let batch_start_time = Instant::now();
-    
-    println!("\nPreparing {} transactions...", num_transactions);
-    
-    let mut prepared_txs = Vec::with_capacity(num_transactions as usize);
-    
-    // First, create all transactions (without signing)
-    let prep_start = Instant::now();
-    for i in 0..num_transactions {
-        let nonce = starting_nonce + i;
-        
-        match create_transaction(client.clone(), nonce, gas_price).await {
-            Ok(tx) => {
-                println!("TX #{} prepared with nonce: {}", i + 1, nonce);
-                prepared_txs.push((i, nonce, tx));
-            },
-            Err(e) => {
-                println!("Failed to prepare TX #{}: {}", i + 1, e);
+    // Shared with the signal handler, which may have to release it before force-quitting
+    let wallet_lock = Arc::new(Mutex::new(wallet_lock));
+    let release_wallets = |lock: &Mutex<Option<WalletLock>>| drop(lock.lock().expect("wallet lock poisoned").take());
+
+    let mut config = config;
+    let deployment = match &cli.command {
+        Some(Command::DeployAndBench) => {
+            let deployment = deploy_contract(&config, counter_init_code()).await?;
+            info!("Deployed the benchmark target at {}", deployment);
+            config.recipients = vec![deployment.address];
+            Some(deployment)
+        }
+        _ => None,
+    };
+
+    match &cli.command {
+        Some(Command::Cancel(args)) => {
+            let report = cancel_nonces(&config, args.from_nonce..=args.to_nonce, args.bump).await?;
+            let rendered = match cli.output {
+                OutputFormat::Text | OutputFormat::Markdown => report.to_string(),
+                OutputFormat::Json => report.to_json()?,
+            };
+            return write_report(&cli, rendered);
+        }
+        Some(Command::Generate(args)) => {
+            let file = Benchmarker::new(config).generate().await?;
+            file.save(&args.out_file)?;
+            info!("{} signed transactions saved to: {}", file.txs.len(), args.out_file.display());
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // Ctrl-C stops launching sends and still prints the summary of what completed; a second one
+    // quits right away
+    let benchmarker = Benchmarker::new(config.clone());
+    let shutdown = benchmarker.shutdown();
+    let signal = shutdown.clone();
+    // Weak, so returning from the run still releases the wallets on drop
+    let held_lock = Arc::downgrade(&wallet_lock);
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        warn!("Interrupted, waiting briefly for in-flight sends... (press Ctrl-C again to quit now)");
+        signal.trigger();
+        if tokio::signal::ctrl_c().await.is_ok() {
+            warn!("Interrupted again, quitting without a summary");
+            if let Some(lock) = held_lock.upgrade() {
+                release_wallets(&lock);
             }
+            std::process::exit(EXIT_INTERRUPTED);
         }
+    });
+    // --max-runtime stops the run the same way once it expires
+    let deadline = cli.max_runtime.map(|limit| {
+        let signal = shutdown.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(limit).await;
+            warn!("Maximum runtime of {:?} reached, waiting briefly for in-flight sends...", limit);
+            signal.trigger();
+        })
+    });
+    let interrupted_status = || match &deadline {
+        Some(deadline) if deadline.is_finished() => EXIT_TIMED_OUT,
+        _ => EXIT_INTERRUPTED,
+    };
+
+    if let Some(Command::Compare(args)) = &cli.command {
+        if args.rpcs.len() < 2 {
+            bail!("compare needs at least two endpoints, got {}", args.rpcs.len());
+        }
+        let comparison = compare_endpoints(&config, &args.rpcs, shutdown.clone()).await?;
+        let rendered = match cli.output {
+            OutputFormat::Text => comparison.to_string(),
+            OutputFormat::Json => comparison.to_json()?,
+            OutputFormat::Markdown => comparison.to_markdown(),
+        };
+        write_report(&cli, rendered)?;
+        if shutdown.is_requested() {
+            // Exiting skips destructors, so release the wallets first
+            release_wallets(&wallet_lock);
+            std::process::exit(interrupted_status());
+        }
+        return Ok(());
     }
-    let prep_duration = prep_start.elapsed();
-    println!("All transactions prepared in {:?} ({:.2} tx/s)", 
-             prep_duration, 
-             prepared_txs.len() as f64 / prep_duration.as_secs_f64());
-    
-    // Now send all transactions in parallel without awaiting each one
-    println!("\nSubmitting all transactions in parallel...");
-    let mut futures = Vec::with_capacity(prepared_txs.len());
-    let mut sent_txs = Vec::with_capacity(prepared_txs.len());
-    
-    // Create futures for all the transactions
-    for (i, nonce, tx) in prepared_txs {
-        let client_clone = client.clone();
-        
-        futures.push(async move {
-            let result = send_transaction(client_clone, tx).await;
-            (i, nonce, result)
+
+    if let Some(port) = cli.metrics_port {
+        let metrics = benchmarker.metrics();
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(metrics, port).await {
+                warn!("{:#}", e);
+            }
         });
     }
-    
-    // Execute all sends in parallel
-    let sending_start = Instant::now();
-    let results = join_all(futures).await;
-    let sending_duration = sending_start.elapsed();
-    
-    // Process results
-    for (i, nonce, result) in results {
-        match result {
-            Ok(hash) => {
-                println!("TX #{} (nonce: {}): hash {}", i + 1, nonce, hash);
-                sent_txs.push(hash);
+
+    if cli.report_interval.is_some_and(|interval| interval.is_zero()) {
+        bail!("--report-interval must be longer than zero");
+    }
+    if cli.no_wait {
+        if cli.command.is_some() {
+            bail!("--no-wait only applies to a plain batch run, not a subcommand");
+        }
+        let submitted = benchmarker.submit_detached().await?;
+        println!("Submitted {} transactions", submitted);
+        return Ok(());
+    }
+
+    let live_report = cli.report_interval.map(|interval| tokio::spawn(report_live(benchmarker.metrics(), interval)));
+
+    if cli.rounds > 1 {
+        if !matches!(cli.command, None | Some(Command::DeployAndBench)) {
+            bail!("--rounds only applies to a benchmark run, not a subcommand");
+        }
+        let mut rounds = RoundsReport::default();
+        let run = run_rounds(&benchmarker, cli.rounds as usize, &mut rounds);
+        match cli.max_runtime {
+            // As with a single run, a round hung on a stalled endpoint is given up on after the grace
+            // period, keeping the rounds that finished
+            Some(limit) => match tokio::time::timeout(limit + MAX_RUNTIME_GRACE, run).await {
+                Ok(result) => result?,
+                Err(_) => warn!("Round did not stop within {:?} of --max-runtime, abandoning it", MAX_RUNTIME_GRACE),
             },
-            Err(e) => {
-                println!("TX #{} (nonce: {}): error: {}", i + 1, nonce, e);
+            None => run.await?,
+        }
+        if let Some(live_report) = live_report {
+            live_report.abort();
+        }
+        let rendered = match cli.output {
+            OutputFormat::Text => rounds.to_string(),
+            OutputFormat::Json => rounds.to_json()?,
+            OutputFormat::Markdown => rounds.to_markdown(),
+        };
+        write_report(&cli, rendered)?;
+        if shutdown.is_requested() {
+            release_wallets(&wallet_lock);
+            std::process::exit(interrupted_status());
+        }
+        return Ok(());
+    }
+
+    let started = Instant::now();
+    let run = async {
+        match &cli.command {
+            Some(Command::Replay(args)) => benchmarker.replay(RawTxFile::load(&args.in_file)?).await,
+            _ => benchmarker.run().await,
+        }
+    };
+    let mut report = match cli.max_runtime {
+        // A call hung on a stalled endpoint never sees the stop, so the run is given up on after a
+        // grace period and summarised from the live counters
+        Some(limit) => match tokio::time::timeout(limit + MAX_RUNTIME_GRACE, run).await {
+            Ok(report) => report?,
+            Err(_) => {
+                warn!("Run did not stop within {:?} of --max-runtime, abandoning it", MAX_RUNTIME_GRACE);
+                let (sent, failed) = benchmarker.metrics().totals();
+                RunReport {
+                    total_sent: sent as usize,
+                    total_failed: failed as usize,
+                    interrupted: true,
+                    batch_duration: started.elapsed(),
+                    send_duration: started.elapsed(),
+                    ..RunReport::default()
+                }
             }
+        },
+        None => run.await?,
+    };
+
+    if let Some(live_report) = live_report {
+        live_report.abort();
+    }
+    report.deployment = deployment;
+
+    // Compare with the previous run before anything overwrites it
+    let regression = match &cli.baseline_file {
+        Some(path) => Baseline::load(path)?.map(|baseline| Regression::new(baseline, &report)),
+        None => None,
+    };
+
+    // Print or save the summary in the requested format
+    let rendered = match (cli.output, &regression) {
+        (OutputFormat::Text, Some(regression)) => format!("{}\n{}", report, regression),
+        (OutputFormat::Text, None) => report.to_string(),
+        (OutputFormat::Json, _) => report.to_json()?,
+        (OutputFormat::Markdown, Some(regression)) => format!("{}\n{}\n", report.to_markdown(), regression),
+        (OutputFormat::Markdown, None) => report.to_markdown(),
+    };
+    write_report(&cli, rendered)?;
+
+    let regressed = regression.is_some_and(|regression| regression.regressed(cli.regression_threshold));
+    if let Some(path) = &cli.baseline_file {
+        // A regressed, partial or dry run would make a misleading baseline for the next one
+        if cli.no_update_baseline || regressed || report.interrupted || report.dry_run {
+            info!("Baseline left unchanged: {}", path.display());
+        } else {
+            Baseline::save(&report, path)?;
+            info!("Baseline saved to: {}", path.display());
         }
     }
-    
-    println!("All transactions submitted in {:?} ({:.2} tx/s)", 
-             sending_duration, 
-             sent_txs.len() as f64 / sending_duration.as_secs_f64());
-    
-    let batch_elapsed = batch_start_time.elapsed();
-    
-    // Print summary
-    println!("\n===== SUMMARY =====");
-    println!("Total time to send all transactions: {:?}", batch_elapsed);
-    println!("Transactions per second: {:.2}", num_transactions as f64 / batch_elapsed.as_secs_f64());
-    println!("Total transactions sent: {}", sent_txs.len());
-    
+
+    if let Some(path) = &cli.timeseries_file {
+        fs::write(path, report.timeseries_csv())?;
+        info!("TPS time series saved to: {}", path.display());
+    }
+
+    if let Some(path) = &cli.latency_histogram_file {
+        fs::write(path, report.latency_histogram.to_csv())?;
+        info!("Send latency histogram saved to: {}", path.display());
+    }
+
+    if let Some(path) = &cli.records_file {
+        fs::write(path, report.records_csv())?;
+        info!("{} transaction records saved to: {}", report.records.len(), path.display());
+    }
+
+    if report.interrupted {
+        release_wallets(&wallet_lock);
+        std::process::exit(interrupted_status());
+    }
+    if let Some(regression) = regression.filter(|_| regressed) {
+        bail!(
+            "send TPS dropped {:.1}% from the baseline, more than the {}% allowed",
+            -regression.tps_change_pct(),
+            cli.regression_threshold
+        );
+    }
+
+    Ok(())
+}
+
+/// Prints a rendered report, or saves it when an output file was given
+fn write_report(cli: &Cli, rendered: String) -> Result<()> {
+    match &cli.output_file {
+        Some(path) => {
+            fs::write(path, rendered + "\n")?;
+            info!("Report saved to: {}", path.display());
+        }
+        None => println!("\n{}", rendered),
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,64 @@
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt};
+
+use crate::send_error::SendError;
+
+/// Broad reason a send failed, as counted in the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// The node already has the transaction, e.g. when resending after an interruption; it is in
+    /// flight, so sends rejected this way count as accepted
+    AlreadyKnown,
+    Timeout,
+    NonceTooLow,
+    ReplacementUnderpriced,
+    InsufficientFunds,
+    RateLimited,
+    Other,
+}
+
+impl FailureKind {
+    /// Classifies a send error, see [`SendError::of`]
+    pub fn classify(err: &anyhow::Error) -> Self {
+        SendError::of(err).kind()
+    }
+
+    /// Classifies a node's rejection message, e.g. one entry of a JSON-RPC batch response
+    pub fn classify_message(message: &str) -> Self {
+        SendError::from_rejection(message).kind()
+    }
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FailureKind::AlreadyKnown => "already known",
+            FailureKind::Timeout => "timeout",
+            FailureKind::NonceTooLow => "nonce too low",
+            FailureKind::ReplacementUnderpriced => "replacement underpriced",
+            FailureKind::InsufficientFunds => "insufficient funds",
+            FailureKind::RateLimited => "rate limited",
+            FailureKind::Other => "other",
+        })
+    }
+}
+
+/// Number of failed sends per reason
+pub type FailureHistogram = BTreeMap<FailureKind, usize>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn already_known_is_told_apart_from_other_rejections() {
+        assert_eq!(FailureKind::classify_message("already known"), FailureKind::AlreadyKnown);
+        assert_eq!(
+            FailureKind::classify(&anyhow!("(code: -32000, message: replacement transaction underpriced)")),
+            FailureKind::ReplacementUnderpriced
+        );
+        assert_ne!(FailureKind::classify_message("unknown transaction"), FailureKind::AlreadyKnown);
+    }
+}
@@ -0,0 +1,408 @@
+use anyhow::{bail, Result};
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, U256},
+    utils::{format_units, parse_units},
+};
+use std::{fmt, str::FromStr};
+use tracing::warn;
+
+use crate::gas_oracle::GasOracle;
+
+/// Blocks of fee history the oracle strategy looks at
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// Reward percentile the oracle strategy takes the tip from when the node has no tip suggestion
+const ORACLE_REWARD_PERCENTILE: f64 = 50.0;
+
+/// Tip used when a plain gas price has to be turned into EIP-1559 fees
+const DEFAULT_PRIORITY_FEE: u64 = 1_000_000_000; // 1 gwei
+
+/// Fee parameters used to build a transaction, selecting between legacy and EIP-1559 envelopes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeMode {
+    Legacy { gas_price: U256 },
+    Eip1559 { max_fee: U256, max_priority_fee: U256 },
+}
+
+impl FeeMode {
+    /// Fee parameters for a single gas price. EIP-1559 uses the same layout as the sync binary:
+    /// 1 gwei tip, max fee at least twice the tip.
+    pub fn from_gas_price(gas_price: U256, eip1559: bool) -> Self {
+        if !eip1559 {
+            return FeeMode::Legacy { gas_price };
+        }
+        let max_priority_fee = U256::from(DEFAULT_PRIORITY_FEE);
+        let max_fee = if gas_price > max_priority_fee {
+            gas_price
+        } else {
+            max_priority_fee * 2
+        };
+        FeeMode::Eip1559 { max_fee, max_priority_fee }
+    }
+
+    /// Caps the per-gas price at `max_gas_price`, lowering the tip too if it would exceed the cap
+    pub fn clamp(self, max_gas_price: U256) -> Self {
+        match self {
+            FeeMode::Legacy { gas_price } => FeeMode::Legacy { gas_price: gas_price.min(max_gas_price) },
+            FeeMode::Eip1559 { max_fee, max_priority_fee } => FeeMode::Eip1559 {
+                max_fee: max_fee.min(max_gas_price),
+                max_priority_fee: max_priority_fee.min(max_gas_price),
+            },
+        }
+    }
+
+    /// Raises every fee by `pct` percent compounded `steps` times, e.g. once per transaction index so
+    /// later nonces always outbid earlier ones, then caps it at `max_gas_price`. The percentage counts
+    /// to 0.01, and each step adds at least 1 wei, so a nonzero fee keeps rising until it hits the cap.
+    pub fn escalate(self, pct: f64, steps: u64, max_gas_price: Option<U256>) -> Self {
+        let basis_points = ((pct * 100.0).round() as u64).max(1);
+        let cap = max_gas_price.unwrap_or(U256::MAX);
+        let escalated = match self {
+            FeeMode::Legacy { gas_price } => FeeMode::Legacy { gas_price: compound(gas_price, basis_points, steps, cap) },
+            FeeMode::Eip1559 { max_fee, max_priority_fee } => FeeMode::Eip1559 {
+                max_fee: compound(max_fee, basis_points, steps, cap),
+                max_priority_fee: compound(max_priority_fee, basis_points, steps, cap),
+            },
+        };
+        escalated.clamp(cap)
+    }
+
+    /// Upper bound of the per-gas price this fee mode can be charged
+    pub fn max_fee_per_gas(&self) -> U256 {
+        match self {
+            FeeMode::Legacy { gas_price } => *gas_price,
+            FeeMode::Eip1559 { max_fee, .. } => *max_fee,
+        }
+    }
+}
+
+impl fmt::Display for FeeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeMode::Legacy { gas_price } => write!(f, "gas price {} gwei", gwei(*gas_price)),
+            FeeMode::Eip1559 { max_fee, max_priority_fee } => {
+                write!(f, "max fee {} gwei, priority fee {} gwei", gwei(*max_fee), gwei(*max_priority_fee))
+            }
+        }
+    }
+}
+
+/// How the fees of every transaction are chosen
+#[derive(Debug, Clone, PartialEq)]
+pub enum GasStrategy {
+    /// Literal gas price in wei
+    Fixed(U256),
+    /// The node's `eth_gasPrice` scaled by a multiplier; for EIP-1559 only the tip is scaled
+    MultipleOfBase(f64),
+    /// Next block's base fee plus the tip suggested by `eth_maxPriorityFeePerGas`,
+    /// or the median reward of recent blocks when the node has no suggestion
+    Oracle,
+    /// Tip taken from this reward percentile of recent blocks, on top of twice the next base fee
+    Percentile(f64),
+    /// Gas price recommended by an external oracle, or the node's `eth_gasPrice` when the oracle
+    /// can't be reached
+    External(GasOracle),
+}
+
+impl Default for GasStrategy {
+    fn default() -> Self {
+        GasStrategy::MultipleOfBase(3.0)
+    }
+}
+
+impl GasStrategy {
+    /// Queries whatever the strategy needs from the node and returns the fees to sign with
+    pub async fn fee_mode<M: Middleware>(&self, client: &M, eip1559: bool) -> Result<FeeMode>
+    where
+        M::Error: 'static,
+    {
+        match *self {
+            GasStrategy::Fixed(gas_price) => Ok(FeeMode::from_gas_price(gas_price, eip1559)),
+            GasStrategy::MultipleOfBase(multiplier) => {
+                if multiplier <= 0.0 {
+                    bail!("gas multiplier must be positive, got {}", multiplier);
+                }
+                let gas_price = client.get_gas_price().await?;
+                if !eip1559 {
+                    return Ok(FeeMode::Legacy { gas_price: apply_gas_multiplier(gas_price, multiplier) });
+                }
+                let history = client.fee_history(1, BlockNumber::Latest, &[]).await?;
+                // The last entry is the base fee of the block after the newest one
+                let base_fee = history.base_fee_per_gas.last().copied().filter(|base_fee| !base_fee.is_zero());
+                let tip = match client.provider().request::<_, U256>("eth_maxPriorityFeePerGas", ()).await {
+                    Ok(tip) => tip,
+                    // The node's gas price is the base fee plus its suggested tip
+                    Err(_) => base_fee
+                        .map(|base_fee| gas_price.saturating_sub(base_fee))
+                        .filter(|tip| !tip.is_zero())
+                        .unwrap_or(U256::from(DEFAULT_PRIORITY_FEE)),
+                };
+                Ok(multiplied_fees(gas_price, base_fee, tip, multiplier, eip1559))
+            }
+            GasStrategy::Oracle => {
+                let history = client
+                    .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[ORACLE_REWARD_PERCENTILE])
+                    .await?;
+                // The last entry is the base fee of the block after the newest one
+                let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+                if base_fee.is_zero() {
+                    // No base fee means a legacy chain, where the node's gas price is the best guess
+                    return Ok(FeeMode::Legacy { gas_price: client.get_gas_price().await? });
+                }
+                let tip = match client.provider().request::<_, U256>("eth_maxPriorityFeePerGas", ()).await {
+                    Ok(tip) => tip,
+                    Err(_) => median_reward(&history.reward),
+                };
+                if eip1559 {
+                    Ok(FeeMode::Eip1559 { max_fee: base_fee * 2 + tip, max_priority_fee: tip })
+                } else {
+                    Ok(FeeMode::Legacy { gas_price: base_fee + tip })
+                }
+            }
+            GasStrategy::Percentile(percentile) => match suggest_1559_fees(client, percentile).await? {
+                // A legacy envelope pays the whole max fee as its gas price
+                fees if !eip1559 => Ok(FeeMode::Legacy { gas_price: fees.max_fee_per_gas() }),
+                fees => Ok(fees),
+            },
+            GasStrategy::External(ref oracle) => {
+                let gas_price = match oracle.fetch().await {
+                    Ok(gas_price) => gas_price,
+                    Err(e) => {
+                        warn!("Gas oracle {} failed, falling back to the node's gas price: {:#}", oracle, e);
+                        client.get_gas_price().await?
+                    }
+                };
+                Ok(FeeMode::from_gas_price(gas_price, eip1559))
+            }
+        }
+    }
+}
+
+/// EIP-1559 fees from the last blocks' fee history: the tip is the median of the given reward
+/// percentile across those blocks, and the max fee leaves room for the base fee to double.
+/// Fails on chains without a base fee.
+pub async fn suggest_1559_fees<M: Middleware>(client: &M, reward_percentile: f64) -> Result<FeeMode>
+where
+    M::Error: 'static,
+{
+    if !(0.0..=100.0).contains(&reward_percentile) {
+        bail!("reward percentile must be between 0 and 100, got {}", reward_percentile);
+    }
+    let history = client
+        .fee_history(FEE_HISTORY_BLOCKS, BlockNumber::Latest, &[reward_percentile])
+        .await?;
+    // The last entry is the base fee of the block after the newest one
+    let base_fee = history.base_fee_per_gas.last().copied().unwrap_or_default();
+    if base_fee.is_zero() {
+        bail!("the chain reports no base fee, so EIP-1559 fees can't be suggested");
+    }
+    let tip = median_reward(&history.reward);
+    Ok(FeeMode::Eip1559 { max_fee: base_fee * 2 + tip, max_priority_fee: tip })
+}
+
+/// Median of the first requested reward percentile across blocks, or the default tip without history
+fn median_reward(rewards: &[Vec<U256>]) -> U256 {
+    let mut rewards: Vec<U256> = rewards.iter().filter_map(|r| r.first().copied()).collect();
+    rewards.sort_unstable();
+    rewards.get(rewards.len() / 2).copied().unwrap_or(U256::from(DEFAULT_PRIORITY_FEE))
+}
+
+impl FromStr for GasStrategy {
+    type Err = String;
+
+    /// Parses `oracle`, `fixed:<gwei>`, `multiple:<factor>` or `percentile:<p>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "oracle" => Ok(GasStrategy::Oracle),
+            Some(("percentile", percentile)) => parse_percentile(percentile).map(GasStrategy::Percentile),
+            Some(("fixed", gwei)) => parse_units(gwei, "gwei")
+                .map(|wei| GasStrategy::Fixed(wei.into()))
+                .map_err(|e| format!("invalid gwei amount {:?}: {}", gwei, e)),
+            Some(("multiple", factor)) => match factor.parse::<f64>() {
+                Ok(factor) if factor > 0.0 => Ok(GasStrategy::MultipleOfBase(factor)),
+                Ok(factor) => Err(format!("multiplier must be positive, got {}", factor)),
+                Err(e) => Err(format!("invalid multiplier {:?}: {}", factor, e)),
+            },
+            _ => Err(format!(
+                "expected oracle, fixed:<gwei>, multiple:<factor> or percentile:<p>, got {:?}",
+                s
+            )),
+        }
+    }
+}
+
+/// Parses a reward percentile between 0 and 100
+pub fn parse_percentile(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(p) if (0.0..=100.0).contains(&p) => Ok(p),
+        Ok(p) => Err(format!("percentile must be between 0 and 100, got {}", p)),
+        Err(e) => Err(format!("invalid percentile {:?}: {}", s, e)),
+    }
+}
+
+impl fmt::Display for GasStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasStrategy::Fixed(gas_price) => write!(f, "fixed {} gwei", gwei(*gas_price)),
+            GasStrategy::MultipleOfBase(multiplier) => write!(f, "{}x node gas price", multiplier),
+            GasStrategy::Oracle => write!(f, "fee history oracle"),
+            GasStrategy::Percentile(percentile) => write!(f, "fee history p{} tip", percentile),
+            GasStrategy::External(oracle) => write!(f, "gas oracle {}", oracle),
+        }
+    }
+}
+
+/// Lowest per-gas price the node currently accepts: the latest base fee on EIP-1559 chains,
+/// the node's gas price otherwise
+pub async fn minimum_gas_price<M: Middleware>(client: &M, eip1559: bool) -> Result<U256>
+where
+    M::Error: 'static,
+{
+    if eip1559 {
+        let latest_block = client.get_block(BlockNumber::Latest).await?;
+        if let Some(base_fee) = latest_block.and_then(|b| b.base_fee_per_gas) {
+            return Ok(base_fee);
+        }
+    }
+    Ok(client.get_gas_price().await?)
+}
+
+/// Fees for `--gas-multiplier`. A legacy envelope scales the whole gas price. An EIP-1559 envelope
+/// only scales the tip: the base fee is set by the market and outbidding happens through the tip,
+/// so the transaction pays `base_fee + tip * multiplier`, with the usual room for the base fee to
+/// double in the max fee. Without a base fee the chain is legacy and the gas price is scaled instead.
+pub fn multiplied_fees(gas_price: U256, base_fee: Option<U256>, tip: U256, multiplier: f64, eip1559: bool) -> FeeMode {
+    match base_fee {
+        Some(base_fee) if eip1559 => {
+            let max_priority_fee = apply_gas_multiplier(tip, multiplier);
+            FeeMode::Eip1559 { max_fee: base_fee * 2 + max_priority_fee, max_priority_fee }
+        }
+        _ => FeeMode::from_gas_price(apply_gas_multiplier(gas_price, multiplier), eip1559),
+    }
+}
+
+/// Whether `fee` is more than `factor` times above or below the node's `node_price`. A node that
+/// reports a zero price, as some dev chains do, leaves nothing to compare against.
+pub fn outside_sanity_band(fee: U256, node_price: U256, factor: f64) -> bool {
+    !node_price.is_zero()
+        && (fee > apply_gas_multiplier(node_price, factor) || apply_gas_multiplier(fee, factor) < node_price)
+}
+
+/// Scales a gas price by a floating point multiplier with 0.001 precision. A product too large
+/// for a `U256`, e.g. from a long run of compounding escalation, saturates at `U256::MAX` rather
+/// than wrapping or silently capping the multiplier, so clamping it still yields the cap.
+pub fn apply_gas_multiplier(gas_price: U256, multiplier: f64) -> U256 {
+    if gas_price.is_zero() {
+        return gas_price;
+    }
+    let scaled = (multiplier * 1000.0).round();
+    if scaled >= u64::MAX as f64 {
+        return U256::MAX;
+    }
+    gas_price.saturating_mul(U256::from(scaled as u64)) / 1000
+}
+
+/// Raises `fee` by `basis_points` hundredths of a percent `steps` times, rounding each step up and by
+/// at least 1 wei, and stops once it reaches `cap`. Zero stays zero.
+fn compound(mut fee: U256, basis_points: u64, steps: u64, cap: U256) -> U256 {
+    if fee.is_zero() {
+        return fee;
+    }
+    for _ in 0..steps {
+        if fee >= cap {
+            break;
+        }
+        // A product too large for a `U256` saturates rather than wrapping
+        let raised = match fee.checked_mul(U256::from(10_000 + basis_points)) {
+            Some(raised) => (raised - 1) / 10_000 + 1,
+            None => U256::MAX,
+        };
+        fee = raised.max(fee.saturating_add(U256::one()));
+    }
+    fee
+}
+
+/// Formats a wei amount in gwei
+pub fn gwei(wei: U256) -> String {
+    format_units(wei, "gwei").unwrap_or_else(|_| wei.to_string())
+}
+
+/// Formats a wei amount in ETH
+pub fn ether(wei: U256) -> String {
+    format_units(wei, "ether").unwrap_or_else(|_| wei.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn multiplier_scales_the_whole_legacy_price_but_only_the_1559_tip() {
+        let base_fee = U256::from(30 * GWEI);
+        let tip = U256::from(2 * GWEI);
+        let gas_price = base_fee + tip;
+
+        let legacy = multiplied_fees(gas_price, Some(base_fee), tip, 3.0, false);
+        assert_eq!(legacy, FeeMode::Legacy { gas_price: U256::from(96 * GWEI) });
+
+        let eip1559 = multiplied_fees(gas_price, Some(base_fee), tip, 3.0, true);
+        assert_eq!(
+            eip1559,
+            FeeMode::Eip1559 { max_fee: U256::from(66 * GWEI), max_priority_fee: U256::from(6 * GWEI) }
+        );
+        // What the 1559 transaction is charged at the current base fee: well below the legacy price
+        let FeeMode::Eip1559 { max_priority_fee, .. } = eip1559 else { unreachable!() };
+        assert_eq!(base_fee + max_priority_fee, U256::from(36 * GWEI));
+    }
+
+    #[test]
+    fn sanity_band_spans_the_factor_either_side_of_the_node_price() {
+        let node_price = U256::from(10 * GWEI);
+        assert!(!outside_sanity_band(U256::from(100 * GWEI), node_price, 10.0));
+        assert!(!outside_sanity_band(U256::from(GWEI), node_price, 10.0));
+        assert!(outside_sanity_band(U256::from(101 * GWEI), node_price, 10.0));
+        assert!(outside_sanity_band(U256::from(GWEI - 1), node_price, 10.0));
+        // A gwei/wei mix-up
+        assert!(outside_sanity_band(U256::from(10), node_price, 10.0));
+        assert!(!outside_sanity_band(U256::from(10), U256::zero(), 10.0));
+    }
+
+    #[test]
+    fn runaway_escalation_saturates_and_clamps_to_the_cap() {
+        let cap = U256::from(500 * GWEI);
+        let escalated = FeeMode::Legacy { gas_price: U256::from(GWEI) }.escalate(10.0, 10_000, None);
+        assert_eq!(escalated, FeeMode::Legacy { gas_price: U256::MAX });
+        let capped = FeeMode::Legacy { gas_price: U256::from(GWEI) }.escalate(10.0, 10_000, Some(cap));
+        assert_eq!(capped, FeeMode::Legacy { gas_price: cap });
+    }
+
+    #[test]
+    fn escalation_raises_every_index_even_by_a_tiny_percentage() {
+        let cap = U256::from(2 * GWEI);
+        for pct in [0.001, 0.01, 0.04, 1.0] {
+            for start in [U256::one(), U256::from(GWEI)] {
+                let fees: Vec<U256> = (0..50)
+                    .map(|index| FeeMode::Legacy { gas_price: start }.escalate(pct, index, Some(cap)).max_fee_per_gas())
+                    .collect();
+                assert!(fees.windows(2).all(|pair| pair[0] < pair[1]), "{} {} {:?}", pct, start, fees);
+            }
+        }
+        // Rounded up per step, so a single step of 0.01% on 1 gwei adds exactly 100 kwei
+        let stepped = FeeMode::Legacy { gas_price: U256::from(GWEI) }.escalate(0.01, 1, None);
+        assert_eq!(stepped.max_fee_per_gas(), U256::from(GWEI + 100_000));
+        // Zero stays zero rather than being raised a wei at a time
+        let tipless = FeeMode::Eip1559 { max_fee: U256::from(GWEI), max_priority_fee: U256::zero() };
+        let FeeMode::Eip1559 { max_priority_fee, .. } = tipless.escalate(1.0, 10, Some(cap)) else { unreachable!() };
+        assert!(max_priority_fee.is_zero());
+    }
+
+    #[test]
+    fn multiplier_falls_back_to_the_gas_price_without_a_base_fee() {
+        let gas_price = U256::from(10 * GWEI);
+        let fees = multiplied_fees(gas_price, None, U256::from(GWEI), 2.0, true);
+        assert_eq!(fees, FeeMode::from_gas_price(U256::from(20 * GWEI), true));
+    }
+}
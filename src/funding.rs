@@ -0,0 +1,91 @@
+use anyhow::{bail, Result};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, BlockNumber, TransactionRequest, U256},
+};
+use serde::Serialize;
+use std::{collections::HashMap, time::Duration};
+use tracing::{info, instrument};
+
+use crate::{
+    confirm::{wait_for_receipts, ConfirmProgress},
+    fees::ether,
+    shutdown::Shutdown,
+    transport::FailoverTransport,
+};
+
+/// Master wallet that tops up the benchmark wallets before a run
+#[derive(Debug, Clone)]
+pub struct FundingConfig {
+    pub funder: LocalWallet,
+    /// Balance in wei every benchmark wallet should have; wallets already holding it are skipped
+    pub amount: U256,
+}
+
+/// What the funding phase distributed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FundingReport {
+    /// Wallets that received funds
+    pub wallets_funded: usize,
+    /// Wei sent across all funding transactions
+    pub total_distributed: U256,
+}
+
+/// Sends `amount` from the funder to every wallet whose balance is below it and waits until all
+/// funding transactions are mined
+#[instrument(name = "fund", skip_all)]
+pub async fn fund_wallets(
+    provider: &Provider<FailoverTransport>,
+    chain_id: u64,
+    funding: &FundingConfig,
+    wallets: &[Address],
+    poll_interval: Duration,
+    timeout: Duration,
+) -> Result<FundingReport> {
+    let funder = SignerMiddleware::new(provider.clone(), funding.funder.clone().with_chain_id(chain_id));
+    let mut nonce = funder
+        .get_transaction_count(funder.address(), Some(BlockNumber::Pending.into()))
+        .await?;
+
+    let mut report = FundingReport::default();
+    let mut hashes = Vec::new();
+    for &wallet in wallets {
+        let balance = provider.get_balance(wallet, None).await?;
+        if balance >= funding.amount {
+            info!("Wallet {} already holds {} wei, skipping", wallet, balance);
+            continue;
+        }
+        let tx = TransactionRequest::pay(wallet, funding.amount).from(funder.address()).nonce(nonce);
+        let pending = funder.send_transaction(tx, None).await?;
+        info!("Funding {} with {} wei: {:?}", wallet, funding.amount, pending.tx_hash());
+        hashes.push(pending.tx_hash());
+        nonce += U256::one();
+        report.wallets_funded += 1;
+        report.total_distributed += funding.amount;
+    }
+    if hashes.is_empty() {
+        return Ok(report);
+    }
+
+    let start_block = provider.get_block_number().await?.as_u64();
+    let progress = ConfirmProgress::default();
+    let confirmation = wait_for_receipts(provider, &hashes, &HashMap::new(), start_block, poll_interval, timeout, &progress, &Shutdown::default())
+        .await?;
+    if confirmation.mined != hashes.len() {
+        bail!(
+            "funding did not complete: {} of {} transactions mined, {} reverted, {} unconfirmed",
+            confirmation.mined,
+            hashes.len(),
+            confirmation.reverted,
+            confirmation.unconfirmed
+        );
+    }
+    info!(
+        "Funded {} wallets with {} ETH in total",
+        report.wallets_funded,
+        ether(report.total_distributed)
+    );
+    Ok(report)
+}
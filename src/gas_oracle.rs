@@ -0,0 +1,95 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ethers::{types::U256, utils::parse_units};
+use serde_json::Value;
+use std::{fmt, time::Duration};
+
+/// How long the oracle may take to answer before the node's gas price is used instead
+const ORACLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// External gas price estimator answering an HTTP GET with JSON that holds a price in gwei, so a
+/// benchmark can pay what a production system using the same estimator would
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasOracle {
+    pub url: String,
+    /// Dot-separated path to the gwei value in the response, e.g. `result.ProposeGasPrice`;
+    /// numeric segments index into arrays
+    pub field: String,
+}
+
+impl GasOracle {
+    /// Fetches the recommended gas price in wei
+    pub async fn fetch(&self) -> Result<U256> {
+        let client = reqwest::Client::builder().timeout(ORACLE_TIMEOUT).build()?;
+        let body: Value = client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            // The URL may carry an API key, so it stays out of the error
+            .map_err(|e| anyhow!("request failed: {}", root_cause(&e.without_url())))?
+            .json()
+            .await
+            .context("response is not JSON")?;
+        self.extract(&body)
+    }
+
+    /// Gas price in wei at `field` of an oracle response; the value may be a number or a string
+    pub fn extract(&self, body: &Value) -> Result<U256> {
+        let value = self
+            .field
+            .split('.')
+            .try_fold(body, |value, segment| {
+                segment.parse::<usize>().ok().and_then(|index| value.get(index)).or_else(|| value.get(segment))
+            })
+            .ok_or_else(|| anyhow!("response has no field {}", self.field))?;
+        let gwei = match value {
+            Value::String(gwei) => gwei.clone(),
+            Value::Number(gwei) => gwei.to_string(),
+            other => bail!("{} is {}, not a gwei amount", self.field, other),
+        };
+        let wei: U256 = parse_units(&gwei, "gwei")
+            .map_err(|e| anyhow!("{} is {:?}, not a gwei amount: {}", self.field, gwei, e))?
+            .into();
+        if wei.is_zero() {
+            bail!("{} is zero", self.field);
+        }
+        Ok(wei)
+    }
+}
+
+/// Innermost error behind a request failure, e.g. the refused connection behind "error sending request"
+pub(crate) fn root_cause(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut cause = err;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    cause.to_string()
+}
+
+impl fmt::Display for GasOracle {
+    /// Leaves out the query string, which often carries an API key
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let url = self.url.split('?').next().unwrap_or_default();
+        write!(f, "{} ({})", url, self.field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn extracts_the_gwei_value_at_the_field_path() {
+        let oracle = |field: &str| GasOracle { url: "http://oracle".to_string(), field: field.to_string() };
+        let body = json!({
+            "result": { "ProposeGasPrice": "12.5" },
+            "blockPrices": [{ "estimatedPrices": [{ "price": 30 }] }],
+        });
+
+        assert_eq!(oracle("result.ProposeGasPrice").extract(&body).unwrap(), U256::from(12_500_000_000u64));
+        assert_eq!(oracle("blockPrices.0.estimatedPrices.0.price").extract(&body).unwrap(), U256::from(30_000_000_000u64));
+        assert!(oracle("result.FastGasPrice").extract(&body).is_err());
+        assert!(oracle("result").extract(&body).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+use ethers::types::transaction::eip2718::TypedTransaction;
+use std::fmt;
+
+/// Customises each transaction of a run without forking the builder. Called once per transaction
+/// after [`Benchmarker::create_transaction`](crate::Benchmarker::create_transaction) has built it
+/// and before the access list and gas limit are resolved and it is signed, so changes to the data
+/// or recipient are estimated like any other. The balance check only knows the configured value,
+/// so a hook that raises values should leave room for them.
+pub trait TxHook: fmt::Debug + Send + Sync {
+    /// Changes the `index`-th transaction of the run in place; does nothing unless overridden
+    fn modify(&self, tx: &mut TypedTransaction, index: u64) {
+        let _ = (tx, index);
+    }
+}
+
+/// Leaves every transaction as built, the benchmarker's default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopHook;
+
+impl TxHook for NoopHook {}
@@ -0,0 +1,65 @@
+//! Core of the `dump` transaction spammer, exposed as a library so the benchmark can be
+//! embedded in other harnesses and its results inspected programmatically.
+
+pub mod adaptive;
+pub mod baseline;
+pub mod benchmarker;
+pub mod bundle;
+pub mod calldata;
+pub mod cancel;
+pub mod compare;
+pub mod config;
+pub mod config_file;
+pub mod confirm;
+pub mod deploy;
+pub mod doctor;
+pub mod failures;
+pub mod fees;
+pub mod funding;
+pub mod gas_oracle;
+pub mod hook;
+pub mod mempool;
+pub mod metrics;
+pub mod nonce_manager;
+pub mod nonce_state;
+pub mod probe;
+pub mod ramp;
+pub mod rate;
+pub mod raw_txs;
+pub mod report;
+pub mod retry;
+pub mod rounds;
+pub mod send_error;
+pub mod shutdown;
+pub mod stats;
+pub mod transport;
+pub mod wallet_lock;
+
+pub use adaptive::ConcurrencyReport;
+pub use benchmarker::{Benchmarker, Client};
+pub use bundle::{BundleRelay, BundleReport};
+pub use cancel::{CancelOutcome, CancelReport};
+pub use compare::ComparisonReport;
+pub use config::{Config, NonceSource, TxType, WalletSelection, Workload};
+pub use confirm::ConfirmationReport;
+pub use deploy::Deployment;
+pub use doctor::DoctorReport;
+pub use failures::FailureKind;
+pub use fees::{FeeMode, GasStrategy};
+pub use funding::{FundingConfig, FundingReport};
+pub use gas_oracle::GasOracle;
+pub use hook::{NoopHook, TxHook};
+pub use metrics::Metrics;
+pub use nonce_manager::NonceManager;
+pub use nonce_state::NonceState;
+pub use probe::{ProbeProfile, ProbeReport};
+pub use ramp::{RampProfile, RampReport};
+pub use rate::{Arrival, TokenBucket};
+pub use raw_txs::RawTxFile;
+pub use report::{BlockRange, GasPriceRange, NonceGap, RunReport, TxRecord};
+pub use rounds::RoundsReport;
+pub use send_error::SendError;
+pub use shutdown::Shutdown;
+pub use stats::{LatencyStats, TpsSample};
+pub use transport::Transport;
+pub use wallet_lock::WalletLock;
@@ -0,0 +1,56 @@
+use ethers::providers::Middleware;
+use serde::Serialize;
+use std::fmt;
+use tracing::{info, warn};
+
+/// Node-wide mempool size as reported by `txpool_status`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MempoolCounts {
+    /// Transactions ready to be mined
+    pub pending: u64,
+    /// Transactions waiting on a nonce gap or fee condition
+    pub queued: u64,
+}
+
+impl fmt::Display for MempoolCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pending, {} queued", self.pending, self.queued)
+    }
+}
+
+/// The node's mempool just before the workload started and right after sending stopped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct MempoolReport {
+    pub before: MempoolCounts,
+    /// Unset when the node stopped answering `txpool_status` during the run
+    pub after: Option<MempoolCounts>,
+}
+
+impl fmt::Display for MempoolReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.after {
+            Some(after) => write!(
+                f,
+                "pending {} -> {}, queued {} -> {}",
+                self.before.pending, after.pending, self.before.queued, after.queued
+            ),
+            None => write!(f, "{} before the run, unknown after", self.before),
+        }
+    }
+}
+
+/// Reads the node's mempool size. The `txpool` namespace is often disabled or missing, so a
+/// failure is only warned about and yields `None`.
+pub async fn txpool_status<M: Middleware>(client: &M, when: &str) -> Option<MempoolCounts> {
+    match client.txpool_status().await {
+        Ok(status) => {
+            let counts = MempoolCounts { pending: status.pending.as_u64(), queued: status.queued.as_u64() };
+            info!("Mempool {}: {}", when, counts);
+            Some(counts)
+        }
+        Err(e) => {
+            warn!("Could not inspect the mempool {}: txpool_status failed: {}", when, e);
+            None
+        }
+    }
+}
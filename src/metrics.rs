@@ -0,0 +1,190 @@
+use anyhow::{Context, Result};
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Response, Server,
+};
+use std::{
+    convert::Infallible,
+    fmt::Write as _,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::time::MissedTickBehavior;
+use tracing::info;
+
+/// Upper bounds in seconds of the `send_latency_seconds` histogram buckets
+const LATENCY_BUCKETS: [f64; 12] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Live counters of a run, updated as sends complete and rendered in the Prometheus text format
+#[derive(Debug)]
+pub struct Metrics {
+    sent: AtomicU64,
+    failed: AtomicU64,
+    /// Observations per latency bucket, not cumulative; the last slot is `+Inf`
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_sum_micros: AtomicU64,
+    second: Mutex<SecondCounter>,
+    started: Instant,
+}
+
+/// Accepted sends per wall-clock second, for the `current_tps` gauge
+#[derive(Debug, Default)]
+struct SecondCounter {
+    second: u64,
+    count: u64,
+    /// Count of the last full second
+    previous: u64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            sent: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+            latency_buckets: Default::default(),
+            latency_sum_micros: AtomicU64::new(0),
+            second: Mutex::new(SecondCounter::default()),
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Records a send the node accepted after `latency`
+    pub fn record_sent(&self, latency: Duration) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| latency.as_secs_f64() <= bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+
+        let now = self.started.elapsed().as_secs();
+        let mut second = self.second.lock().expect("metrics lock poisoned");
+        second.roll(now);
+        second.count += 1;
+    }
+
+    /// Records a send that failed
+    pub fn record_failed(&self) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sends accepted and failed so far
+    pub fn totals(&self) -> (u64, u64) {
+        (self.sent.load(Ordering::Relaxed), self.failed.load(Ordering::Relaxed))
+    }
+
+    /// Sends accepted during the last full second
+    pub fn current_tps(&self) -> u64 {
+        let now = self.started.elapsed().as_secs();
+        let mut second = self.second.lock().expect("metrics lock poisoned");
+        second.roll(now);
+        second.previous
+    }
+
+    /// Renders every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let sent = self.sent.load(Ordering::Relaxed);
+        let _ = writeln!(out, "# HELP txs_sent_total Transactions accepted by the RPC");
+        let _ = writeln!(out, "# TYPE txs_sent_total counter");
+        let _ = writeln!(out, "txs_sent_total {}", sent);
+        let _ = writeln!(out, "# HELP txs_failed_total Transactions that failed to send");
+        let _ = writeln!(out, "# TYPE txs_failed_total counter");
+        let _ = writeln!(out, "txs_failed_total {}", self.failed.load(Ordering::Relaxed));
+
+        let _ = writeln!(out, "# HELP send_latency_seconds Latency of accepted eth_sendRawTransaction calls");
+        let _ = writeln!(out, "# TYPE send_latency_seconds histogram");
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "send_latency_seconds_bucket{{le=\"{}\"}} {}", bound, cumulative);
+        }
+        cumulative += self.latency_buckets[LATENCY_BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "send_latency_seconds_bucket{{le=\"+Inf\"}} {}", cumulative);
+        let sum = self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "send_latency_seconds_sum {}", sum);
+        let _ = writeln!(out, "send_latency_seconds_count {}", cumulative);
+
+        let _ = writeln!(out, "# HELP current_tps Transactions accepted during the last full second");
+        let _ = writeln!(out, "# TYPE current_tps gauge");
+        let _ = writeln!(out, "current_tps {}", self.current_tps());
+        out
+    }
+}
+
+impl SecondCounter {
+    /// Moves the window forward to wall-clock second `now`
+    fn roll(&mut self, now: u64) {
+        if now == self.second {
+            return;
+        }
+        // A gap of more than a second means the last full second saw nothing
+        self.previous = if now == self.second + 1 { self.count } else { 0 };
+        self.second = now;
+        self.count = 0;
+    }
+}
+
+/// Logs the send rate and error rate over the last `interval` every `interval` until aborted. The
+/// window slides with each line, so a degrading endpoint shows up while the run is still going.
+pub async fn report_live(metrics: Arc<Metrics>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes immediately and marks the start of the first window
+    ticker.tick().await;
+    let mut window_start = (Instant::now(), metrics.totals());
+    loop {
+        ticker.tick().await;
+        let now = Instant::now();
+        let (sent, failed) = metrics.totals();
+        let (started, (sent_before, failed_before)) = window_start;
+        let window_sent = sent - sent_before;
+        let window_failed = failed - failed_before;
+        let attempted = window_sent + window_failed;
+        let error_rate = if attempted > 0 { window_failed as f64 / attempted as f64 } else { 0.0 };
+        info!(
+            "Last {:.1}s: {:.2} tx/s, {:.1}% errors ({} sent, {} failed; {} sent in total)",
+            (now - started).as_secs_f64(),
+            window_sent as f64 / (now - started).as_secs_f64(),
+            error_rate * 100.0,
+            window_sent,
+            window_failed,
+            sent
+        );
+        window_start = (now, (sent, failed));
+    }
+}
+
+/// Serves `metrics` at `/metrics` (and every other path) on `port` until the process exits
+pub async fn serve(metrics: Arc<Metrics>, port: u16) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let make_service = make_service_fn(move |_| {
+        let metrics = metrics.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |_request| {
+                let body = metrics.render();
+                async move {
+                    Ok::<_, Infallible>(
+                        Response::builder()
+                            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                            .body(Body::from(body))
+                            .expect("static response parts are valid"),
+                    )
+                }
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)
+        .with_context(|| format!("failed to bind the metrics server to port {}", port))?
+        .serve(make_service);
+    info!("Serving Prometheus metrics on http://{}/metrics", addr);
+    server.await.context("metrics server failed")
+}
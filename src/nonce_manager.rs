@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber},
+};
+use std::collections::BTreeMap;
+use tokio::sync::Mutex;
+
+/// Hands out nonces per wallet: each sequence starts from the chain's count, read once, and counts
+/// up from there, so concurrent senders never pick the same nonce
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    next: BTreeMap<Address, Mutex<u64>>,
+}
+
+impl NonceManager {
+    /// Starts each wallet's sequence at the given nonce
+    pub fn new(starting: impl IntoIterator<Item = (Address, u64)>) -> Self {
+        Self { next: starting.into_iter().map(|(address, nonce)| (address, Mutex::new(nonce))).collect() }
+    }
+
+    /// Starts each wallet's sequence at its transaction count as of `block`, usually pending
+    pub async fn fetch<M: Middleware>(client: &M, addresses: &[Address], block: BlockNumber) -> Result<Self>
+    where
+        M::Error: 'static,
+    {
+        let mut starting = Vec::with_capacity(addresses.len());
+        for &address in addresses {
+            let nonce = client
+                .get_transaction_count(address, Some(block.into()))
+                .await
+                .with_context(|| format!("failed to read the nonce of {:?}", address))?;
+            starting.push((address, nonce.as_u64()));
+        }
+        Ok(Self::new(starting))
+    }
+
+    /// Hands out the next nonce of `address`.
+    ///
+    /// Panics if the wallet isn't managed.
+    pub async fn next(&self, address: Address) -> u64 {
+        let mut next = self.counter(address).lock().await;
+        let nonce = *next;
+        *next += 1;
+        nonce
+    }
+
+    /// Next nonce of `address` without handing it out
+    pub async fn peek(&self, address: Address) -> u64 {
+        *self.counter(address).lock().await
+    }
+
+    /// Moves the sequence of `address` up to `nonce` if it is behind, e.g. for nonces a previous run
+    /// handed out that the node doesn't know about yet. Unmanaged wallets are ignored.
+    pub async fn raise(&self, address: Address, nonce: u64) {
+        if let Some(next) = self.next.get(&address) {
+            let mut next = next.lock().await;
+            *next = (*next).max(nonce);
+        }
+    }
+
+    /// Refetches the pending nonce of `address` after a conflict such as "nonce too low" and hands
+    /// out the higher of it and the sequence, so a nonce is never handed out twice
+    pub async fn reset<M: Middleware>(&self, client: &M, address: Address) -> Result<u64>
+    where
+        M::Error: 'static,
+    {
+        let mut next = self.counter(address).lock().await;
+        let pending = client
+            .get_transaction_count(address, Some(BlockNumber::Pending.into()))
+            .await
+            .with_context(|| format!("failed to refetch the nonce of {:?}", address))?
+            .as_u64();
+        let nonce = pending.max(*next);
+        *next = nonce + 1;
+        Ok(nonce)
+    }
+
+    /// Next nonce of every managed wallet
+    pub async fn snapshot(&self) -> BTreeMap<Address, u64> {
+        let mut nonces = BTreeMap::new();
+        for (address, next) in &self.next {
+            nonces.insert(*address, *next.lock().await);
+        }
+        nonces
+    }
+
+    fn counter(&self, address: Address) -> &Mutex<u64> {
+        self.next
+            .get(&address)
+            .unwrap_or_else(|| panic!("wallet {:?} has no nonce sequence", address))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{providers::Provider, types::U256};
+
+    #[tokio::test]
+    async fn sequences_count_up_per_wallet() {
+        let (a, b) = (Address::repeat_byte(1), Address::repeat_byte(2));
+        let nonces = NonceManager::new([(a, 5), (b, 0)]);
+        assert_eq!(nonces.next(a).await, 5);
+        assert_eq!(nonces.next(a).await, 6);
+        assert_eq!(nonces.next(b).await, 0);
+
+        nonces.raise(a, 3).await;
+        assert_eq!(nonces.peek(a).await, 7);
+        nonces.raise(b, 10).await;
+        assert_eq!(nonces.next(b).await, 10);
+        assert_eq!(nonces.snapshot().await, BTreeMap::from([(a, 7), (b, 11)]));
+    }
+
+    #[tokio::test]
+    async fn reset_hands_out_the_higher_of_pending_and_the_sequence() {
+        let address = Address::repeat_byte(1);
+        let nonces = NonceManager::new([(address, 5)]);
+        let (provider, mock) = Provider::mocked();
+
+        // The node is ahead, e.g. after sends from elsewhere
+        mock.push(U256::from(9)).unwrap();
+        assert_eq!(nonces.reset(&provider, address).await.unwrap(), 9);
+        assert_eq!(nonces.next(address).await, 10);
+
+        // The node lags behind nonces already handed out, which are never reused
+        mock.push(U256::from(3)).unwrap();
+        assert_eq!(nonces.reset(&provider, address).await.unwrap(), 11);
+        assert_eq!(nonces.peek(address).await, 12);
+    }
+}
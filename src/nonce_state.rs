@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use ethers::types::Address;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+/// Age after which a state file is considered stale: by then the transactions it accounts for
+/// have been mined or dropped, and the node's own count is the better source
+const MAX_STATE_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Next unused nonce of every wallet as of the end of a run, persisted so a follow-up run
+/// started before those transactions are mined doesn't reuse them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NonceState {
+    /// Chain the nonces belong to; state from another chain is ignored
+    pub chain_id: u64,
+    /// Unix time the state was written at
+    pub saved_at: u64,
+    pub next_nonces: BTreeMap<Address, u64>,
+}
+
+impl NonceState {
+    /// Creates a state for `chain_id` stamped with the current time
+    pub fn new(chain_id: u64, next_nonces: BTreeMap<Address, u64>) -> Self {
+        Self {
+            chain_id,
+            saved_at: unix_now(),
+            next_nonces,
+        }
+    }
+
+    /// Reads the state file for `chain_id`. A missing file yields `None`; an unreadable, corrupt,
+    /// stale or other-chain file is ignored with a warning.
+    pub fn load(path: &Path, chain_id: u64) -> Option<Self> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(e) => {
+                warn!("Ignoring nonce state file {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        match serde_json::from_str::<NonceState>(&contents) {
+            Ok(state) if state.chain_id != chain_id => {
+                warn!(
+                    "Ignoring nonce state file {}: it is for chain {}, not {}",
+                    path.display(), state.chain_id, chain_id
+                );
+                None
+            }
+            Ok(state) if unix_now().saturating_sub(state.saved_at) > MAX_STATE_AGE.as_secs() => {
+                warn!(
+                    "Ignoring stale nonce state file {}: written more than {:?} ago",
+                    path.display(), MAX_STATE_AGE
+                );
+                None
+            }
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!("Ignoring corrupt nonce state file {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Writes the state, replacing any previous file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").with_context(|| format!("failed to write nonce state file {}", path.display()))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
@@ -0,0 +1,122 @@
+use serde::Serialize;
+use std::{fmt, time::Duration};
+
+use crate::stats::LatencyStats;
+
+/// Share of the target rate a window must actually achieve to count as sustained. Leaves room for
+/// the pacer's start-up and the abandoned tail of a short window.
+const MIN_ACHIEVED_SHARE: f64 = 0.8;
+
+/// Accepted sends per window below which a verdict is noted as shaky
+const MIN_CONFIDENT_SAMPLES: usize = 100;
+
+/// Binary search for the highest paced rate an endpoint sustains
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeProfile {
+    /// Lowest rate considered, in tx/s
+    pub min: f64,
+    /// Highest rate considered, in tx/s
+    pub max: f64,
+    /// Length of each window
+    pub window: Duration,
+    /// Fraction of failed sends (0.0-1.0) above which a rate is unsustainable
+    pub max_error_rate: f64,
+    /// p95 send latency above which a rate is unsustainable
+    pub max_p95: Duration,
+    /// Search stops once the bounds are within this fraction (0.0-1.0) of each other
+    pub resolution: f64,
+    /// Most windows run, however wide the bounds still are
+    pub max_windows: usize,
+}
+
+impl ProbeProfile {
+    /// Whether the search has narrowed enough between a sustained `low` and an unsustained `high`
+    pub fn converged(&self, low: f64, high: f64) -> bool {
+        high - low <= high * self.resolution
+    }
+}
+
+/// Outcome of one probe window
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeWindow {
+    /// Rate the window was paced at, in tx/s
+    pub target_tps: f64,
+    /// Accepted sends per second actually achieved
+    pub achieved_tps: f64,
+    pub sent: usize,
+    pub failed: usize,
+    /// Fraction of attempted sends that failed
+    pub error_rate: f64,
+    /// Latency percentiles of the accepted sends
+    pub send_latency: LatencyStats,
+    /// Whether the window stayed within the error and latency bounds at close to the target rate
+    pub sustained: bool,
+}
+
+impl ProbeWindow {
+    /// Judges a window against `profile`
+    pub fn judge(&mut self, profile: &ProbeProfile) {
+        self.sustained = self.error_rate <= profile.max_error_rate
+            && self.send_latency.p95 <= profile.max_p95
+            && self.achieved_tps >= self.target_tps * MIN_ACHIEVED_SHARE;
+    }
+}
+
+/// Every window of a probe run and where the search ended
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProbeReport {
+    pub windows: Vec<ProbeWindow>,
+    /// Highest rate that was sustained
+    pub sustainable_tps: Option<f64>,
+    /// Lowest rate that was not, the upper bound of the answer
+    pub failed_tps: Option<f64>,
+    /// Whether the bounds narrowed to the configured resolution
+    pub converged: bool,
+}
+
+impl ProbeReport {
+    /// How far to trust the discovered rate
+    pub fn confidence(&self) -> String {
+        let mut notes = Vec::new();
+        match (self.sustainable_tps, self.failed_tps) {
+            (Some(low), Some(high)) => notes.push(format!("the limit lies between {:.2} and {:.2} tx/s", low, high)),
+            (Some(_), None) => notes.push("every rate tried was sustained; raise --max-rate to find the limit".to_string()),
+            (None, Some(high)) => notes.push(format!("no rate was sustained; the limit is below {:.2} tx/s", high)),
+            (None, None) => notes.push("no window completed".to_string()),
+        }
+        if !self.converged && self.sustainable_tps.is_some() && self.failed_tps.is_some() {
+            notes.push("the search ran out of windows before reaching the requested resolution".to_string());
+        }
+        if self.windows.iter().any(|window| window.sent < MIN_CONFIDENT_SAMPLES) {
+            notes.push(format!(
+                "some windows accepted fewer than {} sends; a longer --window gives a steadier verdict",
+                MIN_CONFIDENT_SAMPLES
+            ));
+        }
+        notes.join("; ")
+    }
+}
+
+impl fmt::Display for ProbeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== PROBE =====")?;
+        for window in &self.windows {
+            writeln!(
+                f,
+                "Target {:.2} tx/s: achieved {:.2} tx/s, {} sent, {} failed, {:.1}% errors, p95 {:?} -> {}",
+                window.target_tps,
+                window.achieved_tps,
+                window.sent,
+                window.failed,
+                window.error_rate * 100.0,
+                window.send_latency.p95,
+                if window.sustained { "sustained" } else { "not sustained" }
+            )?;
+        }
+        match self.sustainable_tps {
+            Some(tps) => writeln!(f, "Sustainable rate: {:.2} tx/s", tps)?,
+            None => writeln!(f, "Sustainable rate: none found")?,
+        }
+        write!(f, "Confidence: {}", self.confidence())
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Serialize;
+use std::{fmt, time::Duration};
+
+use crate::stats::LatencyStats;
+
+/// Successive sustained windows at increasing target send rates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RampProfile {
+    /// Target rate of the first window, in tx/s
+    pub start: f64,
+    /// Highest target rate to try, in tx/s
+    pub end: f64,
+    /// Rate increase between windows, in tx/s
+    pub step: f64,
+    /// Length of each window
+    pub window: Duration,
+    /// Fraction of failed sends (0.0-1.0) at which the ramp stops
+    pub fail_threshold: f64,
+}
+
+impl RampProfile {
+    /// Target rates of every window, from `start` up to and including `end`
+    pub fn rates(&self) -> Vec<f64> {
+        let steps = ((self.end - self.start) / self.step).floor() as usize;
+        (0..=steps).map(|i| self.start + self.step * i as f64).collect()
+    }
+}
+
+/// Outcome of one ramp window
+#[derive(Debug, Clone, Serialize)]
+pub struct RampWindow {
+    /// Rate the window was paced at, in tx/s
+    pub target_tps: f64,
+    /// Accepted sends per second actually achieved
+    pub achieved_tps: f64,
+    pub sent: usize,
+    pub failed: usize,
+    /// Fraction of attempted sends that were accepted
+    pub success_rate: f64,
+    /// Latency percentiles of the accepted sends
+    pub send_latency: LatencyStats,
+}
+
+impl RampWindow {
+    /// Fraction of attempted sends that failed
+    pub fn error_rate(&self) -> f64 {
+        1.0 - self.success_rate
+    }
+}
+
+/// Per-window results of a ramp run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RampReport {
+    pub windows: Vec<RampWindow>,
+    /// Highest target rate whose error rate stayed below the threshold
+    pub last_sustainable_tps: Option<f64>,
+}
+
+impl fmt::Display for RampReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== RAMP =====")?;
+        for window in &self.windows {
+            writeln!(
+                f,
+                "Target {:.2} tx/s: achieved {:.2} tx/s, {} sent, {} failed, {:.1}% success, p50 {:?}, p99 {:?}",
+                window.target_tps,
+                window.achieved_tps,
+                window.sent,
+                window.failed,
+                window.success_rate * 100.0,
+                window.send_latency.p50,
+                window.send_latency.p99
+            )?;
+        }
+        match self.last_sustainable_tps {
+            Some(tps) => write!(f, "Last sustainable rate: {:.2} tx/s", tps),
+            None => write!(f, "Last sustainable rate: none, the first window already exceeded the failure threshold"),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use clap::ValueEnum;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+use tokio::{sync::Mutex, time::sleep};
+
+/// How paced sends are spread out over time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Arrival {
+    /// Evenly spaced at the target rate
+    #[default]
+    Uniform,
+    /// Exponentially distributed gaps averaging the target rate, like independent users
+    Poisson,
+}
+
+/// Sends `size` transactions as fast as possible, then pauses for `gap`, until the batch is done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BurstPattern {
+    pub size: usize,
+    pub gap: Duration,
+}
+
+impl FromStr for BurstPattern {
+    type Err = String;
+
+    /// Parses `burst:size=<n>,gap=<duration>`, e.g. `burst:size=100,gap=500ms`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some(params) = s.strip_prefix("burst:") else {
+            return Err(format!("expected burst:size=<n>,gap=<duration>, got {:?}", s));
+        };
+        let (mut size, mut gap) = (None, None);
+        for param in params.split(',') {
+            match param.split_once('=') {
+                Some(("size", value)) => match value.parse::<usize>() {
+                    Ok(value) if value > 0 => size = Some(value),
+                    Ok(_) => return Err("burst size must be at least 1".to_string()),
+                    Err(e) => return Err(format!("invalid burst size {:?}: {}", value, e)),
+                },
+                Some(("gap", value)) => {
+                    gap = Some(humantime::parse_duration(value).map_err(|e| format!("invalid burst gap {:?}: {}", value, e))?)
+                }
+                _ => return Err(format!("unknown burst parameter {:?}, expected size=<n> or gap=<duration>", param)),
+            }
+        }
+        match (size, gap) {
+            (Some(size), Some(gap)) => Ok(Self { size, gap }),
+            _ => Err(format!("burst pattern needs both size and gap, got {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for BurstPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bursts of {} every {:?}", self.size, self.gap)
+    }
+}
+
+/// Token bucket that paces sends to a target rate
+#[derive(Debug)]
+pub struct TokenBucket {
+    /// Tokens added per second
+    rate: f64,
+    /// Most tokens the bucket can hold, i.e. the largest burst allowed
+    capacity: f64,
+    arrival: Arrival,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    /// Tokens the next send costs: always 1 for uniform arrivals, an exponential sample with mean 1 for Poisson
+    next_cost: f64,
+    rng: StdRng,
+}
+
+impl TokenBucket {
+    /// Creates a bucket refilling at `rate` tokens per second, starting with a single token
+    /// and holding at most `capacity`
+    pub fn new(rate: f64, capacity: f64) -> Self {
+        Self::with_arrival(rate, capacity, Arrival::Uniform, None)
+    }
+
+    /// Creates a bucket whose sends follow `arrival` at an average of `rate` per second.
+    /// `seed` makes a Poisson schedule reproducible; without it the schedule differs every run.
+    pub fn with_arrival(rate: f64, capacity: f64, arrival: Arrival, seed: Option<u64>) -> Self {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let next_cost = sample_cost(arrival, &mut rng);
+        Self {
+            rate,
+            capacity: capacity.max(1.0),
+            arrival,
+            state: Mutex::new(BucketState {
+                // Enough for the first send to go out right away
+                tokens: next_cost.max(1.0),
+                last_refill: Instant::now(),
+                next_cost,
+                rng,
+            }),
+        }
+    }
+
+    /// Waits until a token is available and takes it. Waiters are served in FIFO order, only the one
+    /// at the front sleeps. Cancel-safe: a token is only consumed when the future completes.
+    pub async fn acquire(&self) {
+        let mut state = self.state.lock().await;
+        loop {
+            let now = Instant::now();
+            let refill = now.duration_since(state.last_refill).as_secs_f64() * self.rate;
+            // A long Poisson gap may cost more than the capacity, so the bucket must be able to hold it
+            let capacity = self.capacity.max(state.next_cost);
+            state.tokens = (state.tokens + refill).min(capacity);
+            state.last_refill = now;
+            if state.tokens >= state.next_cost {
+                state.tokens -= state.next_cost;
+                state.next_cost = sample_cost(self.arrival, &mut state.rng);
+                return;
+            }
+            sleep(Duration::from_secs_f64((state.next_cost - state.tokens) / self.rate)).await;
+        }
+    }
+}
+
+/// Tokens the next send costs, in units of the mean gap between sends
+fn sample_cost(arrival: Arrival, rng: &mut StdRng) -> f64 {
+    match arrival {
+        Arrival::Uniform => 1.0,
+        // Inverse transform of a uniform sample in (0, 1]
+        Arrival::Poisson => -(1.0 - rng.gen::<f64>()).ln(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_pattern_needs_a_positive_size_and_a_gap() {
+        let pattern: BurstPattern = "burst:size=100,gap=500ms".parse().unwrap();
+        assert_eq!(pattern, BurstPattern { size: 100, gap: Duration::from_millis(500) });
+
+        let error = |s: &str| s.parse::<BurstPattern>().unwrap_err();
+        assert!(error("burst:size=100").contains("needs both size and gap"));
+        assert!(error("burst:gap=1s").contains("needs both size and gap"));
+        assert_eq!(error("burst:size=0,gap=1s"), "burst size must be at least 1");
+        assert!(error("burst:size=ten,gap=1s").starts_with("invalid burst size \"ten\""));
+        assert!(error("burst:size=10,gap=soon").starts_with("invalid burst gap \"soon\""));
+        assert!(error("burst:size=10,gap=1s,jitter=5").starts_with("unknown burst parameter \"jitter=5\""));
+        assert!(error("bursts:size=10,gap=1s").starts_with("expected burst:size=<n>,gap=<duration>"));
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::{anyhow, Context, Result};
+use ethers::types::{Address, Bytes};
+use std::{fmt::Write as _, fs, path::Path};
+
+/// Nonces one wallet signed in a generated file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonceRange {
+    pub wallet: Address,
+    pub first: u64,
+    pub last: u64,
+}
+
+/// Pre-signed transactions written by `generate` and submitted later by `replay`.
+/// The file holds one raw transaction in hex per line, after `#` comments describing where they came from.
+#[derive(Debug, Clone, Default)]
+pub struct RawTxFile {
+    pub chain_id: u64,
+    pub nonces: Vec<NonceRange>,
+    pub txs: Vec<Bytes>,
+}
+
+impl RawTxFile {
+    /// Writes the header comments followed by the raw transactions
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut contents = String::new();
+        writeln!(contents, "# chain {}, {} transactions", self.chain_id, self.txs.len())?;
+        for range in &self.nonces {
+            writeln!(contents, "# wallet {:?} nonces {}..={}", range.wallet, range.first, range.last)?;
+        }
+        for tx in &self.txs {
+            writeln!(contents, "{}", tx)?;
+        }
+        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Reads the raw transactions of a file written by [`RawTxFile::save`], in order.
+    /// Blank lines and `#` comments are skipped, and a malformed line is reported with its line number.
+    pub fn load(path: &Path) -> Result<Vec<Bytes>> {
+        let contents =
+            fs::read_to_string(path).with_context(|| format!("failed to read raw transactions file {}", path.display()))?;
+        let mut txs = Vec::new();
+        for (line_idx, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let tx = line
+                .parse::<Bytes>()
+                .map_err(|e| anyhow!("{}:{}: invalid raw transaction: {}", path.display(), line_idx + 1, e))?;
+            txs.push(tx);
+        }
+        Ok(txs)
+    }
+}
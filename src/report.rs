@@ -0,0 +1,824 @@
+use ethers::types::{Address, H256, U256};
+use serde::{Serialize, Serializer};
+use std::{fmt, time::Duration};
+
+use crate::{
+    adaptive::ConcurrencyReport,
+    bundle::BundleReport,
+    confirm::{ConfirmationReport, DrainReport, MinedThroughput, NEAR_LIMIT_UTILIZATION},
+    deploy::Deployment,
+    failures::{FailureHistogram, FailureKind},
+    fees::{ether, gwei},
+    funding::FundingReport,
+    mempool::MempoolReport,
+    probe::ProbeReport,
+    ramp::RampReport,
+    stats::{LatencyHistogram, LatencyStats, TpsSample},
+    transport::EndpointStats,
+};
+
+/// Seconds of the TPS time series shown in the text summary; the CSV export has all of them
+const TEXT_TIMESERIES_LIMIT: usize = 60;
+
+/// Bursts listed one by one in the text summary; the rest are counted
+const TEXT_BURST_LIMIT: usize = 20;
+
+/// Bundles listed one by one in the text summary; the rest are counted
+const TEXT_BUNDLE_LIMIT: usize = 20;
+
+/// Outcome of a benchmark run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RunReport {
+    /// Transactions accepted by the RPC
+    pub total_sent: usize,
+    /// Transactions that failed to prepare or send
+    pub total_failed: usize,
+    /// Sends still in flight when a sustained run hit its deadline or a shutdown's grace period ran out
+    pub total_abandoned: usize,
+    /// Whether the run was stopped early; totals only cover what completed
+    pub interrupted: bool,
+    /// Whether transactions were only built and signed, never broadcast
+    pub dry_run: bool,
+    /// Seed behind the run's random choices; the same seed repeats them
+    pub seed: Option<u64>,
+    /// Contract deployed as the target before the run, for deploy-and-bench
+    pub deployment: Option<Deployment>,
+    /// Warm-up transactions accepted before measuring, not included in any other figure
+    pub warmup_sent: usize,
+    /// Wall time from the first preparation to the last send completing
+    #[serde(rename = "batch_duration_ms", serialize_with = "serialize_millis")]
+    pub batch_duration: Duration,
+    /// Time spent building transactions
+    #[serde(rename = "prep_duration_ms", serialize_with = "serialize_millis")]
+    pub prep_duration: Duration,
+    /// Time spent signing transactions
+    #[serde(rename = "sign_duration_ms", serialize_with = "serialize_millis")]
+    pub sign_duration: Duration,
+    /// Transactions signed in the up-front signing phase of a batch run
+    pub total_signed: usize,
+    /// Time spent submitting transactions
+    #[serde(rename = "send_duration_ms", serialize_with = "serialize_millis")]
+    pub send_duration: Duration,
+    /// Retries issued for transient send failures
+    pub total_retries: u64,
+    /// Sends the node rejected as already known; they are in its mempool, so they count as sent
+    pub already_known: usize,
+    /// Latency percentiles of the accepted sends
+    pub send_latency: LatencyStats,
+    /// Full latency distribution of the accepted sends, for plotting
+    #[serde(skip)]
+    pub latency_histogram: LatencyHistogram,
+    /// Hashes of the accepted transactions
+    pub hashes: Vec<H256>,
+    /// Receipt outcome, only present when confirmation was requested
+    pub confirmation: Option<ConfirmationReport>,
+    /// How fast the chain mined the wallets' transactions, sampled while sending and confirming
+    pub mined: Option<MinedThroughput>,
+    /// How the mempool drained after sending, when a cooldown ran
+    pub cooldown: Option<DrainReport>,
+    /// Node-wide mempool size before and after sending, when it was inspected
+    pub mempool: Option<MempoolReport>,
+    /// Per-window results, only present for a ramp run
+    pub ramp: Option<RampReport>,
+    /// Per-window results and the discovered rate, only present for a probe run
+    pub probe: Option<ProbeReport>,
+    /// Traffic carried by each RPC endpoint
+    pub endpoints: Vec<EndpointStats>,
+    /// Accepted sends per second of the run
+    pub timeseries: Vec<TpsSample>,
+    /// What the funding phase distributed, when one ran
+    pub funding: Option<FundingReport>,
+    /// Failed sends grouped by reason
+    pub failures: FailureHistogram,
+    /// Chain tip before sending and after the run (including confirmation)
+    pub blocks: Option<BlockRange>,
+    /// JSON-RPC batches used to submit the transactions, when batching was enabled
+    pub rpc_batches: Option<BatchStats>,
+    /// How the relay took each bundle, when the run submitted bundles
+    pub bundles: Option<BundleReport>,
+    /// Upper bound in wei of the fees of accepted sends: gas limit times max per-gas price
+    pub estimated_gas_cost: U256,
+    /// Lowest and highest max per-gas price of the accepted sends
+    pub gas_prices: Option<GasPriceRange>,
+    /// Balance of each wallet before the workload and after the run
+    pub balances: Vec<WalletBalance>,
+    /// Failed nonces that accepted transactions with higher nonces are stuck behind, per wallet
+    pub nonce_gaps: Vec<NonceGap>,
+    /// Failed nonces resent after the send phase to close the gaps
+    pub gaps_filled: usize,
+    /// Where the self-tuning concurrency limit settled, in adaptive mode
+    pub concurrency: Option<ConcurrencyReport>,
+    /// Per-burst results, only present for a burst pattern run
+    pub bursts: Option<BurstReport>,
+    /// Every send of the run, when per-transaction records were requested
+    #[serde(skip)]
+    pub records: Vec<TxRecord>,
+}
+
+/// How one send went, for per-transaction records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxRecord {
+    /// Position of the transaction in the run
+    pub index: u64,
+    /// Nonce the transaction was built with
+    pub nonce: u64,
+    pub wallet: Address,
+    /// Hash the node accepted it under
+    pub hash: Option<H256>,
+    pub send_latency: Option<Duration>,
+    /// Why the send failed
+    pub error: Option<String>,
+}
+
+/// Spread of the max per-gas prices accepted transactions were signed with, in wei
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GasPriceRange {
+    pub min: U256,
+    pub max: U256,
+}
+
+impl GasPriceRange {
+    /// Widens the range to include `price`, starting one if there is none yet
+    pub fn include(range: Option<Self>, price: U256) -> Self {
+        match range {
+            Some(range) => Self { min: range.min.min(price), max: range.max.max(price) },
+            None => Self { min: price, max: price },
+        }
+    }
+}
+
+/// How each burst of a burst pattern run went
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BurstReport {
+    /// Transactions per burst, except possibly the last
+    pub size: usize,
+    /// Pause between bursts
+    #[serde(rename = "gap_ms", serialize_with = "serialize_millis")]
+    pub gap: Duration,
+    pub bursts: Vec<Burst>,
+}
+
+/// One burst of sends
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Burst {
+    pub sent: usize,
+    pub failed: usize,
+    /// From the first send of the burst starting to the last one completing
+    #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+    pub duration: Duration,
+}
+
+impl BurstReport {
+    /// Time spent sending, excluding the gaps
+    pub fn active(&self) -> Duration {
+        self.bursts.iter().map(|burst| burst.duration).sum()
+    }
+
+    /// Accepted sends per second while a burst was going on
+    pub fn in_burst_tps(&self) -> f64 {
+        let sent: usize = self.bursts.iter().map(|burst| burst.sent).sum();
+        sent as f64 / self.active().as_secs_f64()
+    }
+
+    /// Share of the send phase spent inside bursts rather than pausing
+    pub fn duty_cycle(&self, send_duration: Duration) -> f64 {
+        if send_duration.is_zero() {
+            return 0.0;
+        }
+        (self.active().as_secs_f64() / send_duration.as_secs_f64()).min(1.0)
+    }
+}
+
+/// A nonce that never reached the node while later nonces of the same wallet did.
+/// Those later transactions can't be mined until the gap is filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct NonceGap {
+    pub wallet: Address,
+    /// Lowest failed nonce of the wallet
+    pub nonce: u64,
+    /// Failed nonces of the wallet from `nonce` on
+    pub missing: usize,
+    /// Accepted transactions with a nonce above `nonce`
+    pub blocked: usize,
+}
+
+/// A wallet's share of a run and its balance before and after
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct WalletBalance {
+    pub wallet: Address,
+    /// Nonces the wallet handed out during the measured run, whether or not the sends landed
+    pub transactions: u64,
+    /// Nonces it handed out to warm-up sends first. `before` is read ahead of them, so what the wallet
+    /// spent includes their fees.
+    pub warmup: u64,
+    pub before: U256,
+    pub after: U256,
+}
+
+/// How transactions were packed into JSON-RPC batch requests
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchStats {
+    /// Batch requests sent
+    pub count: usize,
+    /// Transactions per batch, except possibly the last
+    pub size: usize,
+    /// Round-trip time of each batch request
+    pub latency: LatencyStats,
+}
+
+/// Chain tip observed before and after a run
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BlockRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl BlockRange {
+    /// Blocks produced while the run was going on
+    pub fn produced(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+}
+
+/// JSON shape of a report: the raw fields plus the derived throughput numbers scripts care about
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    report: &'a RunReport,
+    batch_tps: f64,
+    send_tps: f64,
+    sign_tps: Option<f64>,
+    mined_tps: Option<f64>,
+    mined_per_block: Option<f64>,
+}
+
+impl RunReport {
+    /// Adds the totals of one window of a multi-window run (ramp or probe) to this report
+    pub(crate) fn absorb_window(&mut self, window: RunReport) {
+        self.total_sent += window.total_sent;
+        self.total_failed += window.total_failed;
+        self.total_abandoned += window.total_abandoned;
+        self.batch_duration += window.batch_duration;
+        self.prep_duration += window.prep_duration;
+        self.sign_duration += window.sign_duration;
+        self.send_duration += window.send_duration;
+        self.latency_histogram.merge(&window.latency_histogram);
+        self.hashes.extend(window.hashes);
+    }
+
+    /// Transactions attempted, whether or not they were accepted
+    pub fn total_attempted(&self) -> usize {
+        self.total_sent + self.total_failed
+    }
+
+    /// Transactions known or assumed to be mined per block produced during the run.
+    /// Uses the confirmed count when receipts were awaited, otherwise every accepted send.
+    pub fn mined_per_block(&self) -> Option<f64> {
+        let produced = self.blocks?.produced();
+        if produced == 0 {
+            return None;
+        }
+        let mined = self.confirmation.as_ref().map_or(self.total_sent, |c| c.mined + c.reverted);
+        Some(mined as f64 / produced as f64)
+    }
+
+    /// Attempted transactions per second over the whole batch
+    pub fn batch_tps(&self) -> f64 {
+        self.total_attempted() as f64 / self.batch_duration.as_secs_f64()
+    }
+
+    /// Transactions signed per second in the up-front signing phase, for batch runs
+    pub fn sign_tps(&self) -> Option<f64> {
+        (self.total_signed > 0).then(|| self.total_signed as f64 / self.sign_duration.as_secs_f64())
+    }
+
+    /// Accepted transactions per second over the send phase
+    pub fn send_tps(&self) -> f64 {
+        self.total_sent as f64 / self.send_duration.as_secs_f64()
+    }
+
+    /// Renders the TPS time series as CSV with a `second,tps` header
+    pub fn timeseries_csv(&self) -> String {
+        let mut csv = String::from("second,tps\n");
+        for sample in &self.timeseries {
+            csv.push_str(&format!("{},{}\n", sample.second, sample.tps));
+        }
+        csv
+    }
+
+    /// Renders one CSV row per send in index order. The block, gas and status columns are filled in
+    /// from receipts when the run confirmed; status is `unconfirmed` for accepted sends without one.
+    pub fn records_csv(&self) -> String {
+        let mut csv = String::from("index,nonce,wallet,hash,send_latency_ms,result,error,block_number,gas_used,status\n");
+        let mut records: Vec<&TxRecord> = self.records.iter().collect();
+        records.sort_by_key(|record| (record.index, record.nonce));
+        for record in records {
+            let inclusion = record.hash.and_then(|hash| self.confirmation.as_ref()?.inclusions.get(&hash));
+            let status = match (inclusion, &self.confirmation, record.hash) {
+                (Some(inclusion), _, _) if inclusion.success => "success",
+                (Some(_), _, _) => "reverted",
+                (None, Some(_), Some(_)) => "unconfirmed",
+                _ => "",
+            };
+            csv.push_str(&format!(
+                "{},{},{:?},{},{},{},{},{},{},{}\n",
+                record.index,
+                record.nonce,
+                record.wallet,
+                record.hash.map(|hash| format!("{:?}", hash)).unwrap_or_default(),
+                record.send_latency.map(|latency| format!("{:.3}", latency.as_secs_f64() * 1000.0)).unwrap_or_default(),
+                if record.error.is_none() { "ok" } else { "error" },
+                csv_field(record.error.as_deref().unwrap_or_default()),
+                inclusion.and_then(|inclusion| inclusion.block_number).map(|block| block.to_string()).unwrap_or_default(),
+                inclusion.and_then(|inclusion| inclusion.gas_used).map(|gas| gas.to_string()).unwrap_or_default(),
+                status,
+            ));
+        }
+        csv
+    }
+
+    /// Renders the report as pretty-printed JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&JsonReport {
+            report: self,
+            batch_tps: self.batch_tps(),
+            send_tps: self.send_tps(),
+            sign_tps: self.sign_tps(),
+            mined_tps: self.mined.map(|mined| mined.tps()),
+            mined_per_block: self.mined_per_block(),
+        })
+    }
+}
+
+impl RunReport {
+    /// Renders the report as Markdown: a metric/value table, then one table per endpoint and per
+    /// wallet. Durations are fixed-precision milliseconds and rows always come in the same order,
+    /// so two runs diff cleanly.
+    pub fn to_markdown(&self) -> String {
+        let mut rows: Vec<(&str, String)> = Vec::new();
+        if self.dry_run {
+            rows.push(("Dry run", "no transactions were broadcast".to_string()));
+        }
+        if self.interrupted {
+            rows.push(("Interrupted", "totals cover completed sends only".to_string()));
+        }
+        if let Some(seed) = self.seed {
+            rows.push(("Seed", seed.to_string()));
+        }
+        if let Some(deployment) = &self.deployment {
+            rows.push(("Deployed contract", format!("{:?}", deployment.address)));
+            rows.push(("Deployment gas", deployment.gas_used.to_string()));
+        }
+        rows.push(("Transactions sent", self.total_sent.to_string()));
+        rows.push(("Transactions failed", self.total_failed.to_string()));
+        rows.push(("Retries", self.total_retries.to_string()));
+        rows.push(("Already known (counted as sent)", self.already_known.to_string()));
+        rows.push(("Send duration", millis(self.send_duration)));
+        rows.push(("Send TPS", format!("{:.2}", self.send_tps())));
+        rows.push(("Batch TPS", format!("{:.2}", self.batch_tps())));
+        rows.push(("Send latency p50 / p95 / p99 / max", latency_cell(&self.send_latency)));
+        if let Some(bundles) = &self.bundles {
+            rows.push(("Target block", bundles.block.to_string()));
+            rows.push(("Bundles accepted / rejected", format!("{} / {}", bundles.accepted(), bundles.rejected())));
+        }
+        if let Some(mined) = &self.mined {
+            rows.push(("Mined TPS", format!("{:.2}", mined.tps())));
+        }
+        if let Some(mempool) = &self.mempool {
+            let (before, after) = (mempool.before, mempool.after);
+            rows.push(("Mempool pending / queued before", format!("{} / {}", before.pending, before.queued)));
+            if let Some(after) = after {
+                rows.push(("Mempool pending / queued after", format!("{} / {}", after.pending, after.queued)));
+            }
+        }
+        if let Some(cooldown) = &self.cooldown {
+            rows.push(("Mempool drain", millis(cooldown.duration)));
+            rows.push(("Still pending after cooldown", cooldown.pending.to_string()));
+        }
+        if let Some(confirmation) = &self.confirmation {
+            rows.push((
+                "Mined / reverted / unconfirmed",
+                format!("{} / {} / {}", confirmation.mined, confirmation.reverted, confirmation.unconfirmed),
+            ));
+            if confirmation.depth > 1 {
+                rows.push(("Confirmation depth (blocks)", confirmation.depth.to_string()));
+                rows.push(("Reorgs seen", confirmation.reorgs.to_string()));
+                let reorged = format!("{} / {}", confirmation.reorged_out, confirmation.rebroadcast);
+                rows.push(("Reorged out / rebroadcast", reorged));
+            }
+            rows.push(("Blocks to confirm (avg)", format!("{:.2}", confirmation.avg_blocks_to_confirm)));
+            if let Some(latency) = &confirmation.confirm_latency {
+                rows.push(("Confirmation latency p50 / p95 / p99 / max", latency_cell(latency)));
+            }
+            if let Some(gas) = &confirmation.gas_efficiency {
+                rows.push(("Gas limit used (avg)", format!("{:.1}%", gas.avg_utilization * 100.0)));
+            }
+            rows.push(("Gas paid", format!("{} ETH", ether(confirmation.gas_cost))));
+        }
+        if let Some(blocks) = &self.blocks {
+            rows.push(("Blocks", format!("{} -> {} ({} produced)", blocks.start, blocks.end, blocks.produced())));
+        }
+        if let Some(range) = self.gas_prices {
+            rows.push(("Gas price (gwei, min / max)", format!("{} / {}", gwei(range.min), gwei(range.max))));
+        }
+        rows.push(("Gas cost (estimated upper bound)", format!("{} ETH", ether(self.estimated_gas_cost))));
+
+        let mut md = String::from("## Benchmark summary\n\n| Metric | Value |\n| --- | --- |\n");
+        for (metric, value) in rows {
+            md.push_str(&format!("| {} | {} |\n", metric, markdown_cell(&value)));
+        }
+        if !self.failures.is_empty() {
+            md.push_str("\n### Failures\n\n| Reason | Count |\n| --- | ---: |\n");
+            for (kind, count) in &self.failures {
+                md.push_str(&format!("| {} | {} |\n", markdown_cell(&kind.to_string()), count));
+            }
+        }
+        if !self.endpoints.is_empty() {
+            md.push_str("\n### Endpoints\n\n| Endpoint | Accepted | Errors |\n| --- | ---: | ---: |\n");
+            for endpoint in &self.endpoints {
+                md.push_str(&format!("| {} | {} | {} |\n", markdown_cell(&endpoint.url), endpoint.sent, endpoint.errors));
+            }
+        }
+        if let Some(bundles) = &self.bundles {
+            md.push_str("\n### Bundles\n\n| Bundle | Transactions | Result | Latency |\n| ---: | ---: | --- | ---: |\n");
+            for (idx, bundle) in bundles.bundles.iter().enumerate() {
+                let result = match (&bundle.error, bundle.bundle_hash) {
+                    (Some(error), _) => format!("rejected: {}", error),
+                    (None, Some(hash)) => format!("accepted as {:?}", hash),
+                    (None, None) => "accepted".to_string(),
+                };
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} |\n",
+                    idx + 1,
+                    bundle.transactions,
+                    markdown_cell(&result),
+                    millis(bundle.latency)
+                ));
+            }
+        }
+        if !self.balances.is_empty() {
+            md.push_str(
+                "\n### Wallets\n\n| Wallet | Transactions | Warm-up | Before (ETH) | After (ETH) | Spent (ETH) |\n\
+                 | --- | ---: | ---: | ---: | ---: | ---: |\n",
+            );
+            for balance in &self.balances {
+                md.push_str(&format!(
+                    "| {:?} | {} | {} | {} | {} | {} |\n",
+                    balance.wallet,
+                    balance.transactions,
+                    balance.warmup,
+                    ether(balance.before),
+                    ether(balance.after),
+                    ether(balance.before.saturating_sub(balance.after))
+                ));
+            }
+        }
+        md
+    }
+}
+
+/// A duration as milliseconds with a fixed number of decimals
+fn millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Latency percentiles in one table cell
+fn latency_cell(latency: &LatencyStats) -> String {
+    format!(
+        "{:.1} / {:.1} / {:.1} / {:.1} ms",
+        latency.p50.as_secs_f64() * 1000.0,
+        latency.p95.as_secs_f64() * 1000.0,
+        latency.p99.as_secs_f64() * 1000.0,
+        latency.max.as_secs_f64() * 1000.0
+    )
+}
+
+/// Quotes a CSV field when it holds a separator, quote or line break, doubling any quotes inside
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Escapes the characters that would break a Markdown table cell
+pub(crate) fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+impl fmt::Display for RunReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== SUMMARY =====")?;
+        if self.dry_run {
+            writeln!(f, "Dry run: no transactions were broadcast")?;
+        }
+        if self.interrupted {
+            writeln!(f, "Run interrupted: totals cover completed sends only")?;
+        }
+        if self.warmup_sent > 0 {
+            writeln!(f, "Warm-up: {} transactions sent before measuring, excluded below", self.warmup_sent)?;
+        }
+        if let Some(seed) = self.seed {
+            writeln!(f, "Seed: {} (pass --seed {} to repeat the random choices)", seed, seed)?;
+        }
+        if let Some(deployment) = &self.deployment {
+            writeln!(f, "Deployed contract: {}", deployment)?;
+        }
+        writeln!(f, "Total time to send all transactions: {:?}", self.batch_duration)?;
+        writeln!(f, "Transactions per second: {:.2}", self.batch_tps())?;
+        if let Some(mined) = &self.mined {
+            writeln!(
+                f,
+                "Mined vs sent: {:.2} tx/s mined ({} over {:?}) vs {:.2} tx/s submitted",
+                mined.tps(), mined.mined, mined.duration, self.send_tps()
+            )?;
+        }
+        if let Some(mempool) = &self.mempool {
+            writeln!(f, "Node mempool: {}", mempool)?;
+        }
+        if let Some(cooldown) = &self.cooldown {
+            if cooldown.drained {
+                write!(f, "Cooldown: mempool drained in {:?}", cooldown.duration)?;
+            } else if cooldown.interrupted {
+                write!(f, "Cooldown: stopped after {:?} with {} transactions still pending", cooldown.duration, cooldown.pending)?;
+            } else {
+                write!(f, "Cooldown: {} transactions still pending after {:?}", cooldown.pending, cooldown.duration)?;
+            }
+            writeln!(f, " ({} mined, {:.2} tx/s)", cooldown.mined, cooldown.clearance_rate())?;
+        }
+        write!(f, "Phase timings: prepare {:?}, sign {:?}", self.prep_duration, self.sign_duration)?;
+        if let Some(sign_tps) = self.sign_tps() {
+            write!(f, " ({:.2} tx/s)", sign_tps)?;
+        }
+        writeln!(f, ", send {:?} ({:.2} tx/s)", self.send_duration, self.send_tps())?;
+        writeln!(f, "Total transactions sent: {}", self.total_sent)?;
+        writeln!(f, "Total transactions failed: {}", self.total_failed)?;
+        if self.already_known > 0 {
+            writeln!(f, "Already known to the node: {} (counted as sent)", self.already_known)?;
+        }
+        if !self.failures.is_empty() {
+            let reasons: Vec<String> = self.failures.iter().map(|(kind, count)| format!("{} {}", kind, count)).collect();
+            writeln!(f, "Send failures by reason: {}", reasons.join(", "))?;
+        }
+        if self.failures.contains_key(&FailureKind::ReplacementUnderpriced) {
+            writeln!(
+                f,
+                "Hint: replacements must outbid the transaction already holding the nonce; raise \
+                 --gas-escalation-pct or the gas multiplier"
+            )?;
+        }
+        for gap in &self.nonce_gaps {
+            writeln!(
+                f,
+                "Nonce gap: wallet {:?} is missing {} nonces from {}, blocking {} sent transactions",
+                gap.wallet, gap.missing, gap.nonce, gap.blocked
+            )?;
+        }
+        if self.gaps_filled > 0 {
+            writeln!(f, "Nonce gaps filled: {} transactions resent", self.gaps_filled)?;
+        }
+        if self.total_abandoned > 0 {
+            writeln!(f, "Total transactions abandoned in flight: {}", self.total_abandoned)?;
+        }
+        writeln!(f, "Total retries: {}", self.total_retries)?;
+        if let Some(concurrency) = &self.concurrency {
+            writeln!(
+                f,
+                "Adaptive concurrency: converged on {} in flight (peak {}, {} back-offs)",
+                concurrency.converged, concurrency.peak, concurrency.backoffs
+            )?;
+        }
+        write!(
+            f,
+            "Send latency: p50 {:?}, p95 {:?}, p99 {:?}, max {:?}",
+            self.send_latency.p50, self.send_latency.p95, self.send_latency.p99, self.send_latency.max
+        )?;
+        if let Some(batches) = &self.rpc_batches {
+            write!(
+                f,
+                "\nJSON-RPC batches: {} of up to {} transactions, latency p50 {:?}, p95 {:?}, max {:?}",
+                batches.count, batches.size, batches.latency.p50, batches.latency.p95, batches.latency.max
+            )?;
+        }
+        if let Some(bundles) = &self.bundles {
+            write!(
+                f,
+                "\nBundles: {} of {} accepted by the relay for block {} (up to {} transactions each)",
+                bundles.accepted(),
+                bundles.bundles.len(),
+                bundles.block,
+                bundles.size
+            )?;
+            for (idx, bundle) in bundles.bundles.iter().enumerate().take(TEXT_BUNDLE_LIMIT) {
+                write!(f, "\n  Bundle {}: {}", idx + 1, bundle)?;
+            }
+            if bundles.bundles.len() > TEXT_BUNDLE_LIMIT {
+                write!(f, "\n  ... ({} more bundles)", bundles.bundles.len() - TEXT_BUNDLE_LIMIT)?;
+            }
+        }
+        if let Some(bursts) = &self.bursts {
+            write!(
+                f,
+                "\nBursts: {} of up to {} every {:?}, {:.2} tx/s within bursts, {:.2} tx/s overall at a {:.0}% duty cycle",
+                bursts.bursts.len(),
+                bursts.size,
+                bursts.gap,
+                bursts.in_burst_tps(),
+                self.send_tps(),
+                bursts.duty_cycle(self.send_duration) * 100.0
+            )?;
+            for (idx, burst) in bursts.bursts.iter().enumerate().take(TEXT_BURST_LIMIT) {
+                write!(f, "\n  Burst {}: {} sent, {} failed in {:?}", idx + 1, burst.sent, burst.failed, burst.duration)?;
+            }
+            if bursts.bursts.len() > TEXT_BURST_LIMIT {
+                write!(f, "\n  ... ({} more bursts)", bursts.bursts.len() - TEXT_BURST_LIMIT)?;
+            }
+        }
+        if let Some(confirmation) = &self.confirmation {
+            write!(
+                f,
+                "\nConfirmations: {} mined, {} reverted, {} unconfirmed, {:.2} blocks to confirm on average",
+                confirmation.mined, confirmation.reverted, confirmation.unconfirmed, confirmation.avg_blocks_to_confirm
+            )?;
+            if confirmation.bumped > 0 {
+                write!(f, " ({} needed a fee bump)", confirmation.bumped)?;
+            }
+            if confirmation.interrupted {
+                write!(f, " (stopped early, so the rest are unconfirmed)")?;
+            }
+            if confirmation.depth > 1 {
+                write!(
+                    f,
+                    "\nConfirmation depth: {} blocks, {} reorgs seen, {} transactions reorged out after inclusion",
+                    confirmation.depth, confirmation.reorgs, confirmation.reorged_out
+                )?;
+                if confirmation.rebroadcast > 0 {
+                    write!(f, " ({} rebroadcast)", confirmation.rebroadcast)?;
+                }
+            }
+            if let Some(gas) = confirmation.gas_efficiency {
+                write!(
+                    f,
+                    "\nGas efficiency: {:.1}% of the gas limit used on average (lowest {:.1}%), {} of {} used at least {:.0}% of it",
+                    gas.avg_utilization * 100.0,
+                    gas.min_utilization * 100.0,
+                    gas.near_limit,
+                    gas.samples,
+                    NEAR_LIMIT_UTILIZATION * 100.0
+                )?;
+            }
+            if let Some(latency) = confirmation.confirm_latency {
+                write!(
+                    f,
+                    "\nConfirmation latency: p50 {:?}, p95 {:?}, p99 {:?}, max {:?}",
+                    latency.p50, latency.p95, latency.p99, latency.max
+                )?;
+            }
+            if !confirmation.revert_reasons.is_empty() {
+                let reasons: Vec<String> = confirmation
+                    .revert_reasons
+                    .iter()
+                    .map(|(reason, count)| format!("{:?} x{}", reason, count))
+                    .collect();
+                write!(f, "\nRevert reasons: {}", reasons.join(", "))?;
+            }
+        }
+        if let Some(blocks) = &self.blocks {
+            write!(f, "\nBlocks: {} -> {} ({} produced during the run", blocks.start, blocks.end, blocks.produced())?;
+            if let Some(per_block) = self.mined_per_block() {
+                write!(f, ", {:.2} tx per block", per_block)?;
+            }
+            write!(f, ")")?;
+        }
+        match self.gas_prices {
+            Some(range) if range.min != range.max => {
+                write!(f, "\nGas price: {} to {} gwei", gwei(range.min), gwei(range.max))?
+            }
+            Some(range) => write!(f, "\nGas price: {} gwei", gwei(range.max))?,
+            None => {}
+        }
+        if !self.estimated_gas_cost.is_zero() {
+            write!(f, "\nGas cost: up to {} ETH estimated", ether(self.estimated_gas_cost))?;
+            if let Some(confirmation) = &self.confirmation {
+                write!(f, ", {} ETH paid according to receipts", ether(confirmation.gas_cost))?;
+            }
+        }
+        let transactions: u64 = self.balances.iter().map(|balance| balance.transactions).sum();
+        for balance in &self.balances {
+            write!(
+                f,
+                "\nWallet {:?}: {} transactions ({:.1}%){}, {} ETH -> {} ETH ({} ETH spent)",
+                balance.wallet,
+                balance.transactions,
+                balance.transactions as f64 / transactions.max(1) as f64 * 100.0,
+                match balance.warmup {
+                    0 => String::new(),
+                    warmup => format!(" after {} warm-up", warmup),
+                },
+                ether(balance.before),
+                ether(balance.after),
+                ether(balance.before.saturating_sub(balance.after))
+            )?;
+        }
+        if let Some(funding) = &self.funding {
+            write!(
+                f,
+                "\nFunding: {} ETH distributed to {} wallets",
+                ether(funding.total_distributed), funding.wallets_funded
+            )?;
+        }
+        if !self.timeseries.is_empty() {
+            let shown: Vec<String> = self
+                .timeseries
+                .iter()
+                .take(TEXT_TIMESERIES_LIMIT)
+                .map(|sample| format!("{}s: {}", sample.second, sample.tps))
+                .collect();
+            write!(f, "\nTPS by second: {}", shown.join(", "))?;
+            if self.timeseries.len() > TEXT_TIMESERIES_LIMIT {
+                write!(f, " ... ({} more seconds)", self.timeseries.len() - TEXT_TIMESERIES_LIMIT)?;
+            }
+        }
+        if self.endpoints.len() > 1 {
+            for endpoint in &self.endpoints {
+                write!(f, "\nEndpoint {}: {} sent, {} errors", endpoint.url, endpoint.sent, endpoint.errors)?;
+            }
+        }
+        if let Some(ramp) = &self.ramp {
+            write!(f, "\n{}", ramp)?;
+        }
+        if let Some(probe) = &self.probe {
+            write!(f, "\n{}", probe)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serializes a duration as fractional milliseconds
+pub(crate) fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confirm::Inclusion;
+
+    #[test]
+    fn records_csv_quotes_errors_and_fills_the_status_from_receipts() {
+        let (mined, reverted, pending) = (H256::repeat_byte(0xa1), H256::repeat_byte(0xa2), H256::repeat_byte(0xa3));
+        let record = |index, hash, error: Option<&str>| TxRecord {
+            index,
+            nonce: index + 10,
+            wallet: Address::zero(),
+            hash,
+            send_latency: hash.map(|_| Duration::from_micros(1_500)),
+            error: error.map(str::to_string),
+        };
+        let inclusion = |tx_hash, success| Inclusion {
+            tx_hash,
+            block_number: Some(101),
+            block_hash: Some(H256::repeat_byte(0x65)),
+            gas_used: Some(U256::from(21_000)),
+            success,
+        };
+        let mut confirmation = ConfirmationReport::default();
+        confirmation.inclusions.insert(mined, inclusion(mined, true));
+        confirmation.inclusions.insert(reverted, inclusion(reverted, false));
+        let report = RunReport {
+            records: vec![
+                record(3, Some(pending), None),
+                record(1, None, Some("rejected: \"nonce too low\", have 11\nwant 12")),
+                record(0, Some(mined), None),
+                record(2, Some(reverted), None),
+            ],
+            confirmation: Some(confirmation),
+            ..Default::default()
+        };
+
+        let csv = report.records_csv();
+        let wallet = format!("{:?}", Address::zero());
+        let expected = [
+            "index,nonce,wallet,hash,send_latency_ms,result,error,block_number,gas_used,status".to_string(),
+            format!("0,10,{},{:?},1.500,ok,,101,21000,success", wallet, mined),
+            format!("1,11,{},,,error,\"rejected: \"\"nonce too low\"\", have 11\nwant 12\",,,", wallet),
+            format!("2,12,{},{:?},1.500,ok,,101,21000,reverted", wallet, reverted),
+            format!("3,13,{},{:?},1.500,ok,,,,unconfirmed", wallet, pending),
+        ];
+        assert_eq!(csv, expected.join("\n") + "\n");
+        assert_eq!(csv_field("plain"), "plain");
+    }
+
+    #[test]
+    fn wallet_spend_is_shown_with_the_warm_up_it_includes() {
+        let ether = U256::exp10(18);
+        let balance = WalletBalance { wallet: Address::zero(), transactions: 8, warmup: 2, before: ether * 2, after: ether };
+        let report = RunReport { balances: vec![balance], ..Default::default() };
+        let wallet = format!("{:?}", Address::zero());
+        let summary = format!("Wallet {}: 8 transactions (100.0%) after 2 warm-up, 2.0", wallet);
+        assert!(report.to_string().contains(&summary), "{}", report);
+        let row = format!("| {} | 8 | 2 | 2.000000000000000000 | 1.000000000000000000 | 1.000000000000000000 |", wallet);
+        assert!(report.to_markdown().contains(&row), "{}", report.to_markdown());
+    }
+}
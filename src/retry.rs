@@ -0,0 +1,17 @@
+use std::time::Duration;
+
+/// How failed sends are retried
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Extra attempts after the first failure; 0 disables retrying
+    pub retries: u32,
+    /// Delay before the first retry, doubled on every subsequent one
+    pub base_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Backoff before retry number `attempt` (starting at 1)
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay.saturating_mul(1 << (attempt - 1).min(16))
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fmt;
+use tracing::{info, instrument};
+
+use crate::{benchmarker::Benchmarker, report::RunReport};
+
+/// Mean and sample standard deviation of one figure across rounds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Spread {
+    pub mean: f64,
+    /// Zero with fewer than two rounds
+    pub std_dev: f64,
+}
+
+impl Spread {
+    pub fn of(samples: &[f64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let std_dev = if samples.len() < 2 {
+            0.0
+        } else {
+            (samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+        };
+        Self { mean, std_dev }
+    }
+
+    /// Standard deviation as a percentage of the mean
+    pub fn relative_pct(&self) -> f64 {
+        if self.mean == 0.0 {
+            0.0
+        } else {
+            self.std_dev / self.mean * 100.0
+        }
+    }
+}
+
+impl fmt::Display for Spread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} ± {:.2} ({:.1}%)", self.mean, self.std_dev, self.relative_pct())
+    }
+}
+
+/// The same workload run several times back to back
+#[derive(Debug, Clone, Default)]
+pub struct RoundsReport {
+    pub rounds: Vec<RunReport>,
+}
+
+/// JSON shape of one round
+#[derive(Serialize)]
+struct RoundRow {
+    round: usize,
+    sent: usize,
+    failed: usize,
+    send_tps: f64,
+    p95_ms: f64,
+}
+
+impl RoundsReport {
+    /// Send TPS across the rounds
+    pub fn tps(&self) -> Spread {
+        Spread::of(&self.rounds.iter().map(RunReport::send_tps).collect::<Vec<_>>())
+    }
+
+    /// p95 send latency in milliseconds across the rounds
+    pub fn p95_ms(&self) -> Spread {
+        Spread::of(&self.rounds.iter().map(p95_ms).collect::<Vec<_>>())
+    }
+
+    /// Whether a shutdown stopped the last round early
+    pub fn interrupted(&self) -> bool {
+        self.rounds.last().is_some_and(|round| round.interrupted)
+    }
+
+    /// Renders each round and the aggregate as pretty-printed JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let rounds: Vec<RoundRow> = self
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, report)| RoundRow {
+                round: i + 1,
+                sent: report.total_sent,
+                failed: report.total_failed,
+                send_tps: report.send_tps(),
+                p95_ms: p95_ms(report),
+            })
+            .collect();
+        serde_json::to_string_pretty(&serde_json::json!({
+            "rounds": rounds,
+            "send_tps": self.tps(),
+            "p95_ms": self.p95_ms(),
+        }))
+    }
+
+    /// Renders one table row per round followed by the mean and standard deviation as Markdown
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::from("## Rounds\n\n| Round | Sent | Failed | tx/s | p95 ms |\n| ---: | ---: | ---: | ---: | ---: |\n");
+        for (i, report) in self.rounds.iter().enumerate() {
+            md.push_str(&format!(
+                "| {} | {} | {} | {:.2} | {:.1} |\n",
+                i + 1,
+                report.total_sent,
+                report.total_failed,
+                report.send_tps(),
+                p95_ms(report),
+            ));
+        }
+        let (tps, p95) = (self.tps(), self.p95_ms());
+        md.push_str(&format!("| mean | | | {:.2} | {:.1} |\n", tps.mean, p95.mean));
+        md.push_str(&format!("| std dev | | | {:.2} | {:.1} |\n", tps.std_dev, p95.std_dev));
+        md
+    }
+}
+
+fn p95_ms(report: &RunReport) -> f64 {
+    report.send_latency.p95.as_secs_f64() * 1000.0
+}
+
+impl fmt::Display for RoundsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "===== ROUNDS SUMMARY =====")?;
+        writeln!(f, "{:>5}  {:>7}  {:>7}  {:>9}  {:>9}", "round", "sent", "failed", "tx/s", "p95 ms")?;
+        for (i, report) in self.rounds.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>5}  {:>7}  {:>7}  {:>9.2}  {:>9.1}",
+                i + 1,
+                report.total_sent,
+                report.total_failed,
+                report.send_tps(),
+                p95_ms(report),
+            )?;
+        }
+        if self.interrupted() {
+            writeln!(f, "Interrupted: the last round covers completed sends only")?;
+        }
+        writeln!(f, "Send TPS: {}", self.tps())?;
+        write!(f, "p95 send latency (ms): {}", self.p95_ms())
+    }
+}
+
+/// Runs the benchmarker's workload `rounds` times back to back, confirming each round first when
+/// confirmation is on. Reusing the benchmarker carries each wallet's nonces from one round to the
+/// next, so a round never reuses the nonces of transactions the previous one left pending. A
+/// shutdown stops after the current round. Finished rounds are added to `report` as they complete,
+/// so they survive the caller giving up on a round that hangs.
+#[instrument(name = "rounds", skip_all)]
+pub async fn run_rounds(benchmarker: &Benchmarker, rounds: usize, report: &mut RoundsReport) -> Result<()> {
+    for round in 1..=rounds {
+        if benchmarker.shutdown().is_requested() {
+            break;
+        }
+        info!("Round {}/{}...", round, rounds);
+        let run = benchmarker.run().await.with_context(|| format!("round {} failed", round))?;
+        info!(
+            "Round {}/{}: {} sent, {} failed, {:.2} tx/s, p95 {:?}",
+            round, rounds, run.total_sent, run.total_failed, run.send_tps(), run.send_latency.p95
+        );
+        report.rounds.push(run);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spread_is_the_mean_and_sample_standard_deviation() {
+        let spread = Spread::of(&[90.0, 100.0, 110.0]);
+        assert_eq!(spread.mean, 100.0);
+        assert_eq!(spread.std_dev, 10.0);
+        assert_eq!(spread.relative_pct(), 10.0);
+        assert_eq!(Spread::of(&[42.0]), Spread { mean: 42.0, std_dev: 0.0 });
+        assert_eq!(Spread::of(&[]), Spread::default());
+    }
+}
@@ -0,0 +1,159 @@
+use ethers::providers::MiddlewareError;
+use std::fmt;
+
+use crate::failures::FailureKind;
+
+/// JSON-RPC error code nodes and providers use for "limit exceeded"
+const LIMIT_EXCEEDED_CODE: i64 = -32005;
+
+/// Fragments of a rejection message that mean the node already has the transaction
+const ALREADY_KNOWN: &[&str] = &["already known", "alreadyknown", "already imported"];
+const NONCE_TOO_LOW: &[&str] = &["nonce too low"];
+const REPLACEMENT_UNDERPRICED: &[&str] = &["replacement transaction underpriced", "replacement underpriced"];
+const INSUFFICIENT_FUNDS: &[&str] = &["insufficient funds"];
+const RATE_LIMITED: &[&str] = &["429", "too many requests", "rate limit"];
+const TIMEOUT: &[&str] = &["timeout", "timed out"];
+/// Fragments of a transport error that mean the request may well get through if tried again
+const TRANSIENT: &[&str] = &["server error", "502", "503", "504", "connection", "temporarily unavailable"];
+
+/// Why a send failed, worked out once from the provider's error so that retries, nonce recovery
+/// and the report match on the variant instead of on message text. Each variant keeps the
+/// original message for display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SendError {
+    /// The node already has the transaction, so it is in flight
+    AlreadyKnown(String),
+    /// The nonce was used by another transaction
+    NonceTooLow(String),
+    /// Another transaction holds the nonce and these fees don't outbid it
+    ReplacementUnderpriced(String),
+    /// The sender can't pay for the value plus the fees
+    InsufficientFunds(String),
+    /// The node, or a proxy in front of it, throttled the request
+    RateLimited(String),
+    /// No answer in time
+    Timeout(String),
+    /// The request didn't get through, e.g. a refused connection or a 5xx response
+    Transport(String),
+    /// The node rejected the transaction for a reason not recognised here
+    Rejected(String),
+    /// Anything else, e.g. a response that couldn't be decoded
+    Other(String),
+}
+
+impl SendError {
+    /// Maps an error returned by a provider call
+    pub fn from_provider<E: MiddlewareError>(err: &E) -> Self {
+        match err.as_error_response() {
+            Some(response) if response.code == LIMIT_EXCEEDED_CODE => SendError::RateLimited(response.message.clone()),
+            Some(response) => Self::from_rejection(&response.message),
+            None => Self::from_message(&err.to_string()),
+        }
+    }
+
+    /// Maps a node's JSON-RPC rejection message, e.g. one entry of a batch response
+    pub fn from_rejection(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let matches = |fragments: &[&str]| fragments.iter().any(|fragment| lower.contains(fragment));
+        let message = message.to_string();
+        if matches(ALREADY_KNOWN) {
+            SendError::AlreadyKnown(message)
+        } else if matches(NONCE_TOO_LOW) {
+            SendError::NonceTooLow(message)
+        } else if matches(REPLACEMENT_UNDERPRICED) {
+            SendError::ReplacementUnderpriced(message)
+        } else if matches(INSUFFICIENT_FUNDS) {
+            SendError::InsufficientFunds(message)
+        } else if matches(RATE_LIMITED) {
+            SendError::RateLimited(message)
+        } else if matches(TIMEOUT) {
+            SendError::Timeout(message)
+        } else {
+            SendError::Rejected(message)
+        }
+    }
+
+    /// Maps an error known only by its message, such as a transport failure
+    pub fn from_message(message: &str) -> Self {
+        match Self::from_rejection(message) {
+            SendError::Rejected(message) if TRANSIENT.iter().any(|fragment| message.to_lowercase().contains(fragment)) => {
+                SendError::Transport(message)
+            }
+            SendError::Rejected(message) => SendError::Other(message),
+            known => known,
+        }
+    }
+
+    /// The send error behind `err`, mapped from its message when it didn't come from a send
+    pub fn of(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<SendError>() {
+            Some(send_error) => send_error.clone(),
+            None => Self::from_message(&format!("{:#}", err)),
+        }
+    }
+
+    /// Whether the failure is transient and the send worth retrying. Nonce and funding problems
+    /// are not, so they aren't hammered against the node.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SendError::RateLimited(_) | SendError::Timeout(_) | SendError::Transport(_))
+    }
+
+    /// Reason the failure is counted under in the report
+    pub fn kind(&self) -> FailureKind {
+        match self {
+            SendError::AlreadyKnown(_) => FailureKind::AlreadyKnown,
+            SendError::NonceTooLow(_) => FailureKind::NonceTooLow,
+            SendError::ReplacementUnderpriced(_) => FailureKind::ReplacementUnderpriced,
+            SendError::InsufficientFunds(_) => FailureKind::InsufficientFunds,
+            SendError::RateLimited(_) => FailureKind::RateLimited,
+            SendError::Timeout(_) => FailureKind::Timeout,
+            SendError::Transport(_) | SendError::Rejected(_) | SendError::Other(_) => FailureKind::Other,
+        }
+    }
+
+    /// The message the failure was reported with
+    pub fn message(&self) -> &str {
+        match self {
+            SendError::AlreadyKnown(message)
+            | SendError::NonceTooLow(message)
+            | SendError::ReplacementUnderpriced(message)
+            | SendError::InsufficientFunds(message)
+            | SendError::RateLimited(message)
+            | SendError::Timeout(message)
+            | SendError::Transport(message)
+            | SendError::Rejected(message)
+            | SendError::Other(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for SendError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn errors_are_mapped_once_and_found_again_behind_context() {
+        let err = anyhow::Error::new(SendError::from_rejection("nonce too low: next nonce 5, tx nonce 3"))
+            .context("failed to send transaction 7");
+        assert!(matches!(SendError::of(&err), SendError::NonceTooLow(_)));
+        assert!(!SendError::of(&err).is_retryable());
+
+        let refused = SendError::of(&anyhow!("error sending request: connection refused"));
+        assert_eq!(refused, SendError::Transport("error sending request: connection refused".to_string()));
+        assert!(refused.is_retryable());
+        assert_eq!(refused.kind(), FailureKind::Other);
+
+        assert_eq!(SendError::from_rejection("execution reverted").kind(), FailureKind::Other);
+        assert!(!SendError::from_rejection("execution reverted").is_retryable());
+        assert!(matches!(SendError::of(&anyhow!("bad response").context("decoding")), SendError::Other(_)));
+    }
+}
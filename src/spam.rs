@@ -2,9 +2,8 @@ use anyhow::Result;
 use chrono::Utc;
 use dotenv::dotenv;
 use ethers::{
-    core::types::Bytes,
     middleware::SignerMiddleware,
-    providers::{Http, JsonRpcClient, Middleware, Provider},
+    providers::{Http, Middleware, Provider},
     signers::{LocalWallet, Signer},
     types::{transaction::eip2718::TypedTransaction, TransactionReceipt, H256, U256},
 };
@@ -17,6 +16,7 @@ mod middleware;
 use middleware::sync_transaction::SyncTransactionMiddleware;
 use middleware::realtime_transaction::RealtimeTransactionMiddleware;
 
+#[allow(dead_code)]
 async fn send_and_confirm_transaction_with_duration(
     client: Arc<SignerMiddleware<Provider<Http>, LocalWallet>>,
     nonce: u64,
@@ -176,6 +176,8 @@ async fn send_and_confirm_transaction(
     Ok((tx_hash, send_duration, confirm_duration))
 }
 
+// `usize::is_multiple_of` needs Rust 1.87, newer than the crate otherwise requires
+#[allow(clippy::manual_is_multiple_of)]
 fn median(data: &mut [u128]) -> u128 {
     if data.is_empty() {
         return 0;
@@ -191,6 +193,7 @@ fn median(data: &mut [u128]) -> u128 {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn generate_report_new(
     test_name: &str,
     method: &str,
@@ -215,21 +218,21 @@ fn generate_report_new(
         min_confirm, max_confirm, avg_confirm, med_confirm,
         min_total, max_total, avg_total, med_total) = if !results.is_empty() {
         // Collect send times
-        let mut send_times = results.iter().map(|(_, s, _, _)| s.as_millis() as u128).collect::<Vec<_>>();
+        let mut send_times = results.iter().map(|(_, s, _, _)| s.as_millis()).collect::<Vec<_>>();
         let min_send = *send_times.iter().min().unwrap_or(&0);
         let max_send = *send_times.iter().max().unwrap_or(&0);
         let avg_send = send_times.iter().sum::<u128>() / send_times.len() as u128;
         let med_send = median(&mut send_times);
 
         // Collect confirm times
-        let mut confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis() as u128).collect::<Vec<_>>();
+        let mut confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis()).collect::<Vec<_>>();
         let min_confirm = *confirm_times.iter().min().unwrap_or(&0);
         let max_confirm = *confirm_times.iter().max().unwrap_or(&0);
         let avg_confirm = confirm_times.iter().sum::<u128>() / confirm_times.len() as u128;
         let med_confirm = median(&mut confirm_times);
 
         // Collect total times
-        let mut total_times = results.iter().map(|(_, _, _, t)| t.as_millis() as u128).collect::<Vec<_>>();
+        let mut total_times = results.iter().map(|(_, _, _, t)| t.as_millis()).collect::<Vec<_>>();
         let min_total = *total_times.iter().min().unwrap_or(&0);
         let max_total = *total_times.iter().max().unwrap_or(&0);
         let avg_total = total_times.iter().sum::<u128>() / total_times.len() as u128;
@@ -297,6 +300,7 @@ fn generate_report_new(
 }
 
 /// Generates a markdown report of test results
+#[allow(dead_code, clippy::too_many_arguments)]
 fn generate_report(
     test_name: &str,
     method: &str,
@@ -321,19 +325,19 @@ fn generate_report(
          min_confirm, max_confirm, avg_confirm,
          min_total, max_total, avg_total) = if !results.is_empty() {
         // Send time stats
-        let send_times = results.iter().map(|(_, s, _, _)| s.as_millis() as u128).collect::<Vec<_>>();
+        let send_times = results.iter().map(|(_, s, _, _)| s.as_millis()).collect::<Vec<_>>();
         let min_send = send_times.iter().min().unwrap_or(&0);
         let max_send = send_times.iter().max().unwrap_or(&0);
         let avg_send = send_times.iter().sum::<u128>() / send_times.len() as u128;
 
         // Confirm time stats
-        let confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis() as u128).collect::<Vec<_>>();
+        let confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis()).collect::<Vec<_>>();
         let min_confirm = confirm_times.iter().min().unwrap_or(&0);
         let max_confirm = confirm_times.iter().max().unwrap_or(&0);
         let avg_confirm = confirm_times.iter().sum::<u128>() / confirm_times.len() as u128;
 
         // Total time stats
-        let total_times = results.iter().map(|(_, _, _, t)| t.as_millis() as u128).collect::<Vec<_>>();
+        let total_times = results.iter().map(|(_, _, _, t)| t.as_millis()).collect::<Vec<_>>();
         let min_total = total_times.iter().min().unwrap_or(&0);
         let max_total = total_times.iter().max().unwrap_or(&0);
         let avg_total = total_times.iter().sum::<u128>() / total_times.len() as u128;
@@ -627,21 +631,21 @@ async fn main() -> Result<()> {
     // Calculate min, max, and averages
     if !results.is_empty() {
         // Send time stats
-        let mut send_times = results.iter().map(|(_, s, _, _)| s.as_millis() as u128).collect::<Vec<_>>();
+        let mut send_times = results.iter().map(|(_, s, _, _)| s.as_millis()).collect::<Vec<_>>();
         let min_send = *send_times.iter().min().unwrap_or(&0);
         let max_send = *send_times.iter().max().unwrap_or(&0);
         let avg_send = send_times.iter().sum::<u128>() / send_times.len() as u128;
         let med_send = median(&mut send_times);
 
         // Confirm time stats
-        let mut confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis() as u128).collect::<Vec<_>>();
+        let mut confirm_times = results.iter().map(|(_, _, c, _)| c.as_millis()).collect::<Vec<_>>();
         let min_confirm = *confirm_times.iter().min().unwrap_or(&0);
         let max_confirm = *confirm_times.iter().max().unwrap_or(&0);
         let avg_confirm = confirm_times.iter().sum::<u128>() / confirm_times.len() as u128;
         let med_confirm = median(&mut confirm_times);
 
         // Total time stats
-        let mut total_times = results.iter().map(|(_, _, _, t)| t.as_millis() as u128).collect::<Vec<_>>();
+        let mut total_times = results.iter().map(|(_, _, _, t)| t.as_millis()).collect::<Vec<_>>();
         let min_total = *total_times.iter().min().unwrap_or(&0);
         let max_total = *total_times.iter().max().unwrap_or(&0);
         let avg_total = total_times.iter().sum::<u128>() / total_times.len() as u128;
@@ -0,0 +1,189 @@
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::report::serialize_millis;
+
+/// Tail-latency summary of a set of durations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct LatencyStats {
+    #[serde(rename = "p50_ms", serialize_with = "serialize_millis")]
+    pub p50: Duration,
+    #[serde(rename = "p95_ms", serialize_with = "serialize_millis")]
+    pub p95: Duration,
+    #[serde(rename = "p99_ms", serialize_with = "serialize_millis")]
+    pub p99: Duration,
+    #[serde(rename = "max_ms", serialize_with = "serialize_millis")]
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Computes the percentiles of `samples`; all zero when there are none
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        Self {
+            p50: percentile(&sorted, 50.0),
+            p95: percentile(&sorted, 95.0),
+            p99: percentile(&sorted, 99.0),
+            max: sorted.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Nearest-rank percentile of an already sorted slice
+pub fn percentile(sorted: &[Duration], pct: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Lower edge of the first finite latency bucket, in milliseconds
+const HISTOGRAM_MIN_MS: f64 = 0.1;
+
+/// Log-spaced latency buckets per factor of ten
+const HISTOGRAM_BUCKETS_PER_DECADE: usize = 4;
+
+/// Buckets from 0.1 ms to 100 s; below and above that fall into the first and last bucket
+const HISTOGRAM_BUCKETS: usize = 6 * HISTOGRAM_BUCKETS_PER_DECADE + 2;
+
+/// Full distribution of a set of durations, counted in log-spaced buckets that are the same for
+/// every run, so runs and windows can be added up and compared bucket by bucket
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Empty until the first sample
+    counts: Vec<u64>,
+}
+
+impl LatencyHistogram {
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        let mut histogram = Self::default();
+        for &sample in samples {
+            histogram.record(sample);
+        }
+        histogram
+    }
+
+    pub fn record(&mut self, sample: Duration) {
+        self.counts.resize(HISTOGRAM_BUCKETS, 0);
+        self.counts[Self::bucket(sample)] += 1;
+    }
+
+    /// Adds the counts of `other`, e.g. of another window of the same run
+    pub fn merge(&mut self, other: &Self) {
+        if other.counts.is_empty() {
+            return;
+        }
+        self.counts.resize(HISTOGRAM_BUCKETS, 0);
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.iter().all(|&count| count == 0)
+    }
+
+    /// Renders one `lower_ms,upper_ms,count` row per bucket from the first to the last non-empty
+    /// one, for plotting with a log-scaled x axis; the bucket above 100 s has an `inf` upper edge
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("lower_ms,upper_ms,count\n");
+        let first = self.counts.iter().position(|&count| count > 0);
+        let last = self.counts.iter().rposition(|&count| count > 0);
+        if let (Some(first), Some(last)) = (first, last) {
+            for bucket in first..=last {
+                let (lower, upper) = Self::bounds(bucket);
+                let upper = if upper.is_finite() { format!("{}", upper) } else { "inf".to_string() };
+                csv.push_str(&format!("{},{},{}\n", lower, upper, self.counts[bucket]));
+            }
+        }
+        csv
+    }
+
+    fn bucket(sample: Duration) -> usize {
+        let ms = sample.as_secs_f64() * 1000.0;
+        if ms < HISTOGRAM_MIN_MS {
+            return 0;
+        }
+        let steps = ((ms / HISTOGRAM_MIN_MS).log10() * HISTOGRAM_BUCKETS_PER_DECADE as f64).floor() as usize;
+        (steps + 1).min(HISTOGRAM_BUCKETS - 1)
+    }
+
+    /// Lower and upper edge of a bucket in milliseconds, rounded to three significant digits
+    fn bounds(bucket: usize) -> (f64, f64) {
+        let edge = |step: usize| {
+            let ms = HISTOGRAM_MIN_MS * 10f64.powf(step as f64 / HISTOGRAM_BUCKETS_PER_DECADE as f64);
+            let scale = 10f64.powi(2 - ms.log10().floor() as i32);
+            (ms * scale).round() / scale
+        };
+        match bucket {
+            0 => (0.0, HISTOGRAM_MIN_MS),
+            _ if bucket == HISTOGRAM_BUCKETS - 1 => (edge(bucket - 1), f64::INFINITY),
+            _ => (edge(bucket - 1), edge(bucket)),
+        }
+    }
+}
+
+/// Accepted sends completed during one second of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TpsSample {
+    /// Seconds since the workload started
+    pub second: u64,
+    pub tps: u64,
+}
+
+/// Buckets send completion offsets by the second they fall in, including seconds with none
+pub fn tps_timeseries(completions: &[Duration]) -> Vec<TpsSample> {
+    let Some(last) = completions.iter().max() else {
+        return Vec::new();
+    };
+    let mut counts = vec![0; last.as_secs() as usize + 1];
+    for completion in completions {
+        counts[completion.as_secs() as usize] += 1;
+    }
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(second, tps)| TpsSample { second: second as u64, tps })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_percentiles_of_no_samples_and_of_one() {
+        assert_eq!(LatencyStats::from_samples(&[]), LatencyStats::default());
+
+        let sample = Duration::from_millis(7);
+        let stats = LatencyStats::from_samples(&[sample]);
+        assert_eq!((stats.p50, stats.p95, stats.p99, stats.max), (sample, sample, sample, sample));
+
+        // The rank rounds up, so p50 of two samples is the lower one and p95 the upper one
+        let stats = LatencyStats::from_samples(&[Duration::from_millis(9), Duration::from_millis(3)]);
+        assert_eq!((stats.p50, stats.p95), (Duration::from_millis(3), Duration::from_millis(9)));
+        assert_eq!(percentile(&[sample], 0.0), sample);
+    }
+
+    #[test]
+    fn latency_histogram_keeps_both_modes_of_a_bimodal_run() {
+        let mut samples = vec![Duration::from_micros(1_200); 90];
+        samples.extend(vec![Duration::from_millis(250); 10]);
+        let mut histogram = LatencyHistogram::from_samples(&samples);
+
+        let csv = histogram.to_csv();
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], "lower_ms,upper_ms,count");
+        assert_eq!(rows[1], "1,1.78,90");
+        assert_eq!(rows.last(), Some(&"178,316,10"));
+        assert!(rows[2..rows.len() - 1].iter().all(|row| row.ends_with(",0")));
+
+        histogram.merge(&LatencyHistogram::from_samples(&[Duration::from_secs(500), Duration::ZERO]));
+        let csv = histogram.to_csv();
+        assert!(csv.contains("\n0,0.1,1\n"));
+        assert!(csv.ends_with("\n100000,inf,1\n"));
+        assert!(LatencyHistogram::default().is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use ethers::{
+    providers::{Http, HttpClientError, JsonRpcClient, JsonRpcError, ProviderError, RpcError, Ws, WsClientError},
+    types::{Bytes, H256, U256},
+};
+use reqwest::header::HeaderMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use thiserror::Error;
+use tracing::warn;
+
+/// JSON-RPC transport picked from the endpoint URL scheme
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Http(Http),
+    Ws(Ws),
+}
+
+impl Transport {
+    /// Connects over WebSocket for `ws://`/`wss://` URLs and over HTTP otherwise.
+    /// HTTP requests carry `headers`, e.g. an API key; WebSocket connections ignore them.
+    pub async fn connect(url: &str, headers: &HeaderMap) -> Result<Self> {
+        if url.starts_with("ws://") || url.starts_with("wss://") {
+            if !headers.is_empty() {
+                warn!("Custom RPC headers only apply to HTTP endpoints, not sent to {}", url);
+            }
+            let ws = Ws::connect(url)
+                .await
+                .with_context(|| format!("failed to open WebSocket connection to {}", url))?;
+            Ok(Self::Ws(ws))
+        } else {
+            let parsed: reqwest::Url = url.parse().with_context(|| format!("invalid RPC URL {}", url))?;
+            let client = http_client(headers)?;
+            Ok(Self::Http(Http::new_with_client(parsed, client)))
+        }
+    }
+}
+
+/// HTTP client that sends `headers` with every request
+fn http_client(headers: &HeaderMap) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .default_headers(headers.clone())
+        .build()
+        .context("failed to build the HTTP client")
+}
+
+/// Error of whichever transport served the request
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error(transparent)]
+    Http(#[from] HttpClientError),
+    #[error(transparent)]
+    Ws(#[from] WsClientError),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+impl RpcError for TransportError {
+    fn as_error_response(&self) -> Option<&JsonRpcError> {
+        match self {
+            Self::Http(e) => e.as_error_response(),
+            Self::Ws(e) => e.as_error_response(),
+            Self::Serde(_) => None,
+        }
+    }
+
+    fn as_serde_error(&self) -> Option<&serde_json::Error> {
+        match self {
+            Self::Http(e) => e.as_serde_error(),
+            Self::Ws(e) => e.as_serde_error(),
+            Self::Serde(e) => Some(e),
+        }
+    }
+}
+
+impl From<TransportError> for ProviderError {
+    fn from(e: TransportError) -> Self {
+        ProviderError::JsonRpcClientError(Box::new(e))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for Transport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            Self::Http(http) => Ok(JsonRpcClient::request(http, method, params).await?),
+            Self::Ws(ws) => Ok(JsonRpcClient::request(ws, method, params).await?),
+        }
+    }
+}
+
+impl TransportError {
+    /// Whether the endpoint itself could not be reached, as opposed to it answering with an error
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Self::Http(HttpClientError::ReqwestError(_)) => true,
+            Self::Ws(e) => matches!(
+                e,
+                WsClientError::InternalError(_)
+                    | WsClientError::UnexpectedClose
+                    | WsClientError::DeadChannel
+                    | WsClientError::TooManyReconnects
+            ),
+            _ => false,
+        }
+    }
+}
+
+/// One RPC endpoint of a failover set, with its traffic counters
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    transport: Transport,
+    sent: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// Traffic an endpoint carried during a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EndpointStats {
+    pub url: String,
+    /// Transactions the endpoint accepted
+    pub sent: u64,
+    /// Requests that failed on the endpoint, including connection errors
+    pub errors: u64,
+}
+
+/// Transport over several RPC endpoints that moves on to the next one when the current endpoint
+/// cannot be reached. Requests stick to the endpoint that last worked. Clones share the
+/// endpoints and counters.
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<[Endpoint]>,
+    current: Arc<AtomicUsize>,
+    /// HTTP client for JSON-RPC batches, which the ethers transports can't send
+    batch_client: reqwest::Client,
+}
+
+/// One entry of a JSON-RPC batch response
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    id: usize,
+    result: Option<H256>,
+    error: Option<JsonRpcError>,
+}
+
+impl FailoverTransport {
+    /// Connects to every URL and starts on the first endpoint that answers `eth_chainId`.
+    /// `headers` go out with every HTTP request, batches included.
+    pub async fn connect(urls: &[String], headers: &HeaderMap) -> Result<Self> {
+        let mut endpoints = Vec::with_capacity(urls.len());
+        for url in urls {
+            match Transport::connect(url, headers).await {
+                Ok(transport) => endpoints.push(Endpoint {
+                    url: url.clone(),
+                    transport,
+                    sent: AtomicU64::new(0),
+                    errors: AtomicU64::new(0),
+                }),
+                Err(e) => warn!("Skipping RPC endpoint {}: {:#}", url, e),
+            }
+        }
+
+        let mut current = None;
+        for (idx, endpoint) in endpoints.iter().enumerate() {
+            let probe: Result<U256, _> = JsonRpcClient::request(&endpoint.transport, "eth_chainId", ()).await;
+            match probe {
+                Ok(_) => {
+                    current = Some(idx);
+                    break;
+                }
+                Err(e) => {
+                    endpoint.errors.fetch_add(1, Ordering::Relaxed);
+                    warn!("RPC endpoint {} is unreachable: {}", endpoint.url, e);
+                }
+            }
+        }
+        let Some(current) = current else {
+            bail!("none of the {} RPC endpoints is reachable", urls.len());
+        };
+
+        Ok(Self {
+            endpoints: endpoints.into(),
+            current: Arc::new(AtomicUsize::new(current)),
+            batch_client: http_client(headers)?,
+        })
+    }
+
+    /// Submits raw transactions as a single JSON-RPC batch of `eth_sendRawTransaction` calls and
+    /// returns each transaction's hash or the node's error message, in order. Only HTTP endpoints
+    /// support batches; an unreachable endpoint is failed over like a single request.
+    pub async fn send_raw_batch(&self, txs: &[Bytes]) -> Result<Vec<Result<H256, String>>> {
+        let calls: Vec<Value> = txs
+            .iter()
+            .enumerate()
+            .map(|(id, tx)| json!({"jsonrpc": "2.0", "id": id, "method": "eth_sendRawTransaction", "params": [tx]}))
+            .collect();
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_error = None;
+
+        for offset in 0..self.endpoints.len() {
+            let idx = (start + offset) % self.endpoints.len();
+            let endpoint = &self.endpoints[idx];
+            let Transport::Http(http) = &endpoint.transport else {
+                bail!("JSON-RPC batches need an HTTP endpoint, {} is a WebSocket", endpoint.url);
+            };
+            let response = match self.batch_client.post(http.url().clone()).json(&calls).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    endpoint.errors.fetch_add(1, Ordering::Relaxed);
+                    warn!("RPC endpoint {} is unreachable: {}", endpoint.url, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            let responses: Vec<BatchResponse> = response
+                .error_for_status()?
+                .json()
+                .await
+                .with_context(|| format!("{} did not answer the batch with a JSON-RPC batch response", endpoint.url))?;
+            if idx != start && self.current.compare_exchange(start, idx, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+                warn!("Failed over to RPC endpoint {}", endpoint.url);
+            }
+
+            let mut results: Vec<Result<H256, String>> = vec![Err("missing from the batch response".into()); txs.len()];
+            for response in responses {
+                let Some(slot) = results.get_mut(response.id) else {
+                    continue;
+                };
+                *slot = match (response.result, response.error) {
+                    (Some(hash), _) => Ok(hash),
+                    (None, Some(error)) => Err(error.to_string()),
+                    (None, None) => Err("empty response".into()),
+                };
+            }
+            let accepted = results.iter().filter(|r| r.is_ok()).count() as u64;
+            endpoint.sent.fetch_add(accepted, Ordering::Relaxed);
+            endpoint.errors.fetch_add(txs.len() as u64 - accepted, Ordering::Relaxed);
+            return Ok(results);
+        }
+
+        Err(last_error.expect("failover transport has at least one endpoint").into())
+    }
+
+    /// URL of the endpoint requests currently go to
+    pub fn current_url(&self) -> &str {
+        &self.endpoints[self.current.load(Ordering::Relaxed)].url
+    }
+
+    /// WebSocket connection of the current endpoint, for subscriptions; `None` over HTTP
+    pub fn ws(&self) -> Option<Ws> {
+        match &self.endpoints[self.current.load(Ordering::Relaxed)].transport {
+            Transport::Ws(ws) => Some(ws.clone()),
+            Transport::Http(_) => None,
+        }
+    }
+
+    /// Per-endpoint traffic counters so far
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.endpoints
+            .iter()
+            .map(|endpoint| EndpointStats {
+                url: endpoint.url.clone(),
+                sent: endpoint.sent.load(Ordering::Relaxed),
+                errors: endpoint.errors.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for FailoverTransport {
+    type Error = TransportError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        // Serialize once so the same params can be replayed against another endpoint
+        let params = serde_json::to_value(params)?;
+        let start = self.current.load(Ordering::Relaxed);
+        let mut last_error = None;
+
+        for offset in 0..self.endpoints.len() {
+            let idx = (start + offset) % self.endpoints.len();
+            let endpoint = &self.endpoints[idx];
+            match JsonRpcClient::request(&endpoint.transport, method, params.clone()).await {
+                Ok(response) => {
+                    if method == "eth_sendRawTransaction" {
+                        endpoint.sent.fetch_add(1, Ordering::Relaxed);
+                    }
+                    if idx != start {
+                        // Only the first request to fail over moves everyone else along
+                        if self.current.compare_exchange(start, idx, Ordering::Relaxed, Ordering::Relaxed).is_ok() {
+                            warn!("Failed over to RPC endpoint {}", endpoint.url);
+                        }
+                    }
+                    return Ok(response);
+                }
+                Err(e) => {
+                    endpoint.errors.fetch_add(1, Ordering::Relaxed);
+                    if !e.is_connection_error() {
+                        return Err(e);
+                    }
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.expect("failover transport has at least one endpoint"))
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{bail, Context, Result};
+use ethers::types::Address;
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+use tracing::{debug, warn};
+
+/// Lock files held for the wallets of a run, so a second run with the same keys can't hand out the
+/// same nonces. Each file holds the owning process id and is removed when the lock is dropped.
+#[derive(Debug)]
+pub struct WalletLock {
+    paths: Vec<PathBuf>,
+}
+
+impl WalletLock {
+    /// Directory the lock files are kept in
+    pub fn dir() -> PathBuf {
+        std::env::temp_dir().join("dump-locks")
+    }
+
+    /// Locks every wallet in `wallets`, failing if another live run holds any of them. A lock left
+    /// behind by a process that no longer exists is taken over. With `force`, existing locks are
+    /// overridden with a warning.
+    pub fn acquire(wallets: impl IntoIterator<Item = Address>, force: bool) -> Result<Self> {
+        let dir = Self::dir();
+        fs::create_dir_all(&dir).with_context(|| format!("failed to create lock directory {}", dir.display()))?;
+        // Locks taken so far are released by the drop if a later wallet turns out to be locked
+        let mut lock = Self { paths: Vec::new() };
+        for wallet in wallets {
+            let path = dir.join(format!("{:?}.lock", wallet));
+            if let Some(pid) = holder(&path) {
+                if force {
+                    warn!("Wallet {:?} is locked by process {}, overriding because of --force", wallet, pid);
+                } else if is_running(pid) {
+                    bail!(
+                        "wallet {:?} is in use by another run (process {}); running both would reuse nonces. \
+                         Pass --force to override, or remove {} if that run is gone",
+                        wallet,
+                        pid,
+                        path.display()
+                    );
+                } else {
+                    debug!(?wallet, pid, "taking over a stale wallet lock");
+                }
+                let _ = fs::remove_file(&path);
+            }
+            write_lock(&path).with_context(|| format!("failed to lock wallet {:?}", wallet))?;
+            lock.paths.push(path);
+        }
+        Ok(lock)
+    }
+}
+
+impl Drop for WalletLock {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            // A forced run may have taken the lock over since
+            if holder(path) != Some(std::process::id()) {
+                continue;
+            }
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to release wallet lock {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Process id recorded in the lock file at `path`, if there is one
+fn holder(path: &Path) -> Option<u32> {
+    let contents = fs::read_to_string(path).ok()?;
+    // An unreadable pid still means the file exists; treat it as held by an unknown, live process
+    Some(contents.trim().parse().unwrap_or(0))
+}
+
+/// Whether process `pid` still exists. Only Linux can tell; elsewhere every lock is assumed live.
+fn is_running(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    pid == 0 || !proc.is_dir() || proc.join(pid.to_string()).exists()
+}
+
+/// Creates the lock file, failing if another run created it first
+fn write_lock(path: &Path) -> std::io::Result<()> {
+    let mut file = match OpenOptions::new().write(true).create_new(true).open(path) {
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            return Err(std::io::Error::new(ErrorKind::AlreadyExists, "another run locked it at the same time"))
+        }
+        result => result?,
+    };
+    write!(file, "{}", std::process::id())
+}
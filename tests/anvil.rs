@@ -0,0 +1,90 @@
+//! End-to-end runs against a local anvil node, covering prepare, send and confirm for real.
+//! Needs `anvil` on the PATH, so they only build with `--features anvil-tests`.
+#![cfg(feature = "anvil-tests")]
+
+mod common;
+
+use ethers::{
+    providers::{Middleware, Provider},
+    signers::LocalWallet,
+    types::{transaction::eip2718::TypedTransaction, TransactionRequest, U256},
+    utils::{id, Anvil},
+};
+use rust_web3_utils::{
+    confirm::RbfPolicy,
+    deploy::{counter_init_code, deploy_contract, COUNTER_METHOD},
+    Benchmarker,
+};
+use std::time::Duration;
+
+#[tokio::test]
+async fn confirmed_batch_mines_every_transaction() {
+    let anvil = Anvil::new().spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone());
+    let mut config = common::config(&anvil.endpoint(), vec![wallet], 20);
+    config.confirm = true;
+
+    let report = Benchmarker::new(config).run().await.unwrap();
+
+    assert_eq!(report.total_sent, 20);
+    assert_eq!(report.total_failed, 0);
+    let confirmation = report.confirmation.expect("confirmation was requested");
+    assert_eq!(confirmation.mined, 20);
+    assert_eq!(confirmation.unconfirmed, 0);
+}
+
+#[tokio::test]
+async fn deployed_counter_counts_every_call() {
+    let anvil = Anvil::new().spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone());
+    let mut config = common::config(&anvil.endpoint(), vec![wallet], 10);
+    config.confirm = true;
+
+    let deployment = deploy_contract(&config, counter_init_code()).await.unwrap();
+    config.recipients = vec![deployment.address];
+    config.data = id(COUNTER_METHOD).to_vec().into();
+    let report = Benchmarker::new(config).run().await.unwrap();
+    assert_eq!(report.confirmation.expect("confirmation was requested").mined, 10);
+
+    let provider = Provider::try_from(anvil.endpoint()).unwrap();
+    let read: TypedTransaction = TransactionRequest::new().to(deployment.address).data(id("number()").to_vec()).into();
+    let number = provider.call(&read, None).await.unwrap();
+    assert_eq!(U256::from_big_endian(&number), U256::from(10));
+}
+
+#[tokio::test]
+async fn confirmations_wait_for_blocks_on_top_of_the_inclusion() {
+    let anvil = Anvil::new().block_time(1u64).spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone());
+    let mut config = common::config(&anvil.endpoint(), vec![wallet], 5);
+    config.confirm = true;
+    config.confirmations = 3;
+
+    let report = Benchmarker::new(config).run().await.unwrap();
+
+    let confirmation = report.confirmation.expect("confirmation was requested");
+    assert_eq!(confirmation.mined, 5);
+    assert_eq!(confirmation.depth, 3);
+    assert_eq!(confirmation.reorged_out, 0);
+    let tip = report.blocks.expect("blocks are always recorded").end;
+    for inclusion in confirmation.inclusions.values() {
+        assert!(tip >= inclusion.block_number.unwrap() + 2);
+    }
+}
+
+#[tokio::test]
+async fn warm_up_sends_are_left_out_of_the_confirmation() {
+    let anvil = Anvil::new().spawn();
+    let wallet = LocalWallet::from(anvil.keys()[0].clone());
+    let mut config = common::config(&anvil.endpoint(), vec![wallet], 5);
+    config.warmup = 3;
+    config.confirm = true;
+    config.rbf = Some(RbfPolicy { after: Duration::from_secs(5), bump_pct: 10, max_attempts: 1, max_gas_price: None });
+
+    let report = Benchmarker::new(config).run().await.unwrap();
+
+    assert_eq!(report.warmup_sent, 3);
+    let confirmation = report.confirmation.expect("confirmation was requested");
+    assert_eq!(confirmation.mined + confirmation.reverted + confirmation.unconfirmed, 5);
+    assert_eq!(confirmation.inclusions.len(), 5);
+}
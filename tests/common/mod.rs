@@ -0,0 +1,67 @@
+//! Shared setup for the integration tests
+
+use ethers::{
+    signers::LocalWallet,
+    types::{Bytes, U256},
+};
+use reqwest::header::HeaderMap;
+use rust_web3_utils::{retry::RetryPolicy, Arrival, Config, GasStrategy, NonceSource, TxType, WalletSelection, Workload};
+use std::time::Duration;
+
+/// A plain self-transfer batch of `count` transactions from `wallets` against `rpc_url`
+pub fn config(rpc_url: &str, wallets: Vec<LocalWallet>, count: u64) -> Config {
+    Config {
+        rpc_urls: vec![rpc_url.to_string()],
+        rpc_headers: HeaderMap::new(),
+        chain_id: None,
+        wallets,
+        nonce_source: NonceSource::Pending,
+        wallet_selection: WalletSelection::RoundRobin,
+        at_block: None,
+        funding: None,
+        nonce_state_file: None,
+        workload: Workload::Count(count),
+        warmup: 0,
+        ramp: None,
+        probe: None,
+        pipeline: false,
+        pipeline_buffer: 1,
+        gas_strategy: GasStrategy::default(),
+        max_gas_price: None,
+        gas_escalation_pct: None,
+        gas_sanity_factor: None,
+        force: false,
+        tx_type: TxType::Auto,
+        eip155: true,
+        recipients: Vec::new(),
+        value: U256::zero(),
+        value_jitter_pct: 0,
+        erc20: None,
+        data: Bytes::default(),
+        access_list: None,
+        gas_limit: None,
+        gas_buffer_pct: 20,
+        max_concurrency: 0,
+        adaptive_concurrency: false,
+        rate: None,
+        arrival: Arrival::Uniform,
+        burst: None,
+        seed: None,
+        retry: RetryPolicy { retries: 0, base_delay: Duration::from_millis(100) },
+        send_timeout: None,
+        batch_size: 1,
+        bundle: None,
+        fill_gaps: false,
+        dry_run: false,
+        confirm: false,
+        progress: false,
+        records: false,
+        rbf: None,
+        poll_interval: Duration::from_millis(100),
+        confirm_timeout: Duration::from_secs(30),
+        confirmations: 1,
+        rebroadcast_reorged: false,
+        cooldown: None,
+        inspect_mempool: false,
+    }
+}
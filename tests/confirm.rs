@@ -0,0 +1,88 @@
+//! The confirmation path against ethers' mock provider, no node required
+
+use ethers::{
+    providers::Provider,
+    types::{Bytes, TransactionReceipt, H256, U64},
+};
+use rust_web3_utils::{
+    confirm::{wait_for_depth, ConfirmationReport, Inclusion},
+    Shutdown,
+};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+fn receipt(tx_hash: H256, block: u64, block_hash: H256) -> TransactionReceipt {
+    TransactionReceipt {
+        transaction_hash: tx_hash,
+        block_number: Some(block.into()),
+        block_hash: Some(block_hash),
+        status: Some(1.into()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn reorged_out_transaction_is_rebroadcast_and_waited_for_again() {
+    let tx_hash = H256::repeat_byte(0xaa);
+    let raw_tx = Bytes::from(vec![0x02, 0x01]);
+    let mut report = ConfirmationReport { mined: 1, ..Default::default() };
+    let inclusion = Inclusion {
+        tx_hash,
+        block_number: Some(10),
+        block_hash: Some(H256::repeat_byte(0x10)),
+        gas_used: None,
+        success: true,
+    };
+    report.inclusions.insert(tx_hash, inclusion);
+
+    // First poll: the receipt is gone, so the transaction is resent and turns up in block 12 of the
+    // new fork. Second poll: block 14 puts it three deep. The mock answers from the back of its queue.
+    let (provider, mock) = Provider::mocked();
+    let reincluded = receipt(tx_hash, 12, H256::repeat_byte(0x12));
+    mock.push(reincluded.clone()).unwrap();
+    mock.push(U64::from(14)).unwrap();
+    mock.push(reincluded).unwrap();
+    mock.push(tx_hash).unwrap();
+    mock.push(Option::<TransactionReceipt>::None).unwrap();
+    mock.push(U64::from(11)).unwrap();
+
+    let raw_txs = HashMap::from([(tx_hash, raw_tx.clone())]);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    wait_for_depth(&provider, &mut report, 3, Some(&raw_txs), Duration::from_millis(1), deadline, &Shutdown::default())
+        .await
+        .unwrap();
+
+    mock.assert_request("eth_blockNumber", ()).unwrap();
+    mock.assert_request("eth_getTransactionReceipt", [tx_hash]).unwrap();
+    mock.assert_request("eth_sendRawTransaction", [raw_tx]).unwrap();
+    assert_eq!((report.mined, report.unconfirmed), (1, 0));
+    assert_eq!((report.reorgs, report.rebroadcast, report.reorged_out), (1, 1, 0));
+    assert_eq!(report.inclusions[&tx_hash].block_number, Some(12));
+}
+
+#[tokio::test]
+async fn stop_ends_the_wait_for_depth_with_the_rest_unconfirmed() {
+    let tx_hash = H256::repeat_byte(0xbb);
+    let block_hash = H256::repeat_byte(0x10);
+    let mut report = ConfirmationReport { mined: 1, ..Default::default() };
+    let inclusion =
+        Inclusion { tx_hash, block_number: Some(10), block_hash: Some(block_hash), gas_used: None, success: true };
+    report.inclusions.insert(tx_hash, inclusion);
+
+    // One poll finds the transaction still one block deep, then the stop cuts the wait short
+    let (provider, mock) = Provider::mocked();
+    mock.push(receipt(tx_hash, 10, block_hash)).unwrap();
+    mock.push(U64::from(10)).unwrap();
+    let shutdown = Shutdown::default();
+    shutdown.trigger();
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let started = Instant::now();
+    wait_for_depth(&provider, &mut report, 3, None, Duration::from_secs(30), deadline, &shutdown).await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert!(report.interrupted);
+    assert_eq!((report.mined, report.unconfirmed), (0, 1));
+}
@@ -0,0 +1,198 @@
+//! The send path against a minimal in-process JSON-RPC node, no real node required
+
+mod common;
+
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{transaction::eip2718::TypedTransaction, Bytes, H256, U256},
+    utils::{keccak256, rlp::Rlp},
+};
+use rust_web3_utils::{Benchmarker, GasStrategy, TxType};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+/// First anvil dev account
+const DEV_KEY: &str = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// Nonce the mock node reports as next for every account
+const NODE_NONCE: u64 = 5;
+
+/// What the mock node has been sent
+#[derive(Default)]
+struct Node {
+    /// Every raw transaction it accepted
+    received: Vec<Bytes>,
+    /// Nonces whose next transaction it rejects
+    reject_once: Vec<u64>,
+}
+
+/// Answers just enough of the JSON-RPC API for a plain batch, refusing the first transaction of each
+/// nonce in `reject_once` and keeping every raw transaction it accepts
+async fn mock_node(reject_once: Vec<u64>) -> (String, Arc<Mutex<Node>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let node = Arc::new(Mutex::new(Node { reject_once, ..Node::default() }));
+    let sink = node.clone();
+    tokio::spawn(async move {
+        loop {
+            let (stream, _) = listener.accept().await.unwrap();
+            let sink = sink.clone();
+            tokio::spawn(async move {
+                let mut stream = BufReader::new(stream);
+                // Connections are kept alive, so serve requests until the client hangs up
+                loop {
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+                            return;
+                        }
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    stream.read_exact(&mut body).await.unwrap();
+                    let request: Value = serde_json::from_slice(&body).unwrap();
+                    let response = match request {
+                        Value::Array(requests) => requests.iter().map(|request| answer(request, &sink)).collect(),
+                        request => answer(&request, &sink),
+                    }
+                    .to_string();
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n",
+                        response.len()
+                    );
+                    let stream = stream.get_mut();
+                    stream.write_all(head.as_bytes()).await.unwrap();
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    });
+    (url, node)
+}
+
+fn answer(request: &Value, node: &Mutex<Node>) -> Value {
+    let params = &request["params"];
+    let result = match request["method"].as_str().unwrap() {
+        "eth_chainId" => json!("0x7a69"),
+        "eth_blockNumber" => json!("0x64"),
+        "eth_getTransactionCount" => json!(format!("{:#x}", NODE_NONCE)),
+        "eth_getBalance" => json!("0xd3c21bcecceda1000000"),
+        "eth_gasPrice" => json!("0x3b9aca00"),
+        "eth_getBlockByNumber" => json!({
+            "number": "0x64",
+            "hash": H256::repeat_byte(0x64),
+            "parentHash": H256::repeat_byte(0x63),
+            "timestamp": "0x6553f100",
+            "gasLimit": "0x1c9c380",
+            "gasUsed": "0x0",
+            "transactions": [],
+            "uncles": [],
+        }),
+        "eth_sendRawTransaction" => {
+            let raw_tx: Bytes = serde_json::from_value(params[0].clone()).unwrap();
+            let mut node = node.lock().unwrap();
+            let nonce = nonce(&raw_tx).as_u64();
+            if let Some(pos) = node.reject_once.iter().position(|rejected| *rejected == nonce) {
+                node.reject_once.remove(pos);
+                let error = json!({ "code": -32000, "message": "internal error" });
+                return json!({ "jsonrpc": "2.0", "id": request["id"], "error": error });
+            }
+            let hash = H256::from(keccak256(&raw_tx));
+            node.received.push(raw_tx);
+            json!(hash)
+        }
+        method => {
+            let error = json!({ "code": -32601, "message": format!("method not found: {}", method) });
+            return json!({ "jsonrpc": "2.0", "id": request["id"], "error": error });
+        }
+    };
+    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+}
+
+/// Nonce of a signed raw transaction
+fn nonce(raw_tx: &Bytes) -> U256 {
+    let (tx, _) = TypedTransaction::decode_signed(&Rlp::new(raw_tx)).unwrap();
+    *tx.nonce().unwrap()
+}
+
+fn batch_config(url: &str, wallet: LocalWallet, count: u64) -> rust_web3_utils::Config {
+    let mut config = common::config(url, vec![wallet], count);
+    config.tx_type = TxType::Legacy;
+    config.gas_strategy = GasStrategy::Fixed(U256::from(1_000_000_000u64));
+    config.gas_limit = Some(21000);
+    config
+}
+
+#[tokio::test]
+async fn small_batch_submits_with_increasing_nonces() {
+    let (url, node) = mock_node(Vec::new()).await;
+    let wallet: LocalWallet = DEV_KEY.parse::<LocalWallet>().unwrap().with_chain_id(31337u64);
+    let report = Benchmarker::new(batch_config(&url, wallet.clone(), 3)).run().await.unwrap();
+    assert_eq!(report.hashes.len(), 3);
+
+    // Sends run concurrently, so they may reach the node in any order
+    let received = node.lock().unwrap().received.clone();
+    let mut nonces: Vec<U256> = received
+        .iter()
+        .map(|raw_tx| {
+            let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw_tx)).unwrap();
+            assert_eq!(signature.recover(tx.sighash()).unwrap(), wallet.address());
+            *tx.nonce().unwrap()
+        })
+        .collect();
+    nonces.sort();
+    let expected: Vec<U256> = (NODE_NONCE..NODE_NONCE + 3).map(U256::from).collect();
+    assert_eq!(nonces, expected);
+}
+
+#[tokio::test]
+async fn next_run_reuses_the_nonce_of_a_failed_last_send() {
+    // The last nonce of the first run never reaches the node
+    let (url, node) = mock_node(vec![NODE_NONCE + 2]).await;
+    let wallet: LocalWallet = DEV_KEY.parse::<LocalWallet>().unwrap().with_chain_id(31337u64);
+    let benchmarker = Benchmarker::new(batch_config(&url, wallet, 3));
+
+    let first = benchmarker.run().await.unwrap();
+    assert_eq!((first.total_sent, first.total_failed), (2, 1));
+    assert!(first.nonce_gaps.is_empty());
+    let second = benchmarker.run().await.unwrap();
+    assert_eq!(second.total_sent, 3);
+
+    // Nothing is left stuck behind the nonce that failed
+    let mut nonces: Vec<U256> = node.lock().unwrap().received.iter().map(nonce).collect();
+    nonces.sort();
+    let expected: Vec<U256> = (NODE_NONCE..NODE_NONCE + 5).map(U256::from).collect();
+    assert_eq!(nonces, expected);
+}
+
+#[tokio::test]
+async fn filled_gap_counts_as_sent() {
+    // A nonce in the middle of the batch fails, leaving the one after it stuck
+    let (url, node) = mock_node(vec![NODE_NONCE + 1]).await;
+    let wallet: LocalWallet = DEV_KEY.parse::<LocalWallet>().unwrap().with_chain_id(31337u64);
+    let mut config = batch_config(&url, wallet, 3);
+    config.fill_gaps = true;
+    config.records = true;
+    let report = Benchmarker::new(config).run().await.unwrap();
+
+    assert_eq!(report.gaps_filled, 1);
+    assert_eq!((report.total_sent, report.total_failed), (3, 0));
+    assert_eq!(report.hashes.len(), 3);
+    assert!(report.failures.is_empty());
+    assert_eq!(report.records.len(), 3);
+    assert!(report.records.iter().all(|record| record.hash.is_some() && record.error.is_none()));
+    assert_eq!(node.lock().unwrap().received.len(), 3);
+}